@@ -0,0 +1,342 @@
+/*! The `#[supabase_test]` attribute of `supabase-testcontainers-modules`.
+
+Use it through the `macros` feature of `supabase-testcontainers-modules`, which
+re-exports it and provides the runtime it expands to; this crate is not meant to be
+used on its own.
+
+# Architecture
+
+The annotated `async fn` is kept as an inner function of a `#[tokio::test]` of the same
+name. The test calls the runtime's `setup` with the attribute's arguments, returns early
+if the test is skipped, and otherwise awaits the inner function with the stack. All paths
+go through `::supabase_testcontainers_modules::__private`, so tests need no direct
+dependency on tokio.
+*/
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::meta::ParseNestedMeta;
+use syn::parse::Parser;
+use syn::spanned::Spanned;
+use syn::{Expr, ExprArray, FnArg, Ident, ItemFn, LitStr, ReturnType, Type};
+
+/// Runs an async test against a ready Supabase stack
+///
+/// ```ignore
+/// #[supabase_test(services = [auth, postgrest], migrations = "supabase/migrations")]
+/// async fn lists_todos(stack: &TestStack) -> anyhow::Result<()> {
+///     // ...
+///     Ok(())
+/// }
+/// ```
+///
+/// - `services`: `auth`, `postgrest` (or `rest`) and `storage`; PostgreSQL always runs
+/// - `migrations`: migrations directory, relative to the crate root
+/// - `isolation`: `reset` (default), `database` or `none`
+///
+/// Every annotated test in a binary shares one stack, so all of them must name the same
+/// services and migrations. If Docker is unavailable the test is skipped with a message,
+/// or fails if `SUPABASE_TC_REQUIRE_DOCKER` is set.
+#[proc_macro_attribute]
+pub fn supabase_test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand(attr.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Arguments of the attribute
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Args {
+    /// Canonical service names, in the order given
+    services: Vec<String>,
+    /// Migrations directory as written
+    migrations: Option<String>,
+    /// Isolation variant name
+    isolation: Option<String>,
+}
+
+impl Args {
+    /// Parses one `name = value` argument
+    fn parse_meta(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("services") {
+            let array: ExprArray = meta.value()?.parse()?;
+            for expr in array.elems {
+                let name = match &expr {
+                    Expr::Path(path) => path.path.get_ident().map(Ident::to_string),
+                    _ => None,
+                };
+                let service = match name.as_deref() {
+                    Some("auth") => "auth",
+                    Some("postgrest" | "rest") => "postgrest",
+                    Some("storage") => "storage",
+                    _ => {
+                        return Err(syn::Error::new(
+                            expr.span(),
+                            "unknown service; expected auth, postgrest or storage",
+                        ))
+                    }
+                };
+                if self.services.iter().any(|s| s == service) {
+                    return Err(syn::Error::new(expr.span(), "duplicate service"));
+                }
+                self.services.push(service.to_string());
+            }
+            Ok(())
+        } else if meta.path.is_ident("migrations") {
+            let dir: LitStr = meta.value()?.parse()?;
+            self.migrations = Some(dir.value());
+            Ok(())
+        } else if meta.path.is_ident("isolation") {
+            let isolation: Ident = meta.value()?.parse()?;
+            self.isolation = Some(match isolation.to_string().as_str() {
+                "reset" => "Reset".to_string(),
+                "database" => "Database".to_string(),
+                "none" => "None".to_string(),
+                _ => {
+                    return Err(syn::Error::new(
+                        isolation.span(),
+                        "unknown isolation; expected reset, database or none",
+                    ))
+                }
+            });
+            Ok(())
+        } else {
+            Err(meta.error("unknown argument; expected services, migrations or isolation"))
+        }
+    }
+}
+
+/// How the test function takes the stack
+enum Param {
+    /// It takes no parameter
+    None,
+    /// It takes `&TestStack`
+    Ref,
+    /// It takes `TestStack`
+    Owned,
+}
+
+/// Expands the attribute on `item`
+fn expand(attr: TokenStream2, item: TokenStream2) -> syn::Result<TokenStream2> {
+    let mut args = Args::default();
+    syn::meta::parser(|meta| args.parse_meta(meta)).parse2(attr)?;
+    let function: ItemFn = syn::parse2(item)?;
+
+    let sig = &function.sig;
+    if sig.asyncness.is_none() {
+        return Err(syn::Error::new(
+            sig.fn_token.span(),
+            "#[supabase_test] functions must be async",
+        ));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            sig.generics.span(),
+            "#[supabase_test] functions cannot be generic",
+        ));
+    }
+    let param = match sig.inputs.len() {
+        0 => Param::None,
+        1 => match &sig.inputs[0] {
+            FnArg::Typed(arg) if matches!(*arg.ty, Type::Reference(_)) => Param::Ref,
+            FnArg::Typed(_) => Param::Owned,
+            FnArg::Receiver(receiver) => {
+                return Err(syn::Error::new(
+                    receiver.span(),
+                    "#[supabase_test] functions cannot take self",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                sig.inputs.span(),
+                "#[supabase_test] functions take at most one parameter, the stack",
+            ))
+        }
+    };
+
+    let private = quote!(::supabase_testcontainers_modules::__private);
+    let name = &sig.ident;
+    let output = match &sig.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ty) => quote!(#ty),
+    };
+    // Sorted so that tests naming the same services in any order share the stack
+    let mut services = args.services;
+    services.sort();
+    let migrations = match &args.migrations {
+        Some(dir) if dir.starts_with('/') => quote!(::core::option::Option::Some(#dir)),
+        Some(dir) => quote!(::core::option::Option::Some(::core::concat!(
+            ::core::env!("CARGO_MANIFEST_DIR"),
+            "/",
+            #dir
+        ))),
+        None => quote!(::core::option::Option::None),
+    };
+    let isolation = Ident::new(
+        args.isolation.as_deref().unwrap_or("Reset"),
+        Span::call_site(),
+    );
+    let call = match param {
+        // The stack is kept until the test ends, as it holds the test's isolation
+        Param::None => quote! {
+            let output = #name().await;
+            ::core::mem::drop(stack);
+            output
+        },
+        Param::Ref => quote!(#name(&stack).await),
+        Param::Owned => quote!(#name(stack).await),
+    };
+    let attrs = &function.attrs;
+    let vis = &function.vis;
+    let block = &function.block;
+    let inputs = &sig.inputs;
+    let ret = &sig.output;
+    let tokio_crate = LitStr::new(
+        "::supabase_testcontainers_modules::__private::tokio",
+        Span::call_site(),
+    );
+
+    Ok(quote! {
+        #(#attrs)*
+        #[::supabase_testcontainers_modules::__private::tokio::test(crate = #tokio_crate)]
+        #vis async fn #name() #ret {
+            async fn #name(#inputs) #ret #block
+
+            let config = #private::TestConfig {
+                test: ::core::concat!(::core::module_path!(), "::", ::core::stringify!(#name)),
+                services: &[#(#services),*],
+                migrations: #migrations,
+                isolation: #private::TestIsolation::#isolation,
+            };
+            let ::core::option::Option::Some(stack) = #private::setup(config).await else {
+                return <#output as #private::TestReturn>::skipped();
+            };
+            #call
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(attr: TokenStream2, item: TokenStream2) -> String {
+        expand(attr, item).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let mut args = Args::default();
+        syn::meta::parser(|meta| args.parse_meta(meta))
+            .parse2(quote!(
+                services = [auth, rest, storage],
+                migrations = "supabase/migrations",
+                isolation = database
+            ))
+            .unwrap();
+
+        assert_eq!(
+            args,
+            Args {
+                services: vec!["auth".into(), "postgrest".into(), "storage".into()],
+                migrations: Some("supabase/migrations".into()),
+                isolation: Some("Database".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_expand() {
+        let expanded = expand(
+            quote!(services = [postgrest], migrations = "/abs/migrations"),
+            quote!(
+                async fn reads(stack: &TestStack) -> anyhow::Result<()> {
+                    Ok(())
+                }
+            ),
+        )
+        .unwrap()
+        .to_string();
+
+        assert!(expanded.contains("async fn reads () -> anyhow :: Result < () >"));
+        assert!(expanded.contains("services : & [\"postgrest\"]"));
+        assert!(expanded.contains("Some (\"/abs/migrations\")"));
+        assert!(expanded.contains("TestIsolation :: Reset"));
+        assert!(expanded.contains("reads (& stack) . await"));
+    }
+
+    #[test]
+    fn test_relative_migrations_use_manifest_dir() {
+        let expanded = expand(
+            quote!(migrations = "supabase/migrations"),
+            quote!(
+                async fn migrates(stack: TestStack) {}
+            ),
+        )
+        .unwrap()
+        .to_string();
+
+        assert!(expanded.contains("CARGO_MANIFEST_DIR"));
+        assert!(expanded.contains("migrates (stack) . await"));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            error(
+                quote!(),
+                quote!(
+                    fn sync() {}
+                )
+            ),
+            "#[supabase_test] functions must be async"
+        );
+        assert_eq!(
+            error(
+                quote!(),
+                quote!(
+                    async fn two(a: &TestStack, b: u32) {}
+                )
+            ),
+            "#[supabase_test] functions take at most one parameter, the stack"
+        );
+        assert_eq!(
+            error(
+                quote!(services = [realtime]),
+                quote!(
+                    async fn f() {}
+                )
+            ),
+            "unknown service; expected auth, postgrest or storage"
+        );
+        assert_eq!(
+            error(
+                quote!(services = [auth, auth]),
+                quote!(
+                    async fn f() {}
+                )
+            ),
+            "duplicate service"
+        );
+        assert_eq!(
+            error(
+                quote!(isolation = schemas),
+                quote!(
+                    async fn f() {}
+                )
+            ),
+            "unknown isolation; expected reset, database or none"
+        );
+        assert_eq!(
+            error(
+                quote!(network = "x"),
+                quote!(
+                    async fn f() {}
+                )
+            ),
+            "unknown argument; expected services, migrations or isolation"
+        );
+    }
+}
@@ -10,6 +10,7 @@ powered by Logflare - a centralized logging and analytics platform.
 - Single-tenant mode for self-hosted deployments
 - Configurable access tokens for API authentication
 - Health endpoint for readiness checks
+- HTTP client for source management, log ingestion and endpoint queries
 
 # Architecture
 
@@ -63,22 +64,42 @@ The [`Analytics`] struct provides builder methods for common configuration optio
 - [`Analytics::with_log_level`] - Log verbosity (error, warning, info)
 
 See the struct documentation for the full list of options.
+
+# Sources, Ingestion and Queries
+
+[`AnalyticsClient`] wraps the Logflare HTTP API: [`AnalyticsClient::create_source`]
+manages sources, [`AnalyticsClient::ingest`] and [`AnalyticsClient::ingest_batch`] post
+events to `/api/logs`, and [`AnalyticsClient::create_endpoint`] and
+[`AnalyticsClient::query_sql`] run LQL/SQL queries. Ingested events are buffered before
+they become queryable, so use [`AnalyticsClient::wait_for_results`] to poll.
 */
 
 use std::borrow::Cow;
 use std::collections::BTreeMap;
+use std::time::Duration;
 
+use anyhow::{bail, Context};
+use serde_json::{json, Value};
 use testcontainers_modules::testcontainers::core::{
     ContainerPort, ContainerState, ExecCommand, WaitFor,
 };
-use testcontainers_modules::testcontainers::{Image, TestcontainersError};
+use testcontainers_modules::testcontainers::{ContainerAsync, Image, TestcontainersError};
+
+use crate::overrides::{image_name, image_tag, pin_digest, with_registry};
+use crate::{ReadyCheck, SupabaseVersion};
 
 /// Default image name for Supabase Analytics (Logflare)
 const NAME: &str = "supabase/logflare";
 /// Default image tag version
-const TAG: &str = "1.26.13";
+const TAG: &str = crate::consts::ANALYTICS_TAG;
 /// Default port for Supabase Analytics API
 pub const ANALYTICS_PORT: u16 = 4000;
+/// Default time to wait for ingested events to become queryable
+pub const ANALYTICS_QUERY_TIMEOUT: Duration = Duration::from_secs(60);
+/// Interval between polls while waiting on the Logflare API
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Endpoint seeded in Supabase mode that runs arbitrary SQL passed as `sql`
+const LOGS_ALL_ENDPOINT: &str = "logs.all";
 
 /// Supabase Analytics container for integration testing.
 ///
@@ -111,8 +132,12 @@ pub const ANALYTICS_PORT: u16 = 4000;
 pub struct Analytics {
     /// Environment variables to be passed to the container
     env_vars: BTreeMap<String, String>,
+    /// Docker image name
+    name: String,
     /// Docker image tag version
     tag: String,
+    /// How the container is considered ready
+    ready_check: ReadyCheck,
 }
 
 impl Analytics {
@@ -282,6 +307,37 @@ impl Analytics {
         self
     }
 
+    /// Sets the Docker image name, e.g. a fork or a copy in a private registry
+    ///
+    /// `SUPABASE_TC_REGISTRY` still prefixes names without a registry
+    pub fn with_image_name(mut self, name: impl Into<String>) -> Self {
+        self.name = with_registry(&name.into());
+        self
+    }
+
+    /// Pins the image to a digest such as `sha256:4c1e...`
+    ///
+    /// Docker pulls the digest and ignores the tag; [`Analytics::with_tag`] removes the pin
+    pub fn with_digest(mut self, digest: impl AsRef<str>) -> Self {
+        self.tag = pin_digest(&self.tag, digest.as_ref());
+        self
+    }
+
+    /// Sets the tag from a [`SupabaseVersion`]
+    pub fn with_version(self, version: &SupabaseVersion) -> Self {
+        self.with_tag(version.analytics())
+    }
+
+    /// Sets how the container is considered ready
+    ///
+    /// Default is [`ReadyCheck::Http`] on `/health`, which Logflare answers only after
+    /// its migrations have completed. [`ReadyCheck::Log`] waits for the startup log line
+    /// instead.
+    pub fn with_ready_check(mut self, check: ReadyCheck) -> Self {
+        self.ready_check = check;
+        self
+    }
+
     /// Adds a custom environment variable
     ///
     /// Use this for Logflare configuration options not covered by other methods.
@@ -291,6 +347,410 @@ impl Analytics {
     }
 }
 
+/// Query language of a Logflare endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryLanguage {
+    /// Logflare Query Language
+    Lql,
+    /// PostgreSQL SQL, for the Postgres backend
+    PgSql,
+    /// BigQuery SQL
+    BqSql,
+}
+
+impl QueryLanguage {
+    /// Returns the value Logflare expects in the endpoint's `language` field
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QueryLanguage::Lql => "lql",
+            QueryLanguage::PgSql => "pg_sql",
+            QueryLanguage::BqSql => "bq_sql",
+        }
+    }
+}
+
+impl std::fmt::Display for QueryLanguage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A Logflare source events are ingested into
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalyticsSource {
+    /// Numeric source ID
+    pub id: i64,
+    /// Source UUID, accepted as `?source=` when ingesting
+    pub token: String,
+    /// Source name, accepted as `?source_name=` when ingesting
+    pub name: String,
+}
+
+/// A Logflare endpoint: a saved query callable over HTTP
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalyticsEndpoint {
+    /// Numeric endpoint ID
+    pub id: i64,
+    /// Endpoint UUID used in `/api/endpoints/query/{token}`
+    pub token: String,
+    /// Endpoint name, also accepted in place of the token
+    pub name: String,
+}
+
+/// HTTP client for the Logflare management, ingestion and endpoints APIs
+///
+/// Requests authenticate with the `x-api-key` header. Source and endpoint
+/// management needs the private access token; ingestion and queries also accept
+/// the public one.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use serde_json::json;
+/// use supabase_testcontainers_modules::{
+///     Analytics, AnalyticsClient, QueryLanguage, ANALYTICS_QUERY_TIMEOUT,
+/// };
+/// use testcontainers::ContainerAsync;
+///
+/// async fn verify(analytics: &ContainerAsync<Analytics>) -> anyhow::Result<()> {
+///     let client = AnalyticsClient::from_container(analytics).await?;
+///
+///     client.create_source("app.logs").await?;
+///     client
+///         .ingest("app.logs", json!({ "event_message": "user signed in" }))
+///         .await?;
+///
+///     client
+///         .create_endpoint(
+///             "app.recent",
+///             "select event_message from `app.logs`",
+///             QueryLanguage::BqSql,
+///         )
+///         .await?;
+///     let rows = client
+///         .wait_for_results("app.recent", &[], 1, ANALYTICS_QUERY_TIMEOUT)
+///         .await?;
+///     assert_eq!(rows[0]["event_message"], "user signed in");
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AnalyticsClient {
+    /// Underlying HTTP client
+    http: reqwest::Client,
+    /// Base URL of the Logflare API, without a trailing slash
+    base_url: String,
+    /// Access token sent as `x-api-key`
+    access_token: String,
+}
+
+impl AnalyticsClient {
+    /// Creates a client for the Logflare API at `base_url`
+    pub fn new(base_url: impl Into<String>, access_token: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            access_token: access_token.into(),
+        }
+    }
+
+    /// Creates a client for a running [`Analytics`] container
+    ///
+    /// Uses the container's private access token, falling back to the public one.
+    ///
+    /// # Errors
+    /// Returns an error if neither token is configured or the port is not mapped.
+    pub async fn from_container(container: &ContainerAsync<Analytics>) -> anyhow::Result<Self> {
+        let env_vars = &container.image().env_vars;
+        let access_token = env_vars
+            .get("LOGFLARE_PRIVATE_ACCESS_TOKEN")
+            .or_else(|| env_vars.get("LOGFLARE_PUBLIC_ACCESS_TOKEN"))
+            .context("Analytics container has no access token configured")?;
+
+        let host = container.get_host().await?;
+        let port = container.get_host_port_ipv4(ANALYTICS_PORT).await?;
+        Ok(Self::new(format!("http://{}:{}", host, port), access_token))
+    }
+
+    /// Returns the base URL of the Logflare API
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Returns whether `/health` reports the server as healthy
+    pub async fn is_healthy(&self) -> anyhow::Result<bool> {
+        let url = format!("{}/health", self.base_url);
+        let response = self
+            .http
+            .get(&url)
+            .send()
+            .await
+            .with_context(|| format!("failed to reach Logflare at {}", url))?;
+        Ok(response.status().is_success())
+    }
+
+    /// Waits until `/health` reports the server as healthy
+    ///
+    /// # Errors
+    /// Returns an error if the server is not healthy within `timeout`.
+    pub async fn wait_for_health(&self, timeout: Duration) -> anyhow::Result<()> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            if let Ok(true) = self.is_healthy().await {
+                return Ok(());
+            }
+            if tokio::time::Instant::now() >= deadline {
+                bail!(
+                    "Logflare at {} not healthy after {:?}",
+                    self.base_url,
+                    timeout
+                );
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Creates a source named `name`
+    pub async fn create_source(&self, name: &str) -> anyhow::Result<AnalyticsSource> {
+        let body = self
+            .send(
+                self.http
+                    .post(format!("{}/api/sources", self.base_url))
+                    .json(&json!({ "name": name })),
+            )
+            .await
+            .with_context(|| format!("failed to create source '{}'", name))?;
+        parse_source(&body)
+    }
+
+    /// Lists all sources
+    pub async fn sources(&self) -> anyhow::Result<Vec<AnalyticsSource>> {
+        let body = self
+            .send(self.http.get(format!("{}/api/sources", self.base_url)))
+            .await
+            .context("failed to list sources")?;
+        body.as_array()
+            .context("Logflare returned a non-array source list")?
+            .iter()
+            .map(parse_source)
+            .collect()
+    }
+
+    /// Returns the source named `name`, if it exists
+    pub async fn source(&self, name: &str) -> anyhow::Result<Option<AnalyticsSource>> {
+        Ok(self
+            .sources()
+            .await?
+            .into_iter()
+            .find(|source| source.name == name))
+    }
+
+    /// Deletes the source with the given UUID
+    pub async fn delete_source(&self, token: &str) -> anyhow::Result<()> {
+        self.send(
+            self.http
+                .delete(format!("{}/api/sources/{}", self.base_url, token)),
+        )
+        .await
+        .with_context(|| format!("failed to delete source '{}'", token))?;
+        Ok(())
+    }
+
+    /// Ingests one event into the source named `source_name`
+    ///
+    /// Events need an `event_message` (or `message`) field; any other fields are
+    /// stored as event metadata.
+    pub async fn ingest(&self, source_name: &str, event: Value) -> anyhow::Result<()> {
+        self.ingest_body(source_name, event).await
+    }
+
+    /// Ingests several events into the source named `source_name` in one request
+    pub async fn ingest_batch(&self, source_name: &str, events: Vec<Value>) -> anyhow::Result<()> {
+        self.ingest_body(source_name, json!({ "batch": events }))
+            .await
+    }
+
+    /// Creates an endpoint running `query` in `language`
+    ///
+    /// Query parameters such as `@user_id` in SQL become endpoint parameters.
+    pub async fn create_endpoint(
+        &self,
+        name: &str,
+        query: &str,
+        language: QueryLanguage,
+    ) -> anyhow::Result<AnalyticsEndpoint> {
+        let body = self
+            .send(
+                self.http
+                    .post(format!("{}/api/endpoints", self.base_url))
+                    .json(&json!({
+                        "name": name,
+                        "query": query,
+                        "language": language.as_str(),
+                        "cache_duration_seconds": 0,
+                        "proactive_requerying_seconds": 0,
+                    })),
+            )
+            .await
+            .with_context(|| format!("failed to create endpoint '{}'", name))?;
+        parse_endpoint(&body)
+    }
+
+    /// Runs the endpoint identified by `name_or_token` and returns its rows
+    pub async fn query_endpoint(
+        &self,
+        name_or_token: &str,
+        params: &[(&str, &str)],
+    ) -> anyhow::Result<Vec<Value>> {
+        let body = self
+            .send(
+                self.http
+                    .get(format!(
+                        "{}/api/endpoints/query/{}",
+                        self.base_url, name_or_token
+                    ))
+                    .query(params),
+            )
+            .await
+            .with_context(|| format!("failed to query endpoint '{}'", name_or_token))?;
+        parse_result(body)
+    }
+
+    /// Runs an ad-hoc SQL query through the `logs.all` endpoint
+    ///
+    /// `logs.all` is seeded when Supabase mode is enabled (the default) and is what
+    /// Supabase Studio's log explorer queries.
+    pub async fn query_sql(&self, sql: &str) -> anyhow::Result<Vec<Value>> {
+        self.query_endpoint(LOGS_ALL_ENDPOINT, &[("sql", sql)])
+            .await
+    }
+
+    /// Polls an endpoint until it returns at least `min_rows` rows
+    ///
+    /// Ingested events are buffered before they are written, so queries lag behind
+    /// ingestion; errors while polling are retried until `timeout`.
+    ///
+    /// # Errors
+    /// Returns the last error or row count if `min_rows` is not reached in time.
+    pub async fn wait_for_results(
+        &self,
+        name_or_token: &str,
+        params: &[(&str, &str)],
+        min_rows: usize,
+        timeout: Duration,
+    ) -> anyhow::Result<Vec<Value>> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let last = match self.query_endpoint(name_or_token, params).await {
+                Ok(rows) if rows.len() >= min_rows => return Ok(rows),
+                Ok(rows) => format!("{} of {} rows", rows.len(), min_rows),
+                Err(e) => format!("{:#}", e),
+            };
+            if tokio::time::Instant::now() >= deadline {
+                bail!(
+                    "endpoint '{}' did not return {} rows within {:?} (last: {})",
+                    name_or_token,
+                    min_rows,
+                    timeout,
+                    last
+                );
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Polls an ad-hoc SQL query until it returns at least `min_rows` rows
+    pub async fn wait_for_sql(
+        &self,
+        sql: &str,
+        min_rows: usize,
+        timeout: Duration,
+    ) -> anyhow::Result<Vec<Value>> {
+        self.wait_for_results(LOGS_ALL_ENDPOINT, &[("sql", sql)], min_rows, timeout)
+            .await
+    }
+
+    /// Posts an ingestion body to `/api/logs`
+    async fn ingest_body(&self, source_name: &str, body: Value) -> anyhow::Result<()> {
+        self.send(
+            self.http
+                .post(format!("{}/api/logs", self.base_url))
+                .query(&[("source_name", source_name)])
+                .json(&body),
+        )
+        .await
+        .with_context(|| format!("failed to ingest into source '{}'", source_name))?;
+        Ok(())
+    }
+
+    /// Sends an authenticated request and returns the JSON body (null when empty)
+    async fn send(&self, request: reqwest::RequestBuilder) -> anyhow::Result<Value> {
+        let response = request
+            .header("x-api-key", &self.access_token)
+            .send()
+            .await
+            .with_context(|| format!("failed to reach Logflare at {}", self.base_url))?;
+
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .context("failed to read Logflare response")?;
+        if !status.is_success() {
+            bail!("Logflare returned {}: {}", status, text);
+        }
+        if text.trim().is_empty() {
+            return Ok(Value::Null);
+        }
+        serde_json::from_str(&text).context("Logflare returned invalid JSON")
+    }
+}
+
+/// Parses a source object from the management API
+fn parse_source(value: &Value) -> anyhow::Result<AnalyticsSource> {
+    Ok(AnalyticsSource {
+        id: value["id"].as_i64().context("source has no id")?,
+        token: value["token"]
+            .as_str()
+            .context("source has no token")?
+            .to_string(),
+        name: value["name"]
+            .as_str()
+            .context("source has no name")?
+            .to_string(),
+    })
+}
+
+/// Parses an endpoint object from the management API
+fn parse_endpoint(value: &Value) -> anyhow::Result<AnalyticsEndpoint> {
+    Ok(AnalyticsEndpoint {
+        id: value["id"].as_i64().context("endpoint has no id")?,
+        token: value["token"]
+            .as_str()
+            .context("endpoint has no token")?
+            .to_string(),
+        name: value["name"]
+            .as_str()
+            .context("endpoint has no name")?
+            .to_string(),
+    })
+}
+
+/// Extracts the rows of an endpoint query response
+fn parse_result(body: Value) -> anyhow::Result<Vec<Value>> {
+    if let Some(error) = body.get("error").filter(|error| !error.is_null()) {
+        bail!("endpoint query failed: {}", error);
+    }
+    match body {
+        Value::Object(mut object) => match object.remove("result") {
+            Some(Value::Array(rows)) => Ok(rows),
+            Some(Value::Null) | None => Ok(Vec::new()),
+            Some(other) => bail!("endpoint returned a non-array result: {}", other),
+        },
+        other => bail!("endpoint returned an unexpected response: {}", other),
+    }
+}
+
 impl Default for Analytics {
     fn default() -> Self {
         let mut env_vars = BTreeMap::new();
@@ -318,14 +778,16 @@ impl Default for Analytics {
 
         Self {
             env_vars,
-            tag: TAG.to_string(),
+            name: image_name("ANALYTICS", NAME),
+            tag: image_tag("ANALYTICS", TAG),
+            ready_check: ReadyCheck::http("/health"),
         }
     }
 }
 
 impl Image for Analytics {
     fn name(&self) -> &str {
-        NAME
+        &self.name
     }
 
     fn tag(&self) -> &str {
@@ -333,8 +795,10 @@ impl Image for Analytics {
     }
 
     fn ready_conditions(&self) -> Vec<WaitFor> {
-        // Logflare/Phoenix logs startup message when server is ready
-        vec![WaitFor::message_on_stdout("Starting migration")]
+        vec![self.ready_check.wait_for(
+            ANALYTICS_PORT,
+            WaitFor::message_on_stdout("Starting migration"),
+        )]
     }
 
     fn expose_ports(&self) -> &[ContainerPort] {
@@ -409,6 +873,35 @@ mod tests {
         assert_eq!(analytics.tag(), "1.26.13");
     }
 
+    #[test]
+    fn test_default_tag_matches_tested_version() {
+        assert_eq!(
+            Analytics::default().tag(),
+            SupabaseVersion::tested().analytics()
+        );
+    }
+
+    #[test]
+    fn test_with_version_sets_tag() {
+        let version = SupabaseVersion::tested().with_analytics("1.20.0");
+        assert_eq!(Analytics::default().with_version(&version).tag(), "1.20.0");
+    }
+
+    #[test]
+    fn test_with_image_name() {
+        let analytics = Analytics::default().with_image_name("ghcr.io/acme/analytics");
+        assert_eq!(analytics.name(), "ghcr.io/acme/analytics");
+    }
+
+    #[test]
+    fn test_with_digest_pins_tag() {
+        let analytics = Analytics::default()
+            .with_tag("v1")
+            .with_digest("sha256:abc");
+        assert_eq!(analytics.tag(), "v1@sha256:abc");
+        assert_eq!(analytics.with_tag("v2").tag(), "v2");
+    }
+
     #[test]
     fn test_analytics_port_constant() {
         assert_eq!(ANALYTICS_PORT, 4000);
@@ -646,5 +1139,74 @@ mod tests {
         let analytics = Analytics::default();
         let conditions = analytics.ready_conditions();
         assert_eq!(conditions.len(), 1);
+        assert!(matches!(conditions[0], WaitFor::Http(_)));
+    }
+
+    #[test]
+    fn test_with_ready_check_log() {
+        let analytics = Analytics::default().with_ready_check(ReadyCheck::Log);
+        let conditions = analytics.ready_conditions();
+        assert_eq!(conditions.len(), 1);
+        assert!(matches!(conditions[0], WaitFor::Log(_)));
+    }
+
+    #[test]
+    fn test_query_language_as_str() {
+        assert_eq!(QueryLanguage::Lql.as_str(), "lql");
+        assert_eq!(QueryLanguage::PgSql.as_str(), "pg_sql");
+        assert_eq!(QueryLanguage::BqSql.to_string(), "bq_sql");
+    }
+
+    #[test]
+    fn test_client_trims_trailing_slash() {
+        let client = AnalyticsClient::new("http://localhost:4000/", "token");
+        assert_eq!(client.base_url(), "http://localhost:4000");
+    }
+
+    #[test]
+    fn test_parse_source() {
+        let source = parse_source(&json!({
+            "id": 7,
+            "token": "3f1b1c2e-0000-4000-8000-000000000000",
+            "name": "app.logs",
+            "favorite": false
+        }))
+        .unwrap();
+
+        assert_eq!(
+            source,
+            AnalyticsSource {
+                id: 7,
+                token: "3f1b1c2e-0000-4000-8000-000000000000".to_string(),
+                name: "app.logs".to_string(),
+            }
+        );
+        assert!(parse_source(&json!({ "name": "app.logs" })).is_err());
+    }
+
+    #[test]
+    fn test_parse_endpoint() {
+        let endpoint = parse_endpoint(&json!({
+            "id": 3,
+            "token": "a8d6c5b4-0000-4000-8000-000000000000",
+            "name": "app.recent",
+            "query": "select 1"
+        }))
+        .unwrap();
+
+        assert_eq!(endpoint.id, 3);
+        assert_eq!(endpoint.name, "app.recent");
+        assert!(parse_endpoint(&json!({})).is_err());
+    }
+
+    #[test]
+    fn test_parse_result() {
+        let rows = parse_result(json!({ "result": [{ "count": 2 }] })).unwrap();
+        assert_eq!(rows, vec![json!({ "count": 2 })]);
+
+        assert!(parse_result(json!({ "result": null })).unwrap().is_empty());
+        assert!(parse_result(json!({ "error": { "message": "bad query" } })).is_err());
+        assert!(parse_result(json!({ "result": 1 })).is_err());
+        assert!(parse_result(json!([])).is_err());
     }
 }
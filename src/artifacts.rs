@@ -0,0 +1,568 @@
+/*! Container logs and database dumps kept for failed stack tests.
+
+A failing assertion rarely explains itself; the reason is usually in the logs of
+GoTrue, PostgREST or PostgreSQL. Every [`RunningStack`] collects its containers'
+stdout and stderr while it runs, and writes them to an artifacts directory when a test
+fails: through an [`ArtifactsGuard`] that notices the panic when dropped, or through an
+explicit [`RunningStack::write_artifacts`] call.
+
+# Architecture
+
+1. When the stack starts, each container's stdout and stderr are followed by a
+   background task that stamps every line with the time it was received
+2. When a service is started again, its logs are followed again; lines already
+   collected before it stopped are skipped
+3. Writing the artifacts creates `<artifacts dir>/<test name>/` with:
+   - `stack.log`: all lines in the order received, as
+     `2024-01-01T12:00:00.000Z [auth stderr] ...`
+   - `database.sql`: a plain `pg_dump` of the database, if enabled with
+     [`SupabaseStack::with_database_dump`]
+
+# Configuration
+
+The artifacts directory is, in order of precedence:
+
+1. [`SupabaseStack::with_artifacts_dir`]
+2. The [`ARTIFACTS_DIR_ENV`] environment variable (`SUPABASE_TC_ARTIFACTS_DIR`)
+3. `target/supabase-artifacts`, relative to the working directory (the package root
+   under `cargo test`)
+
+# Example
+
+```rust,no_run
+use supabase_testcontainers_modules::{Auth, SupabaseStack};
+
+#[tokio::test]
+async fn signup_works() -> anyhow::Result<()> {
+    let stack = SupabaseStack::new()
+        .with_auth(Auth::default())
+        .with_database_dump(true)
+        .start()
+        .await?;
+    // Writes the logs and the dump if the test panics
+    let _guard = stack.artifacts_guard("signup_works");
+
+    // ... assertions against stack.auth_url() ...
+    Ok(())
+}
+```
+*/
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context};
+use futures_util::StreamExt;
+use testcontainers_modules::testcontainers::bollard::container::LogOutput;
+use testcontainers_modules::testcontainers::bollard::exec::{CreateExecOptions, StartExecResults};
+use testcontainers_modules::testcontainers::bollard::Docker;
+use testcontainers_modules::testcontainers::{ContainerAsync, Image};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+
+use crate::RunningStack;
+
+/// Environment variable naming the directory failed tests' artifacts are written to
+pub const ARTIFACTS_DIR_ENV: &str = "SUPABASE_TC_ARTIFACTS_DIR";
+
+/// Artifacts directory used when neither the stack nor [`ARTIFACTS_DIR_ENV`] sets one
+const DEFAULT_ARTIFACTS_DIR: &str = "target/supabase-artifacts";
+/// File the collected logs are written to
+pub(crate) const LOG_FILE: &str = "stack.log";
+/// File the database dump is written to
+pub(crate) const DUMP_FILE: &str = "database.sql";
+
+/// Returns the artifacts directory, preferring `configured` over [`ARTIFACTS_DIR_ENV`]
+pub(crate) fn artifacts_dir(configured: Option<&Path>) -> PathBuf {
+    artifacts_dir_from(configured, std::env::var(ARTIFACTS_DIR_ENV).ok())
+}
+
+/// [`artifacts_dir`] with the environment variable's value passed in
+fn artifacts_dir_from(configured: Option<&Path>, env: Option<String>) -> PathBuf {
+    match (configured, env) {
+        (Some(dir), _) => dir.to_path_buf(),
+        (None, Some(dir)) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(DEFAULT_ARTIFACTS_DIR),
+    }
+}
+
+/// Returns `test_name` as a single path component
+///
+/// Characters other than ASCII letters, digits, `-`, `_` and `.` become `_`, so module
+/// paths such as `tests::signup` map to `tests__signup`.
+pub(crate) fn test_dir_name(test_name: &str) -> String {
+    let name: String = test_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match name.trim_matches('.') {
+        "" => "unnamed".to_string(),
+        _ => name,
+    }
+}
+
+/// Stream a log line was written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+impl LogStream {
+    /// Returns the stream's name in log prefixes
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Stdout => "stdout",
+            Self::Stderr => "stderr",
+        }
+    }
+}
+
+/// A collected log line
+#[derive(Debug, Clone)]
+struct LogLine {
+    /// When the line was received
+    at: SystemTime,
+    /// Service that wrote the line
+    service: String,
+    /// Stream the line was written to
+    stream: LogStream,
+    /// The line, without its line break
+    text: String,
+}
+
+impl std::fmt::Display for LogLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} [{} {}] {}",
+            format_timestamp(self.at),
+            self.service,
+            self.stream.as_str(),
+            self.text
+        )
+    }
+}
+
+/// Lines collected so far
+#[derive(Debug, Default)]
+struct Collected {
+    /// Lines in the order received
+    lines: Vec<LogLine>,
+    /// Number of lines collected per container and stream
+    ///
+    /// Following a restarted container replays its earlier lines; these counts skip them.
+    /// A service's replaced container starts a count of its own.
+    seen: HashMap<(String, LogStream), usize>,
+}
+
+impl Collected {
+    /// Records the `index`th line of a container's stream, unless it was collected before
+    ///
+    /// Returns whether the line was recorded.
+    fn record(&mut self, index: usize, source: &Source, text: &str) -> bool {
+        let seen = self
+            .seen
+            .entry((source.container_id.clone(), source.stream))
+            .or_default();
+        if index < *seen {
+            return false;
+        }
+        *seen += 1;
+        self.lines.push(LogLine {
+            at: SystemTime::now(),
+            service: source.service.clone(),
+            stream: source.stream,
+            text: text.to_string(),
+        });
+        true
+    }
+}
+
+/// Collects the stdout and stderr of a stack's containers
+///
+/// The collecting tasks are aborted when it is dropped.
+#[derive(Debug, Default)]
+pub(crate) struct LogCollector {
+    /// Lines collected so far, shared with the tasks
+    collected: Arc<Mutex<Collected>>,
+    /// Tasks following the containers' logs
+    tasks: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl LogCollector {
+    /// Creates a collector without containers
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Follows a container's stdout and stderr, prefixing its lines with `service`
+    ///
+    /// Must be called within a Tokio runtime. Call again after the container was
+    /// started again; its following ends when the container stops. With the `tracing`
+    /// feature, new lines are also emitted as events.
+    pub(crate) fn follow<I: Image>(&self, container: &ContainerAsync<I>, service: &str) {
+        let streams = [
+            (LogStream::Stdout, container.stdout(true)),
+            (LogStream::Stderr, container.stderr(true)),
+        ];
+        let mut tasks = self.tasks.lock().unwrap_or_else(PoisonError::into_inner);
+        tasks.retain(|task| !task.is_finished());
+        for (stream, reader) in streams {
+            let collected = self.collected.clone();
+            let source = Source {
+                service: service.to_string(),
+                container_id: container.id().to_string(),
+                stream,
+            };
+            tasks.push(tokio::spawn(collect(reader, collected, source)));
+        }
+    }
+
+    /// Returns the collected lines, one per line
+    pub(crate) fn render(&self) -> String {
+        let collected = self
+            .collected
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        collected
+            .lines
+            .iter()
+            .map(|line| format!("{}\n", line))
+            .collect()
+    }
+}
+
+impl Drop for LogCollector {
+    fn drop(&mut self) {
+        let tasks = self.tasks.get_mut().unwrap_or_else(PoisonError::into_inner);
+        for task in tasks.drain(..) {
+            task.abort();
+        }
+    }
+}
+
+/// Container stream a collecting task reads
+#[derive(Debug, Clone)]
+struct Source {
+    /// Service running in the container
+    service: String,
+    /// Docker container ID
+    container_id: String,
+    /// Stream read
+    stream: LogStream,
+}
+
+impl Source {
+    /// Emits a newly collected line as a `tracing` event, with the `tracing` feature
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    fn emit(&self, text: &str) {
+        #[cfg(feature = "tracing")]
+        crate::logs::emit(
+            &self.service,
+            &self.container_id,
+            self.stream.as_str(),
+            text,
+        );
+    }
+}
+
+/// Reads `reader` line by line into `collected` until the stream ends
+async fn collect(
+    mut reader: Pin<Box<dyn AsyncBufRead + Send>>,
+    collected: Arc<Mutex<Collected>>,
+    source: Source,
+) {
+    let mut buffer = Vec::new();
+    let mut index = 0;
+    // Errors end the stream like EOF: the container was stopped or removed
+    while let Ok(read) = reader.read_until(b'\n', &mut buffer).await {
+        if read == 0 {
+            break;
+        }
+        let line = String::from_utf8_lossy(&buffer);
+        let text = line.trim_end_matches(['\r', '\n']);
+        if collected
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .record(index, &source, text)
+        {
+            source.emit(text);
+        }
+        index += 1;
+        buffer.clear();
+    }
+}
+
+/// Formats `time` as an RFC 3339 UTC timestamp with milliseconds
+fn format_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3_600,
+        secs_of_day % 3_600 / 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Runs `cmd` in a container through `docker` and returns its stdout
+///
+/// The guard cannot use the client the containers were started with: its connections
+/// belong to the test's runtime, which is blocked while the guard is dropped.
+pub(crate) async fn exec_with(
+    docker: &Docker,
+    container_id: &str,
+    cmd: &[String],
+) -> anyhow::Result<String> {
+    let exec = docker
+        .create_exec(
+            container_id,
+            CreateExecOptions {
+                cmd: Some(cmd.to_vec()),
+                attach_stdout: Some(true),
+                attach_stderr: Some(true),
+                ..Default::default()
+            },
+        )
+        .await
+        .with_context(|| format!("failed to run '{}'", cmd.join(" ")))?;
+    let mut output = match docker.start_exec(&exec.id, None).await? {
+        StartExecResults::Attached { output, .. } => output,
+        StartExecResults::Detached => bail!("'{}' was started detached", cmd.join(" ")),
+    };
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    while let Some(chunk) = output.next().await {
+        match chunk? {
+            LogOutput::StdOut { message } => stdout.extend_from_slice(&message),
+            LogOutput::StdErr { message } => stderr.extend_from_slice(&message),
+            _ => {}
+        }
+    }
+
+    match docker.inspect_exec(&exec.id).await?.exit_code {
+        Some(0) => Ok(String::from_utf8_lossy(&stdout).into_owned()),
+        code => bail!(
+            "'{}' exited with code {}: {}",
+            cmd.join(" "),
+            code.map_or("unknown".to_string(), |code| code.to_string()),
+            String::from_utf8_lossy(&stderr).trim()
+        ),
+    }
+}
+
+/// Writes a stack's artifacts if the test panics before it is dropped
+///
+/// Obtained from [`RunningStack::artifacts_guard`]. Keep it alive for the whole test,
+/// for example as `let _guard = ...;` (not `let _ = ...;`, which drops it at once).
+#[must_use = "the guard only writes artifacts when it is dropped during a panic"]
+pub struct ArtifactsGuard<'a> {
+    /// The stack whose artifacts are written
+    stack: &'a RunningStack,
+    /// Name of the test's artifacts directory
+    test_name: String,
+    /// Runtime the artifacts are written on; a new one when `None`
+    runtime: Option<Handle>,
+}
+
+impl<'a> ArtifactsGuard<'a> {
+    /// Creates a guard for `test_name`'s artifacts
+    pub(crate) fn new(stack: &'a RunningStack, test_name: String) -> Self {
+        Self {
+            stack,
+            test_name,
+            runtime: None,
+        }
+    }
+
+    /// Writes the artifacts on `runtime`, the one the stack was started on, instead of a
+    /// new one
+    #[cfg_attr(not(feature = "blocking"), allow(dead_code))]
+    pub(crate) fn with_runtime(mut self, runtime: Handle) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+}
+
+impl Drop for ArtifactsGuard<'_> {
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            return;
+        }
+        // Drop cannot await, and the test's runtime is unwinding; write on a separate
+        // thread. On a new runtime, the dump needs a Docker client of its own.
+        let (stack, test_name) = (self.stack, self.test_name.as_str());
+        let runtime = self.runtime.clone();
+        let result = std::thread::scope(|scope| {
+            scope
+                .spawn(move || match runtime {
+                    Some(runtime) => runtime.block_on(stack.write_artifacts(test_name)),
+                    None => tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()
+                        .context("failed to build artifacts runtime")?
+                        .block_on(async {
+                            let docker = Docker::connect_with_defaults()
+                                .context("failed to connect to Docker")?;
+                            stack.write_artifacts_with(test_name, Some(&docker)).await
+                        }),
+                })
+                .join()
+        });
+        match result {
+            Ok(Ok(dir)) => report!(warn, "wrote stack artifacts to {}", dir.display()),
+            Ok(Err(e)) => report!(error, "failed to write stack artifacts: {:#}", e),
+            Err(_) => report!(error, "failed to write stack artifacts: writing panicked"),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "stack")]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_artifacts_dir_precedence() {
+        let configured = Path::new("/tmp/configured");
+
+        assert_eq!(
+            artifacts_dir_from(Some(configured), Some("/tmp/env".to_string())),
+            configured
+        );
+        assert_eq!(
+            artifacts_dir_from(None, Some("/tmp/env".to_string())),
+            Path::new("/tmp/env")
+        );
+        assert_eq!(
+            artifacts_dir_from(None, Some(String::new())),
+            Path::new(DEFAULT_ARTIFACTS_DIR)
+        );
+        assert_eq!(
+            artifacts_dir_from(None, None),
+            Path::new(DEFAULT_ARTIFACTS_DIR)
+        );
+    }
+
+    #[test]
+    fn test_test_dir_name() {
+        assert_eq!(test_dir_name("signup_works"), "signup_works");
+        assert_eq!(test_dir_name("tests::signup works"), "tests__signup_works");
+        assert_eq!(test_dir_name("../escape"), ".._escape");
+        assert_eq!(test_dir_name(".."), "unnamed");
+        assert_eq!(test_dir_name(""), "unnamed");
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH + Duration::from_millis(951_782_400_123)),
+            "2000-02-29T00:00:00.123Z"
+        );
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH + Duration::from_secs(1_704_110_399)),
+            "2024-01-01T11:59:59.000Z"
+        );
+    }
+
+    #[test]
+    fn test_record_skips_replayed_lines() {
+        let source = |container_id: &str, stream| Source {
+            service: "auth".to_string(),
+            container_id: container_id.to_string(),
+            stream,
+        };
+        let (stderr, stdout) = (
+            source("a1", LogStream::Stderr),
+            source("a1", LogStream::Stdout),
+        );
+        let mut collected = Collected::default();
+        collected.record(0, &stderr, "starting");
+        collected.record(1, &stderr, "listening");
+        collected.record(0, &stdout, "hello");
+        // The restarted container's stream replays both lines before the new one
+        collected.record(0, &stderr, "starting");
+        collected.record(1, &stderr, "listening");
+        collected.record(2, &stderr, "restarted");
+        // A replacement container's lines are all new
+        collected.record(0, &source("a2", LogStream::Stderr), "starting");
+
+        let texts: Vec<_> = collected.lines.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            ["starting", "listening", "hello", "restarted", "starting"]
+        );
+    }
+
+    #[test]
+    fn test_log_line_display() {
+        let line = LogLine {
+            at: UNIX_EPOCH + Duration::from_secs(60),
+            service: "rest".to_string(),
+            stream: LogStream::Stdout,
+            text: "Listening on port 3000".to_string(),
+        };
+
+        assert_eq!(
+            line.to_string(),
+            "1970-01-01T00:01:00.000Z [rest stdout] Listening on port 3000"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_collect_reads_lines_until_eof() {
+        let collected = Arc::new(Mutex::new(Collected::default()));
+        let reader: Pin<Box<dyn AsyncBufRead + Send>> =
+            Box::pin(&b"first\r\nsecond\nlast without break"[..]);
+
+        let source = Source {
+            service: "db".to_string(),
+            container_id: "0123abcd".to_string(),
+            stream: LogStream::Stderr,
+        };
+
+        collect(reader, collected.clone(), source).await;
+
+        let collected = collected.lock().unwrap();
+        let texts: Vec<_> = collected.lines.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(texts, ["first", "second", "last without break"]);
+        assert_eq!(
+            collected.seen[&("0123abcd".to_string(), LogStream::Stderr)],
+            3
+        );
+    }
+}
@@ -70,12 +70,15 @@ use testcontainers_modules::testcontainers::core::{
     ContainerPort, ContainerState, ExecCommand, WaitFor,
 };
 use testcontainers_modules::testcontainers::{Image, TestcontainersError};
-use tokio_postgres::NoTls;
+
+use crate::db::connect;
+use crate::overrides::{image_name, image_tag, pin_digest, with_registry};
+use crate::{ReadyCheck, SupabaseVersion};
 
 /// Default image name for Supabase Auth
 const NAME: &str = "supabase/gotrue";
 /// Default image tag version
-const TAG: &str = "v2.183.0";
+const TAG: &str = crate::consts::AUTH_TAG;
 /// Default port for Supabase Auth API
 pub const AUTH_PORT: u16 = 9999;
 
@@ -108,8 +111,12 @@ pub const AUTH_PORT: u16 = 9999;
 pub struct Auth {
     /// Environment variables to be passed to the container
     env_vars: BTreeMap<String, String>,
+    /// Docker image name
+    name: String,
     /// Docker image tag version
     tag: String,
+    /// How the container is considered ready
+    ready_check: ReadyCheck,
 }
 
 impl Auth {
@@ -195,6 +202,43 @@ impl Auth {
         self
     }
 
+    /// Sets the Docker image name, e.g. a fork or a copy in a private registry
+    ///
+    /// `SUPABASE_TC_REGISTRY` still prefixes names without a registry
+    pub fn with_image_name(mut self, name: impl Into<String>) -> Self {
+        self.name = with_registry(&name.into());
+        self
+    }
+
+    /// Pins the image to a digest such as `sha256:4c1e...`
+    ///
+    /// Docker pulls the digest and ignores the tag; [`Auth::with_tag`] removes the pin
+    pub fn with_digest(mut self, digest: impl AsRef<str>) -> Self {
+        self.tag = pin_digest(&self.tag, digest.as_ref());
+        self
+    }
+
+    /// Sets the tag from a [`SupabaseVersion`]
+    pub fn with_version(self, version: &SupabaseVersion) -> Self {
+        self.with_tag(version.auth())
+    }
+
+    /// Sets how the container is considered ready
+    ///
+    /// Default is [`ReadyCheck::Http`] on `/health`, which GoTrue answers once its
+    /// migrations have run and the API is listening. [`ReadyCheck::Log`] waits for the
+    /// startup log line instead.
+    pub fn with_ready_check(mut self, check: ReadyCheck) -> Self {
+        self.ready_check = check;
+        self
+    }
+
+    /// Returns how the container is considered ready
+    #[cfg(feature = "stack")]
+    pub(crate) fn ready_check(&self) -> &ReadyCheck {
+        &self.ready_check
+    }
+
     /// Adds a custom environment variable
     pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
         self.env_vars.insert(key.into(), value.into());
@@ -224,6 +268,7 @@ impl Auth {
     /// * The database URL is empty
     /// * Database connection fails
     /// * Schema creation fails
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
     pub async fn init_db_schema(
         self,
         db_url: &str,
@@ -239,16 +284,7 @@ impl Auth {
             .map(|s| s.as_str())
             .unwrap_or("auth");
 
-        let (client, connection) = tokio_postgres::connect(db_url, NoTls)
-            .await
-            .with_context(|| format!("failed to connect to PostgreSQL at {}", db_url))?;
-
-        // Spawn connection handler
-        tokio::spawn(async move {
-            if let Err(e) = connection.await {
-                eprintln!("PostgreSQL connection error: {}", e);
-            }
-        });
+        let client = connect(db_url).await?;
 
         let query = format!(
             "CREATE USER supabase_admin LOGIN CREATEROLE CREATEDB REPLICATION BYPASSRLS;
@@ -265,6 +301,25 @@ impl Auth {
 
         Ok(self)
     }
+
+    /// Initializes the database schema for Supabase Auth without an async runtime
+    ///
+    /// See [`Auth::init_db_schema`].
+    ///
+    /// # Errors
+    /// Returns an error if the database URL is empty, the connection fails or schema
+    /// creation fails.
+    ///
+    /// # Panics
+    /// Panics if called from within an async runtime.
+    #[cfg(feature = "blocking")]
+    pub fn init_db_schema_blocking(
+        self,
+        db_url: &str,
+        auth_admin_password: &str,
+    ) -> anyhow::Result<Self> {
+        crate::blocking::block_on(self.init_db_schema(db_url, auth_admin_password))
+    }
 }
 
 /// Default implementation for Auth container configuration
@@ -316,7 +371,9 @@ impl Default for Auth {
 
         Self {
             env_vars,
-            tag: TAG.to_string(),
+            name: image_name("AUTH", NAME),
+            tag: image_tag("AUTH", TAG),
+            ready_check: ReadyCheck::http("/health"),
         }
     }
 }
@@ -325,7 +382,7 @@ impl Default for Auth {
 impl Image for Auth {
     /// Returns the name of the Docker image
     fn name(&self) -> &str {
-        NAME
+        &self.name
     }
 
     /// Returns the tag of the Docker image
@@ -336,7 +393,9 @@ impl Image for Auth {
     /// Specifies the conditions that indicate when the container is ready
     /// Waits for the API to start listening on the configured port
     fn ready_conditions(&self) -> Vec<WaitFor> {
-        vec![WaitFor::message_on_stderr("API started")]
+        vec![self
+            .ready_check
+            .wait_for(AUTH_PORT, WaitFor::message_on_stderr("API started"))]
     }
 
     /// Returns the ports to expose from the container
@@ -412,6 +471,30 @@ mod tests {
         assert_eq!(auth.tag(), "v2.183.0");
     }
 
+    #[test]
+    fn test_default_tag_matches_tested_version() {
+        assert_eq!(Auth::default().tag(), SupabaseVersion::tested().auth());
+    }
+
+    #[test]
+    fn test_with_version_sets_tag() {
+        let version = SupabaseVersion::tested().with_auth("v2.170.0");
+        assert_eq!(Auth::default().with_version(&version).tag(), "v2.170.0");
+    }
+
+    #[test]
+    fn test_with_image_name() {
+        let auth = Auth::default().with_image_name("ghcr.io/acme/auth");
+        assert_eq!(auth.name(), "ghcr.io/acme/auth");
+    }
+
+    #[test]
+    fn test_with_digest_pins_tag() {
+        let auth = Auth::default().with_tag("v1").with_digest("sha256:abc");
+        assert_eq!(auth.tag(), "v1@sha256:abc");
+        assert_eq!(auth.with_tag("v2").tag(), "v2");
+    }
+
     #[test]
     fn test_builder_method_chaining() {
         let auth = Auth::default()
@@ -488,4 +571,19 @@ mod tests {
     fn test_auth_port_constant() {
         assert_eq!(AUTH_PORT, 9999);
     }
+
+    #[test]
+    fn test_ready_conditions() {
+        let auth = Auth::default();
+        let conditions = auth.ready_conditions();
+        assert_eq!(conditions.len(), 1);
+        assert!(matches!(conditions[0], WaitFor::Http(_)));
+    }
+
+    #[test]
+    fn test_with_ready_check_log() {
+        let auth = Auth::default().with_ready_check(ReadyCheck::Log);
+        let conditions = auth.ready_conditions();
+        assert!(matches!(conditions[0], WaitFor::Log(_)));
+    }
 }
@@ -0,0 +1,233 @@
+//! Command-line parsing
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Context};
+
+/// Stack name used when `--name` is not given
+pub const DEFAULT_NAME: &str = "supabase-tc";
+
+/// Usage text printed by `--help` and after usage errors
+pub const USAGE: &str = "\
+Starts the Supabase containers used by the Rust tests and prints how to connect to them.
+
+Usage: supabase-tc <command> [options]
+
+Commands:
+  up       Start the stack, print its connection environment and wait for Ctrl-C
+  down     Remove a stack started with `up --detach`
+  status   Show the state of the stack's containers
+  env      Print the connection environment of a running stack
+
+Options:
+  --name <name>         Stack name; names the Docker network and containers [default: supabase-tc]
+  --services <list>     Services to start, comma-separated: auth, rest, storage
+                        [default: auth,rest,storage]
+  --project <dir>       Start the services of a Supabase CLI project instead
+  --toxiproxy           Proxy the services' links through Toxiproxy
+  --detach              Exit after printing; the containers run until `down`
+  --format <env|json>   Output format of `up` and `env` [default: env]
+  --prefix <prefix>     Prefix of the variable names in env format, e.g. VITE_
+  -h, --help            Print this help
+";
+
+/// A subcommand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Up,
+    Down,
+    Status,
+    Env,
+    Help,
+}
+
+/// A service `--services` can select
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Service {
+    Auth,
+    Rest,
+    Storage,
+}
+
+impl Service {
+    /// Parses a service name, accepting PostgREST's crate name as well
+    fn parse(name: &str) -> anyhow::Result<Self> {
+        match name {
+            "auth" => Ok(Self::Auth),
+            "rest" | "postgrest" => Ok(Self::Rest),
+            "storage" => Ok(Self::Storage),
+            _ => bail!("unknown service '{}'; expected auth, rest or storage", name),
+        }
+    }
+}
+
+/// Output format of the connection environment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// `KEY=value` lines, as read by `.env` loaders
+    Env,
+    /// A JSON object
+    Json,
+}
+
+/// Parsed command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Args {
+    /// Subcommand to run
+    pub command: Command,
+    /// Stack name
+    pub name: String,
+    /// Services to start
+    pub services: Vec<Service>,
+    /// Supabase CLI project to start instead of `services`
+    pub project: Option<PathBuf>,
+    /// Whether the links are proxied through Toxiproxy
+    pub toxiproxy: bool,
+    /// Whether `up` exits after printing
+    pub detach: bool,
+    /// Output format
+    pub format: Format,
+    /// Prefix of variable names in env format
+    pub prefix: String,
+}
+
+impl Args {
+    /// Parses the arguments after the program name
+    ///
+    /// # Errors
+    /// Returns an error describing the first invalid argument.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> anyhow::Result<Self> {
+        let mut parsed = Self {
+            command: Command::Help,
+            name: DEFAULT_NAME.to_string(),
+            services: vec![Service::Auth, Service::Rest, Service::Storage],
+            project: None,
+            toxiproxy: false,
+            detach: false,
+            format: Format::Env,
+            prefix: String::new(),
+        };
+        let mut command = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |option: &str| {
+                args.next()
+                    .with_context(|| format!("{} requires a value", option))
+            };
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(parsed),
+                "--name" => parsed.name = value("--name")?,
+                "--services" => {
+                    parsed.services = value("--services")?
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty())
+                        .map(Service::parse)
+                        .collect::<anyhow::Result<_>>()?;
+                }
+                "--project" => parsed.project = Some(value("--project")?.into()),
+                "--toxiproxy" => parsed.toxiproxy = true,
+                "--detach" => parsed.detach = true,
+                "--format" => {
+                    parsed.format = match value("--format")?.as_str() {
+                        "env" => Format::Env,
+                        "json" => Format::Json,
+                        other => bail!("unknown format '{}'; expected env or json", other),
+                    }
+                }
+                "--prefix" => parsed.prefix = value("--prefix")?,
+                option if option.starts_with('-') => bail!("unknown option '{}'", option),
+                name if command.is_none() => {
+                    command = Some(match name {
+                        "up" => Command::Up,
+                        "down" => Command::Down,
+                        "status" => Command::Status,
+                        "env" => Command::Env,
+                        "help" => Command::Help,
+                        _ => bail!("unknown command '{}'", name),
+                    })
+                }
+                extra => bail!("unexpected argument '{}'", extra),
+            }
+        }
+        parsed.command = command.context("no command given")?;
+        if parsed.name.is_empty() {
+            bail!("--name cannot be empty");
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> anyhow::Result<Args> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_defaults() {
+        let args = parse(&["up"]).unwrap();
+
+        assert_eq!(args.command, Command::Up);
+        assert_eq!(args.name, DEFAULT_NAME);
+        assert_eq!(
+            args.services,
+            [Service::Auth, Service::Rest, Service::Storage]
+        );
+        assert_eq!(args.format, Format::Env);
+        assert!(!args.detach);
+        assert!(args.prefix.is_empty());
+    }
+
+    #[test]
+    fn test_options() {
+        let args = parse(&[
+            "--name",
+            "web",
+            "up",
+            "--services",
+            "postgrest, auth",
+            "--format",
+            "json",
+            "--prefix",
+            "VITE_",
+            "--detach",
+            "--toxiproxy",
+        ])
+        .unwrap();
+
+        assert_eq!(args.name, "web");
+        assert_eq!(args.services, [Service::Rest, Service::Auth]);
+        assert_eq!(args.format, Format::Json);
+        assert_eq!(args.prefix, "VITE_");
+        assert!(args.detach);
+        assert!(args.toxiproxy);
+    }
+
+    #[test]
+    fn test_help() {
+        assert_eq!(parse(&["up", "--help"]).unwrap().command, Command::Help);
+        assert_eq!(parse(&["help"]).unwrap().command, Command::Help);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |args: &[&str]| parse(args).unwrap_err().to_string();
+
+        assert_eq!(error(&[]), "no command given");
+        assert_eq!(error(&["start"]), "unknown command 'start'");
+        assert_eq!(error(&["up", "down"]), "unexpected argument 'down'");
+        assert_eq!(error(&["up", "--verbose"]), "unknown option '--verbose'");
+        assert_eq!(error(&["up", "--name"]), "--name requires a value");
+        assert_eq!(
+            error(&["up", "--services", "auth,realtime"]),
+            "unknown service 'realtime'; expected auth, rest or storage"
+        );
+        assert_eq!(
+            error(&["env", "--format", "yaml"]),
+            "unknown format 'yaml'; expected env or json"
+        );
+    }
+}
@@ -0,0 +1,187 @@
+/*! `supabase-tc`: the test stack for local development.
+
+Starts the same containers the Rust tests use, through [`SupabaseStack`], and prints
+the connection environment: the service URLs, the API keys, the database URL and the
+JWT secret. Secrets are the crate's defaults, so keys printed here work against stacks
+started by the tests too.
+
+- `up` starts the stack, prints the environment and keeps the containers until Ctrl-C;
+  with `--detach` the containers are kept (as reused containers) and `down` removes them
+- `env` prints the environment of a stack that is up
+- `status` shows the state of its containers
+- `down` removes a stack's containers and network
+
+`up` records the stack in a state file in the temporary directory, so the other
+commands find it by `--name`. `status` and `down` use the `docker` CLI.
+
+Build with `cargo install supabase-testcontainers-modules --features cli`.
+*/
+
+mod args;
+mod state;
+
+use std::process::{Command as Process, ExitCode};
+
+use anyhow::{bail, Context};
+use supabase_testcontainers_modules::{Auth, PostgREST, Storage, SupabaseStack, Toxiproxy};
+
+use crate::args::{Args, Command, Service, USAGE};
+use crate::state::State;
+
+fn main() -> ExitCode {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match run(&args) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Runs the command
+fn run(args: &Args) -> anyhow::Result<ExitCode> {
+    match args.command {
+        Command::Help => print!("{}", USAGE),
+        Command::Up => tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .context("failed to build the runtime")?
+            .block_on(up(args))?,
+        Command::Down => down(args)?,
+        Command::Status => return status(args),
+        Command::Env => print!("{}", load(args)?.render(args.format, &args.prefix)),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Starts the stack and prints its environment; waits for Ctrl-C unless detached
+async fn up(args: &Args) -> anyhow::Result<()> {
+    if let Some(state) = State::load(&args.name)? {
+        // A detached stack is reused when started again with the same configuration
+        if !(state.detached && args.detach) {
+            bail!(
+                "stack '{}' is already up; remove it with `supabase-tc down --name {}`",
+                args.name,
+                args.name
+            );
+        }
+    }
+    let stack = match &args.project {
+        Some(dir) => SupabaseStack::from_project_dir(dir)?,
+        None => args
+            .services
+            .iter()
+            .fold(SupabaseStack::new(), |stack, service| match service {
+                Service::Auth => stack.with_auth(Auth::default()),
+                Service::Rest => stack.with_postgrest(PostgREST::default()),
+                Service::Storage => stack.with_storage(Storage::default()),
+            }),
+    };
+    let stack = match args.toxiproxy {
+        true => stack.with_toxiproxy(Toxiproxy::default()),
+        false => stack,
+    };
+
+    eprintln!("starting stack '{}'...", args.name);
+    let stack = stack
+        .with_network(&args.name)
+        .with_reuse(args.detach)
+        .start()
+        .await?;
+    let state = State::from_stack(&stack, args.detach);
+    state.save(&args.name)?;
+    print!("{}", state.render(args.format, &args.prefix));
+
+    if args.detach {
+        eprintln!(
+            "stack '{}' is up; remove it with `supabase-tc down --name {}`",
+            args.name, args.name
+        );
+        return Ok(());
+    }
+    eprintln!("stack '{}' is up; press Ctrl-C to stop it", args.name);
+    let signal = tokio::signal::ctrl_c().await;
+    eprintln!("stopping stack '{}'...", args.name);
+    state::remove(&args.name)?;
+    drop(stack);
+    signal.context("failed to wait for Ctrl-C")
+}
+
+/// Removes the stack's containers and network
+fn down(args: &Args) -> anyhow::Result<()> {
+    let state = load(args)?;
+    let mut rm = vec!["rm", "--force", "--volumes"];
+    rm.extend(state.containers.iter().map(|c| c.id.as_str()));
+    docker(&rm, "No such container")?;
+    docker(&["network", "rm", &state.network], "not found")?;
+    state::remove(&args.name)?;
+    eprintln!("removed stack '{}'", args.name);
+    Ok(())
+}
+
+/// Prints the state of the stack's containers; fails unless all are running
+fn status(args: &Args) -> anyhow::Result<ExitCode> {
+    let Some(state) = State::load(&args.name)? else {
+        println!("stack '{}' is not up", args.name);
+        return Ok(ExitCode::FAILURE);
+    };
+    let mut all_running = true;
+    for container in &state.containers {
+        let status = docker(
+            &["inspect", "--format", "{{.State.Status}}", &container.id],
+            "No such object",
+        )?;
+        let status = match status.trim() {
+            "" => "removed",
+            status => status,
+        };
+        all_running &= status == "running";
+        println!(
+            "{:<10} {:<10} {}",
+            container.service,
+            status,
+            &container.id[..container.id.len().min(12)]
+        );
+    }
+    Ok(match all_running {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    })
+}
+
+/// Returns the state of the stack `up` started
+fn load(args: &Args) -> anyhow::Result<State> {
+    State::load(&args.name)?.with_context(|| {
+        format!(
+            "stack '{}' is not up; start it with `supabase-tc up --name {}`",
+            args.name, args.name
+        )
+    })
+}
+
+/// Runs the `docker` CLI and returns its stdout
+///
+/// Error lines containing `missing` (the message for an object that does not exist)
+/// are ignored.
+fn docker(args: &[&str], missing: &str) -> anyhow::Result<String> {
+    let output = Process::new("docker")
+        .args(args)
+        .output()
+        .context("failed to run docker")?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let errors: Vec<_> = stderr
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.contains(missing))
+        .collect();
+    if !output.status.success() && !errors.is_empty() {
+        bail!("'docker {}' failed: {}", args.join(" "), errors.join("\n"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
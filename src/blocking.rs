@@ -0,0 +1,353 @@
+/*! Synchronous variants of the async helpers, for test suites without an async runtime.
+
+Every image in this crate implements [`testcontainers::Image`], so with the `blocking`
+feature enabled it starts with [`testcontainers::runners::SyncRunner`] like any other
+image. The helpers that talk to the database or orchestrate a [`SupabaseStack`] are
+async; their `_blocking` variants run them to completion on a background runtime that
+lives as long as the process.
+
+# Architecture
+
+1. A multi-threaded runtime is built on first use
+2. Each `_blocking` helper runs its async counterpart on that runtime and waits for it
+3. [`SupabaseStack::start_blocking`] starts the stack on that runtime and returns a
+   [`BlockingStack`]; its containers are stopped there when it is dropped
+
+The `_blocking` helpers must not be called from within an async runtime; use the async
+helpers there.
+
+# Example
+
+```rust,no_run
+use supabase_testcontainers_modules::{Auth, PostgREST, SupabaseStack};
+
+#[test]
+fn sync_test() -> anyhow::Result<()> {
+    let stack = SupabaseStack::new()
+        .with_auth(Auth::default())
+        .with_postgrest(PostgREST::default())
+        .start_blocking()?;
+
+    // ... run the test against stack.rest_url() ...
+    stack.reset()?;
+    Ok(())
+}
+```
+*/
+
+// Without an async helper to wrap, only the runtime is compiled
+#![cfg_attr(
+    not(any(
+        feature = "auth",
+        feature = "graphql",
+        feature = "postgres_testcontainer",
+        feature = "stack"
+    )),
+    allow(dead_code)
+)]
+
+use std::future::Future;
+use std::sync::OnceLock;
+
+use tokio::runtime::{Builder, Runtime};
+
+#[cfg(feature = "stack")]
+use anyhow::Context;
+
+#[cfg(feature = "stack")]
+use crate::{
+    ArtifactsGuard, IsolatedStack, Isolation, MigrationRunner, RunningStack, StackService,
+    SupabaseStack,
+};
+
+/// Worker threads of the background runtime
+const RUNTIME_WORKERS: usize = 2;
+
+/// Runtime running the `_blocking` helpers and owning blocking stacks
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+/// Returns the background runtime, building it on first use
+fn runtime() -> &'static Runtime {
+    RUNTIME.get_or_init(|| {
+        Builder::new_multi_thread()
+            .worker_threads(RUNTIME_WORKERS)
+            .thread_name("supabase-blocking")
+            .enable_all()
+            .build()
+            .expect("failed to build the blocking runtime")
+    })
+}
+
+/// Runs `future` to completion on the background runtime
+///
+/// # Panics
+/// Panics if called from within an async runtime.
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    runtime().block_on(future)
+}
+
+#[cfg(feature = "stack")]
+impl SupabaseStack {
+    /// Starts the stack without an async runtime
+    ///
+    /// See [`SupabaseStack::start`]. The containers are started on a background runtime
+    /// and stopped when the returned [`BlockingStack`] is dropped.
+    ///
+    /// # Errors
+    /// Returns an error if any container fails to start or the database cannot be
+    /// prepared.
+    ///
+    /// # Panics
+    /// Panics if called from within an async runtime.
+    pub fn start_blocking(self) -> anyhow::Result<BlockingStack> {
+        Ok(BlockingStack {
+            stack: Some(block_on(self.start())?),
+        })
+    }
+}
+
+/// A [`RunningStack`] driven from synchronous code
+///
+/// Obtained from [`SupabaseStack::start_blocking`]. Operations run on a background
+/// runtime; the containers are stopped there when the handle is dropped.
+#[cfg(feature = "stack")]
+pub struct BlockingStack {
+    /// The stack; taken when dropped
+    stack: Option<RunningStack>,
+}
+
+#[cfg(feature = "stack")]
+impl BlockingStack {
+    /// Returns the running stack, for its containers and accessors
+    ///
+    /// Its async operations must be run through the `BlockingStack` methods.
+    pub fn stack(&self) -> &RunningStack {
+        self.stack.as_ref().expect("stack is only taken on drop")
+    }
+
+    /// Returns the Docker network the containers share
+    pub fn network(&self) -> &str {
+        self.stack().network()
+    }
+
+    /// Returns the JWT secret shared by all services
+    pub fn jwt_secret(&self) -> &str {
+        self.stack().jwt_secret()
+    }
+
+    /// Returns the API key for the `anon` role
+    pub fn anon_key(&self) -> &str {
+        self.stack().anon_key()
+    }
+
+    /// Returns the API key for the `service_role` role
+    pub fn service_role_key(&self) -> &str {
+        self.stack().service_role_key()
+    }
+
+    /// Returns a superuser connection string for the database, reachable from the host
+    pub fn db_url(&self) -> &str {
+        self.stack().db_url()
+    }
+
+    /// Returns the Auth URL, if Auth is part of the stack
+    pub fn auth_url(&self) -> Option<&str> {
+        self.stack().auth_url()
+    }
+
+    /// Returns the PostgREST URL, if PostgREST is part of the stack
+    pub fn rest_url(&self) -> Option<&str> {
+        self.stack().rest_url()
+    }
+
+    /// Returns the Storage URL, if Storage is part of the stack
+    pub fn storage_url(&self) -> Option<&str> {
+        self.stack().storage_url()
+    }
+
+    /// Returns the Toxiproxy API URL, if the links are proxied
+    pub fn toxiproxy_url(&self) -> Option<&str> {
+        self.stack().toxiproxy_url()
+    }
+
+    /// Returns the stack to its snapshot
+    ///
+    /// See [`RunningStack::reset`].
+    ///
+    /// # Errors
+    /// Returns an error if any of the reset steps fails.
+    pub fn reset(&self) -> anyhow::Result<()> {
+        block_on(self.stack().reset())
+    }
+
+    /// Applies the migrations not yet applied to the stack's database
+    ///
+    /// See [`RunningStack::migrate`].
+    ///
+    /// # Errors
+    /// Returns an error naming the migration and statement that failed, or if the
+    /// schema cache reload fails.
+    pub fn migrate(&self, migrations: &MigrationRunner) -> anyhow::Result<Vec<String>> {
+        block_on(self.stack().migrate(migrations))
+    }
+
+    /// Replaces the snapshot with the current state
+    ///
+    /// See [`RunningStack::take_snapshot`].
+    ///
+    /// # Errors
+    /// Returns an error if the database snapshot or the Storage archive cannot be taken.
+    pub fn take_snapshot(&mut self) -> anyhow::Result<()> {
+        let stack = self.stack.as_mut().expect("stack is only taken on drop");
+        block_on(stack.take_snapshot())
+    }
+
+    /// Stops a service's container
+    ///
+    /// See [`RunningStack::stop_service`].
+    ///
+    /// # Errors
+    /// Returns an error if the service is not part of the stack or Docker fails.
+    pub fn stop_service(&self, service: StackService) -> anyhow::Result<()> {
+        block_on(self.stack().stop_service(service))
+    }
+
+    /// Kills a service's container without a graceful shutdown
+    ///
+    /// See [`RunningStack::kill_service`].
+    ///
+    /// # Errors
+    /// Returns an error if the service is not part of the stack or Docker fails.
+    pub fn kill_service(&self, service: StackService) -> anyhow::Result<()> {
+        block_on(self.stack().kill_service(service))
+    }
+
+    /// Starts a stopped service's container and waits until it is ready again
+    ///
+    /// See [`RunningStack::start_service`].
+    ///
+    /// # Errors
+    /// Returns an error if the service is not part of the stack, Docker fails or the
+    /// service is not ready within a minute.
+    pub fn start_service(&mut self, service: StackService) -> anyhow::Result<()> {
+        let stack = self.stack.as_mut().expect("stack is only taken on drop");
+        block_on(stack.start_service(service))
+    }
+
+    /// Stops and starts a service's container
+    ///
+    /// See [`RunningStack::restart_service`].
+    ///
+    /// # Errors
+    /// Returns an error if the service is not part of the stack, Docker fails or the
+    /// service is not ready within a minute.
+    pub fn restart_service(&mut self, service: StackService) -> anyhow::Result<()> {
+        let stack = self.stack.as_mut().expect("stack is only taken on drop");
+        block_on(stack.restart_service(service))
+    }
+
+    /// Pauses a service's processes
+    ///
+    /// See [`RunningStack::pause_service`].
+    ///
+    /// # Errors
+    /// Returns an error if the service is not part of the stack or Docker fails.
+    pub fn pause_service(&self, service: StackService) -> anyhow::Result<()> {
+        block_on(self.stack().pause_service(service))
+    }
+
+    /// Resumes a paused service's processes
+    ///
+    /// # Errors
+    /// Returns an error if the service is not part of the stack or Docker fails.
+    pub fn unpause_service(&self, service: StackService) -> anyhow::Result<()> {
+        block_on(self.stack().unpause_service(service))
+    }
+
+    /// Writes the containers' logs (and the database dump, if enabled) for `test_name`
+    ///
+    /// See [`RunningStack::write_artifacts`].
+    ///
+    /// # Errors
+    /// Returns an error if the files cannot be written or the dump fails.
+    pub fn write_artifacts(&self, test_name: &str) -> anyhow::Result<std::path::PathBuf> {
+        block_on(self.stack().write_artifacts(test_name))
+    }
+
+    /// Returns a guard writing the artifacts for `test_name` if the test panics
+    ///
+    /// See [`RunningStack::artifacts_guard`]. The artifacts are written on the
+    /// background runtime.
+    pub fn artifacts_guard(&self, test_name: impl Into<String>) -> ArtifactsGuard<'_> {
+        self.stack()
+            .artifacts_guard(test_name)
+            .with_runtime(runtime().handle().clone())
+    }
+
+    /// Isolates a test from others sharing this stack
+    ///
+    /// See [`RunningStack::isolate`]. The returned [`IsolatedStack`] is cleaned up on
+    /// the background runtime when dropped.
+    ///
+    /// # Errors
+    /// Returns an error if the database or schemas cannot be created or a per-test
+    /// container fails to start.
+    pub fn isolate(&self, isolation: Isolation) -> anyhow::Result<IsolatedStack> {
+        let isolated = block_on(self.stack().isolate(isolation))?;
+        Ok(isolated.with_runtime(runtime().handle().clone()))
+    }
+}
+
+#[cfg(feature = "stack")]
+impl Drop for BlockingStack {
+    fn drop(&mut self) {
+        let Some(stack) = self.stack.take() else {
+            return;
+        };
+        // Containers are stopped on the runtime that started them; use a separate
+        // thread so dropping also works from within an async runtime
+        let result = std::thread::spawn(move || {
+            block_on(runtime().spawn(async move { drop(stack) }))
+                .context("stopping the stack panicked")
+        })
+        .join();
+        if !matches!(result, Ok(Ok(()))) {
+            report!(
+                error,
+                "failed to stop the stack; containers may be left running"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "blocking")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_on_runs_on_background_runtime() {
+        let name = block_on(async {
+            tokio::spawn(async { std::thread::current().name().map(str::to_string) })
+                .await
+                .unwrap()
+        });
+
+        assert_eq!(name.as_deref(), Some("supabase-blocking"));
+    }
+
+    #[test]
+    fn test_runtime_is_reused() {
+        assert!(std::ptr::eq(runtime(), runtime()));
+    }
+
+    #[test]
+    fn test_block_on_from_threads() {
+        let handles: Vec<_> = (0..4)
+            .map(|i| std::thread::spawn(move || block_on(async move { i * 2 })))
+            .collect();
+        let values: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        assert_eq!(values, [0, 2, 4, 6]);
+    }
+}
@@ -2,3 +2,20 @@
 pub const DOCKER_INTERNAL_HOST: &str = "host.docker.internal";
 /// Standard localhost address for local development
 pub const LOCAL_HOST: &str = "localhost";
+
+/// Default Auth (`supabase/gotrue`) image tag, also in the `tested` `SupabaseVersion`
+pub(crate) const AUTH_TAG: &str = "v2.183.0";
+/// Default PostgREST image tag, also in the `tested` `SupabaseVersion`
+pub(crate) const POSTGREST_TAG: &str = "v12.2.3";
+/// Default Storage (`supabase/storage-api`) image tag, also in the `tested` `SupabaseVersion`
+pub(crate) const STORAGE_TAG: &str = "v1.11.1";
+/// Default Realtime image tag, also in the `tested` `SupabaseVersion`
+pub(crate) const REALTIME_TAG: &str = "v2.33.58";
+/// Default Functions (`supabase/edge-runtime`) image tag, also in the `tested` `SupabaseVersion`
+pub(crate) const FUNCTIONS_TAG: &str = "v1.67.4";
+/// Default Analytics (`supabase/logflare`) image tag, also in the `tested` `SupabaseVersion`
+pub(crate) const ANALYTICS_TAG: &str = "1.26.13";
+/// Default `supabase/postgres`, used by GraphQL image tag, also in the `tested` `SupabaseVersion`
+pub(crate) const SUPABASE_POSTGRES_TAG: &str = "15.8.1.085";
+/// Default Vector image tag, also in the `tested` `SupabaseVersion`
+pub(crate) const VECTOR_TAG: &str = "0.28.1-alpine";
@@ -0,0 +1,46 @@
+//! PostgreSQL connections for the modules that run SQL.
+//!
+//! tokio-postgres splits a connection into a client and a connection future that must
+//! be polled for the client to make progress. These helpers spawn that future on the
+//! current runtime and report its errors, so callers only hold the client.
+
+use anyhow::{bail, Context};
+use tokio_postgres::tls::NoTlsStream;
+use tokio_postgres::{Client, Config, Connection, NoTls, Socket};
+
+/// Connects to PostgreSQL at `db_url`
+///
+/// # Errors
+/// Returns an error if `db_url` is empty or the connection fails.
+pub(crate) async fn connect(db_url: &str) -> anyhow::Result<Client> {
+    if db_url.is_empty() {
+        bail!("database URL cannot be empty");
+    }
+    let (client, connection) = tokio_postgres::connect(db_url, NoTls)
+        .await
+        .with_context(|| format!("failed to connect to PostgreSQL at {}", db_url))?;
+    Ok(spawn_connection(client, connection))
+}
+
+/// Connects to PostgreSQL with a parsed `config`
+///
+/// # Errors
+/// Returns an error if the connection fails.
+#[cfg_attr(not(feature = "stack"), allow(dead_code))]
+pub(crate) async fn connect_config(config: &Config) -> anyhow::Result<Client> {
+    let (client, connection) = config
+        .connect(NoTls)
+        .await
+        .context("failed to connect to PostgreSQL")?;
+    Ok(spawn_connection(client, connection))
+}
+
+/// Spawns the connection handler and returns the client
+fn spawn_connection(client: Client, connection: Connection<Socket, NoTlsStream>) -> Client {
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            report!(error, "PostgreSQL connection error: {}", e);
+        }
+    });
+    client
+}
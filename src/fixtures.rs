@@ -0,0 +1,883 @@
+/*! Seed data beyond SQL: Auth users, Storage objects and table rows.
+
+Tests often need users that already exist with a known password, files already in a
+bucket, or rows to work on. [`Fixtures`] declares them and applies them to a running
+stack:
+
+- Users are created through the GoTrue admin API, with their email and phone already
+  confirmed, and signed in when they have a password. Identities for other providers
+  are added to `auth.identities`.
+- Objects are uploaded through the Storage API; missing buckets are created private.
+- Rows are inserted with SQL, so column defaults apply and no PostgREST is needed.
+
+[`SeededFixtures`] returns the created user IDs and session tokens, the object keys and
+the inserted rows.
+
+# Example
+
+```rust,no_run
+use serde_json::json;
+use supabase_testcontainers_modules::{
+    Auth, Fixtures, ObjectFixture, Storage, SupabaseStack, UserFixture,
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let stack = SupabaseStack::new()
+        .with_auth(Auth::default())
+        .with_storage(Storage::default())
+        .with_setup_sql("CREATE TABLE public.todos (id serial PRIMARY KEY, owner uuid, task text);")
+        .start()
+        .await?;
+
+    let seeded = Fixtures::new()
+        .with_user(
+            UserFixture::email("alice@example.com")
+                .with_id("6f1d3c1e-2a4b-4c8d-9e0f-1a2b3c4d5e6f")
+                .with_password("password123")
+                .with_user_metadata(json!({ "name": "Alice" })),
+        )
+        .with_object(ObjectFixture::bytes("avatars", "alice.png", vec![0x89, 0x50]))
+        .with_rows(
+            "public.todos",
+            [json!({ "owner": "6f1d3c1e-2a4b-4c8d-9e0f-1a2b3c4d5e6f", "task": "write tests" })],
+        )
+        .apply(&stack)
+        .await?;
+
+    let alice = seeded.user("alice@example.com").unwrap();
+    println!("Alice's token: {:?}", alice.access_token);
+    Ok(())
+}
+```
+*/
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context};
+use serde_json::{json, Value};
+use tokio_postgres::Client;
+
+use crate::db::connect;
+use crate::{IsolatedStack, RunningStack, SharedStack};
+
+/// Content type of objects declared without one
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// A user created through the GoTrue admin API
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UserFixture {
+    /// Fixed user ID; generated by GoTrue when `None`
+    id: Option<String>,
+    /// Email address
+    email: Option<String>,
+    /// Phone number
+    phone: Option<String>,
+    /// Password; the user is signed in when set
+    password: Option<String>,
+    /// Application metadata, only writable with the service role
+    app_metadata: Option<Value>,
+    /// User metadata
+    user_metadata: Option<Value>,
+    /// Identities added for other providers
+    identities: Vec<IdentityFixture>,
+}
+
+impl UserFixture {
+    /// Declares a user with an email address
+    pub fn email(email: impl Into<String>) -> Self {
+        Self {
+            email: Some(email.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Declares a user with a phone number
+    ///
+    /// Signing in with the phone number needs Auth's phone provider enabled
+    /// (`GOTRUE_EXTERNAL_PHONE_ENABLED`).
+    pub fn phone(phone: impl Into<String>) -> Self {
+        Self {
+            phone: Some(phone.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Sets a fixed user ID (a UUID), so rows can reference the user
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Adds a phone number to a user declared by email
+    pub fn with_phone(mut self, phone: impl Into<String>) -> Self {
+        self.phone = Some(phone.into());
+        self
+    }
+
+    /// Sets the password; users with a password are signed in when seeded
+    pub fn with_password(mut self, password: impl Into<String>) -> Self {
+        self.password = Some(password.into());
+        self
+    }
+
+    /// Sets `app_metadata`, e.g. roles checked by RLS policies
+    pub fn with_app_metadata(mut self, metadata: Value) -> Self {
+        self.app_metadata = Some(metadata);
+        self
+    }
+
+    /// Sets `user_metadata`
+    pub fn with_user_metadata(mut self, metadata: Value) -> Self {
+        self.user_metadata = Some(metadata);
+        self
+    }
+
+    /// Adds an identity for another provider, as if the user had signed in with it
+    pub fn with_identity(mut self, identity: IdentityFixture) -> Self {
+        self.identities.push(identity);
+        self
+    }
+
+    /// Returns the body of the admin API's create user request
+    fn admin_body(&self) -> Value {
+        let mut body = json!({});
+        let fields = [
+            ("id", self.id.clone().map(Value::from)),
+            ("email", self.email.clone().map(Value::from)),
+            ("phone", self.phone.clone().map(Value::from)),
+            ("password", self.password.clone().map(Value::from)),
+            ("app_metadata", self.app_metadata.clone()),
+            ("user_metadata", self.user_metadata.clone()),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                body[key] = value;
+            }
+        }
+        if self.email.is_some() {
+            body["email_confirm"] = json!(true);
+        }
+        if self.phone.is_some() {
+            body["phone_confirm"] = json!(true);
+        }
+        body
+    }
+
+    /// Returns the body of the password sign-in request, if the user has a password
+    fn sign_in_body(&self) -> Option<Value> {
+        let password = self.password.as_ref()?;
+        match (&self.email, &self.phone) {
+            (Some(email), _) => Some(json!({ "email": email, "password": password })),
+            (None, Some(phone)) => Some(json!({ "phone": phone, "password": password })),
+            (None, None) => None,
+        }
+    }
+}
+
+/// An identity linked to a seeded user, e.g. a GitHub account
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdentityFixture {
+    /// Provider name, e.g. `github`
+    provider: String,
+    /// The user's ID at the provider
+    provider_id: String,
+    /// Claims from the provider
+    identity_data: Value,
+}
+
+impl IdentityFixture {
+    /// Declares an identity at `provider` with the user's ID there
+    pub fn new(provider: impl Into<String>, provider_id: impl Into<String>) -> Self {
+        Self {
+            provider: provider.into(),
+            provider_id: provider_id.into(),
+            identity_data: json!({}),
+        }
+    }
+
+    /// Sets the claims from the provider; `sub` defaults to the provider ID
+    pub fn with_identity_data(mut self, data: Value) -> Self {
+        self.identity_data = data;
+        self
+    }
+
+    /// Returns the identity data with `sub` and the user's email filled in
+    fn data(&self, email: Option<&str>) -> Value {
+        let mut data = match &self.identity_data {
+            Value::Object(_) => self.identity_data.clone(),
+            _ => json!({}),
+        };
+        if data.get("sub").is_none() {
+            data["sub"] = json!(self.provider_id);
+        }
+        if let (None, Some(email)) = (data.get("email"), email) {
+            data["email"] = json!(email);
+        }
+        data
+    }
+}
+
+/// Where an object's content comes from
+#[derive(Debug, Clone, PartialEq)]
+enum ObjectSource {
+    /// Content given directly
+    Bytes(Vec<u8>),
+    /// Content read from a file when the fixtures are applied
+    File(PathBuf),
+}
+
+/// A file uploaded through the Storage API
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectFixture {
+    /// Bucket ID
+    bucket: String,
+    /// Object path inside the bucket
+    path: String,
+    /// Object content
+    source: ObjectSource,
+    /// MIME type sent with the upload
+    content_type: String,
+}
+
+impl ObjectFixture {
+    /// Declares an object with the given content
+    pub fn bytes(
+        bucket: impl Into<String>,
+        path: impl Into<String>,
+        content: impl Into<Vec<u8>>,
+    ) -> Self {
+        Self {
+            bucket: bucket.into(),
+            path: path.into(),
+            source: ObjectSource::Bytes(content.into()),
+            content_type: DEFAULT_CONTENT_TYPE.to_string(),
+        }
+    }
+
+    /// Declares an object with the content of a local file, read when applied
+    pub fn file(
+        bucket: impl Into<String>,
+        path: impl Into<String>,
+        file: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            bucket: bucket.into(),
+            path: path.into(),
+            source: ObjectSource::File(file.into()),
+            content_type: DEFAULT_CONTENT_TYPE.to_string(),
+        }
+    }
+
+    /// Sets the MIME type (default `application/octet-stream`)
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = content_type.into();
+        self
+    }
+
+    /// Returns the object's content
+    fn content(&self) -> anyhow::Result<Vec<u8>> {
+        match &self.source {
+            ObjectSource::Bytes(bytes) => Ok(bytes.clone()),
+            ObjectSource::File(path) => {
+                fs::read(path).with_context(|| format!("failed to read {}", path.display()))
+            }
+        }
+    }
+}
+
+/// Services and credentials [`Fixtures::apply`] seeds through
+///
+/// Created from a [`RunningStack`], [`SharedStack`] or [`IsolatedStack`], or with
+/// [`FixtureTarget::new`] for services started on their own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixtureTarget {
+    /// Superuser connection string reachable from the host
+    db_url: String,
+    /// API key for the `service_role` role
+    service_role_key: String,
+    /// Auth URL, needed for users
+    auth_url: Option<String>,
+    /// Storage URL, needed for objects
+    storage_url: Option<String>,
+}
+
+impl FixtureTarget {
+    /// Creates a target for the database at `db_url`
+    pub fn new(db_url: impl Into<String>, service_role_key: impl Into<String>) -> Self {
+        Self {
+            db_url: db_url.into(),
+            service_role_key: service_role_key.into(),
+            auth_url: None,
+            storage_url: None,
+        }
+    }
+
+    /// Sets the Auth URL users are created through
+    pub fn with_auth_url(mut self, url: impl Into<String>) -> Self {
+        self.auth_url = Some(url.into());
+        self
+    }
+
+    /// Sets the Storage URL objects are uploaded through
+    pub fn with_storage_url(mut self, url: impl Into<String>) -> Self {
+        self.storage_url = Some(url.into());
+        self
+    }
+}
+
+impl From<&RunningStack> for FixtureTarget {
+    fn from(stack: &RunningStack) -> Self {
+        Self {
+            db_url: stack.db_url().to_string(),
+            service_role_key: stack.service_role_key().to_string(),
+            auth_url: stack.auth_url().map(str::to_string),
+            storage_url: stack.storage_url().map(str::to_string),
+        }
+    }
+}
+
+impl From<&SharedStack> for FixtureTarget {
+    fn from(stack: &SharedStack) -> Self {
+        Self {
+            db_url: stack.db_url().to_string(),
+            service_role_key: stack.service_role_key().to_string(),
+            auth_url: stack.auth_url().map(str::to_string),
+            storage_url: stack.storage_url().map(str::to_string),
+        }
+    }
+}
+
+/// Storage is shared between isolated stacks, so objects cannot be seeded into one
+impl From<&IsolatedStack> for FixtureTarget {
+    fn from(stack: &IsolatedStack) -> Self {
+        Self {
+            db_url: stack.db_url().to_string(),
+            service_role_key: stack.service_role_key().to_string(),
+            auth_url: stack.auth_url().map(str::to_string),
+            storage_url: None,
+        }
+    }
+}
+
+/// Users, objects and rows to seed into a running stack
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Fixtures {
+    /// Users, created first
+    users: Vec<UserFixture>,
+    /// Rows per table, inserted after the users
+    rows: Vec<(String, Vec<Value>)>,
+    /// Objects, uploaded last
+    objects: Vec<ObjectFixture>,
+}
+
+impl Fixtures {
+    /// Creates an empty set of fixtures
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a user
+    pub fn with_user(mut self, user: UserFixture) -> Self {
+        self.users.push(user);
+        self
+    }
+
+    /// Adds a Storage object
+    pub fn with_object(mut self, object: ObjectFixture) -> Self {
+        self.objects.push(object);
+        self
+    }
+
+    /// Adds rows to `table` (optionally schema-qualified), given as JSON objects
+    ///
+    /// Columns missing from a row get `NULL` if another row sets them, their default
+    /// otherwise.
+    pub fn with_rows(
+        mut self,
+        table: impl Into<String>,
+        rows: impl IntoIterator<Item = Value>,
+    ) -> Self {
+        self.rows.push((table.into(), rows.into_iter().collect()));
+        self
+    }
+
+    /// Creates the users, inserts the rows and uploads the objects
+    ///
+    /// # Errors
+    /// Returns an error if users or objects are declared but the target has no Auth or
+    /// Storage, or if a request or insert fails. Fixtures applied before the failure
+    /// are kept.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+    pub async fn apply(&self, target: impl Into<FixtureTarget>) -> anyhow::Result<SeededFixtures> {
+        let target = target.into();
+        let http = reqwest::Client::new();
+        let mut seeded = SeededFixtures::default();
+        // One connection for identities and rows, opened only if either needs it
+        let needs_db =
+            !self.rows.is_empty() || self.users.iter().any(|user| !user.identities.is_empty());
+        let db = match needs_db {
+            true => Some(connect(&target.db_url).await?),
+            false => None,
+        };
+
+        if !self.users.is_empty() {
+            let auth_url = target
+                .auth_url
+                .as_deref()
+                .context("users were declared but the stack has no Auth")?;
+            for user in &self.users {
+                seeded
+                    .users
+                    .push(create_user(&http, db.as_ref(), &target, auth_url, user).await?);
+            }
+        }
+
+        if let Some(client) = db.as_ref().filter(|_| !self.rows.is_empty()) {
+            for (table, rows) in &self.rows {
+                let Some(sql) = insert_rows_sql(table, rows)? else {
+                    continue;
+                };
+                let inserted: Vec<Value> = client
+                    .query(&sql, &[&Value::Array(rows.clone()).to_string()])
+                    .await
+                    .with_context(|| format!("failed to insert rows into {}", table))?
+                    .iter()
+                    .map(|row| serde_json::from_str(row.get::<_, &str>(0)))
+                    .collect::<Result<_, _>>()?;
+                seeded.rows.push(SeededRows {
+                    table: table.clone(),
+                    rows: inserted,
+                });
+            }
+        }
+
+        if !self.objects.is_empty() {
+            let storage_url = target
+                .storage_url
+                .as_deref()
+                .context("objects were declared but the stack has no Storage")?;
+            let mut buckets: Vec<&str> = Vec::new();
+            for object in &self.objects {
+                if !buckets.contains(&object.bucket.as_str()) {
+                    ensure_bucket(&http, &target, storage_url, &object.bucket).await?;
+                    buckets.push(&object.bucket);
+                }
+                seeded
+                    .objects
+                    .push(upload_object(&http, &target, storage_url, object).await?);
+            }
+        }
+
+        Ok(seeded)
+    }
+}
+
+/// A user created by [`Fixtures::apply`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeededUser {
+    /// User ID
+    pub id: String,
+    /// Email address, if declared
+    pub email: Option<String>,
+    /// Phone number, if declared
+    pub phone: Option<String>,
+    /// Access token, if the user has a password
+    pub access_token: Option<String>,
+    /// Refresh token, if the user has a password
+    pub refresh_token: Option<String>,
+    /// The user as returned by the admin API
+    pub user: Value,
+}
+
+/// An object uploaded by [`Fixtures::apply`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeededObject {
+    /// Bucket ID
+    pub bucket: String,
+    /// Object path inside the bucket
+    pub path: String,
+    /// Object ID, if Storage returned one
+    pub id: Option<String>,
+}
+
+/// Rows inserted into one table by [`Fixtures::apply`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeededRows {
+    /// Table name as declared
+    pub table: String,
+    /// The inserted rows, including generated columns
+    pub rows: Vec<Value>,
+}
+
+/// What [`Fixtures::apply`] created
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SeededFixtures {
+    /// Users, in declaration order
+    pub users: Vec<SeededUser>,
+    /// Objects, in declaration order
+    pub objects: Vec<SeededObject>,
+    /// Rows, per declared table
+    pub rows: Vec<SeededRows>,
+}
+
+impl SeededFixtures {
+    /// Returns the user with `email_or_phone`
+    pub fn user(&self, email_or_phone: &str) -> Option<&SeededUser> {
+        self.users.iter().find(|user| {
+            user.email.as_deref() == Some(email_or_phone)
+                || user.phone.as_deref() == Some(email_or_phone)
+        })
+    }
+
+    /// Returns the rows inserted into `table`
+    pub fn rows(&self, table: &str) -> &[Value] {
+        self.rows
+            .iter()
+            .filter(|seeded| seeded.table == table)
+            .map(|seeded| seeded.rows.as_slice())
+            .next()
+            .unwrap_or_default()
+    }
+}
+
+/// Creates `user` through the admin API, adds its identities and signs it in
+///
+/// `db` is connected whenever the user has identities.
+async fn create_user(
+    http: &reqwest::Client,
+    db: Option<&Client>,
+    target: &FixtureTarget,
+    auth_url: &str,
+    user: &UserFixture,
+) -> anyhow::Result<SeededUser> {
+    let label = user
+        .email
+        .as_deref()
+        .or(user.phone.as_deref())
+        .unwrap_or("user without email or phone");
+    let created = send(
+        http.post(format!("{}/admin/users", auth_url))
+            .bearer_auth(&target.service_role_key)
+            .json(&user.admin_body()),
+        "Auth",
+    )
+    .await
+    .with_context(|| format!("failed to create user {}", label))?;
+    let id = created["id"]
+        .as_str()
+        .context("Auth returned a user without an id")?
+        .to_string();
+
+    if let Some(client) = db.filter(|_| !user.identities.is_empty()) {
+        add_identities(client, &id, user)
+            .await
+            .with_context(|| format!("failed to add identities to user {}", label))?;
+    }
+
+    let (access_token, refresh_token) = match user.sign_in_body() {
+        Some(body) => {
+            let session = send(
+                http.post(format!("{}/token?grant_type=password", auth_url))
+                    .json(&body),
+                "Auth",
+            )
+            .await
+            .with_context(|| format!("failed to sign in user {}", label))?;
+            (
+                session["access_token"].as_str().map(str::to_string),
+                session["refresh_token"].as_str().map(str::to_string),
+            )
+        }
+        None => (None, None),
+    };
+
+    Ok(SeededUser {
+        id,
+        email: user.email.clone(),
+        phone: user.phone.clone(),
+        access_token,
+        refresh_token,
+        user: created,
+    })
+}
+
+/// Inserts the user's identities into `auth.identities`
+async fn add_identities(client: &Client, user_id: &str, user: &UserFixture) -> anyhow::Result<()> {
+    for identity in &user.identities {
+        client
+            .execute(
+                "INSERT INTO auth.identities \
+                 (provider_id, user_id, identity_data, provider, last_sign_in_at, created_at, updated_at) \
+                 VALUES ($1, $2::text::uuid, $3::text::jsonb, $4, now(), now(), now())",
+                &[
+                    &identity.provider_id,
+                    &user_id,
+                    &identity.data(user.email.as_deref()).to_string(),
+                    &identity.provider,
+                ],
+            )
+            .await
+            .with_context(|| format!("failed to add {} identity", identity.provider))?;
+    }
+    Ok(())
+}
+
+/// Creates `bucket` as a private bucket unless it exists
+async fn ensure_bucket(
+    http: &reqwest::Client,
+    target: &FixtureTarget,
+    storage_url: &str,
+    bucket: &str,
+) -> anyhow::Result<()> {
+    let exists = http
+        .get(format!("{}/bucket/{}", storage_url, bucket))
+        .bearer_auth(&target.service_role_key)
+        .send()
+        .await
+        .with_context(|| format!("failed to reach Storage at {}", storage_url))?
+        .status()
+        .is_success();
+    if !exists {
+        send(
+            http.post(format!("{}/bucket", storage_url))
+                .bearer_auth(&target.service_role_key)
+                .json(&json!({ "id": bucket, "name": bucket, "public": false })),
+            "Storage",
+        )
+        .await
+        .with_context(|| format!("failed to create bucket {}", bucket))?;
+    }
+    Ok(())
+}
+
+/// Uploads `object`, replacing an existing object at the same path
+async fn upload_object(
+    http: &reqwest::Client,
+    target: &FixtureTarget,
+    storage_url: &str,
+    object: &ObjectFixture,
+) -> anyhow::Result<SeededObject> {
+    let uploaded = send(
+        http.post(format!(
+            "{}/object/{}/{}",
+            storage_url,
+            object.bucket,
+            object.path.trim_start_matches('/')
+        ))
+        .bearer_auth(&target.service_role_key)
+        .header("content-type", &object.content_type)
+        .header("x-upsert", "true")
+        .body(object.content()?),
+        "Storage",
+    )
+    .await
+    .with_context(|| format!("failed to upload {}/{}", object.bucket, object.path))?;
+
+    Ok(SeededObject {
+        bucket: object.bucket.clone(),
+        path: object.path.clone(),
+        id: uploaded["Id"]
+            .as_str()
+            .or(uploaded["id"].as_str())
+            .map(str::to_string),
+    })
+}
+
+/// Sends a request and returns the JSON body (null when empty)
+async fn send(request: reqwest::RequestBuilder, service: &str) -> anyhow::Result<Value> {
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("failed to reach {}", service))?;
+    let status = response.status();
+    let text = response
+        .text()
+        .await
+        .with_context(|| format!("failed to read {} response", service))?;
+    if !status.is_success() {
+        bail!("{} returned {}: {}", service, status, text);
+    }
+    if text.trim().is_empty() {
+        return Ok(Value::Null);
+    }
+    serde_json::from_str(&text).with_context(|| format!("{} returned invalid JSON", service))
+}
+
+/// Returns SQL inserting the JSON array passed as `$1` into `table`
+///
+/// Only the columns the rows mention are inserted, so others keep their defaults.
+/// Returns `None` when there are no rows.
+fn insert_rows_sql(table: &str, rows: &[Value]) -> anyhow::Result<Option<String>> {
+    let mut columns: Vec<&str> = Vec::new();
+    for row in rows {
+        let Value::Object(row) = row else {
+            bail!("rows for {} must be JSON objects, found {}", table, row);
+        };
+        for column in row.keys() {
+            if !columns.contains(&column.as_str()) {
+                columns.push(column);
+            }
+        }
+    }
+    if rows.is_empty() {
+        return Ok(None);
+    }
+
+    let table = table
+        .split('.')
+        .map(quote_identifier)
+        .collect::<Vec<_>>()
+        .join(".");
+    let columns = columns
+        .into_iter()
+        .map(quote_identifier)
+        .collect::<Vec<_>>()
+        .join(", ");
+    Ok(Some(format!(
+        "INSERT INTO {table} AS inserted ({columns}) \
+         SELECT {columns} FROM jsonb_populate_recordset(NULL::{table}, $1::text::jsonb) \
+         RETURNING to_jsonb(inserted.*)::text",
+        table = table,
+        columns = columns
+    )))
+}
+
+/// Quotes `name` as a SQL identifier
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+#[cfg(feature = "stack")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_admin_body() {
+        let user = UserFixture::email("alice@example.com")
+            .with_id("6f1d3c1e-2a4b-4c8d-9e0f-1a2b3c4d5e6f")
+            .with_password("password123")
+            .with_app_metadata(json!({ "role": "admin" }))
+            .with_user_metadata(json!({ "name": "Alice" }));
+
+        assert_eq!(
+            user.admin_body(),
+            json!({
+                "id": "6f1d3c1e-2a4b-4c8d-9e0f-1a2b3c4d5e6f",
+                "email": "alice@example.com",
+                "password": "password123",
+                "app_metadata": { "role": "admin" },
+                "user_metadata": { "name": "Alice" },
+                "email_confirm": true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_phone_user_body() {
+        let user = UserFixture::phone("+15555550100");
+
+        assert_eq!(
+            user.admin_body(),
+            json!({ "phone": "+15555550100", "phone_confirm": true })
+        );
+        assert_eq!(user.sign_in_body(), None);
+        assert_eq!(
+            user.with_password("secret").sign_in_body(),
+            Some(json!({ "phone": "+15555550100", "password": "secret" }))
+        );
+    }
+
+    #[test]
+    fn test_sign_in_prefers_email() {
+        let user = UserFixture::email("bob@example.com")
+            .with_phone("+15555550101")
+            .with_password("secret");
+
+        assert_eq!(
+            user.sign_in_body(),
+            Some(json!({ "email": "bob@example.com", "password": "secret" }))
+        );
+    }
+
+    #[test]
+    fn test_identity_data_defaults() {
+        let identity = IdentityFixture::new("github", "12345");
+        assert_eq!(
+            identity.data(Some("alice@example.com")),
+            json!({ "sub": "12345", "email": "alice@example.com" })
+        );
+
+        let identity = identity.with_identity_data(json!({ "sub": "abc", "user_name": "alice" }));
+        assert_eq!(
+            identity.data(None),
+            json!({ "sub": "abc", "user_name": "alice" })
+        );
+    }
+
+    #[test]
+    fn test_object_content() {
+        let object = ObjectFixture::bytes("avatars", "a.txt", "hello");
+        assert_eq!(object.content().unwrap(), b"hello");
+        assert_eq!(object.content_type, DEFAULT_CONTENT_TYPE);
+
+        let missing = ObjectFixture::file("avatars", "b.txt", "/nonexistent/b.txt")
+            .with_content_type("text/plain");
+        assert_eq!(missing.content_type, "text/plain");
+        let error = missing.content().unwrap_err().to_string();
+        assert!(error.contains("/nonexistent/b.txt"), "{}", error);
+    }
+
+    #[test]
+    fn test_insert_rows_sql() {
+        let sql = insert_rows_sql(
+            "public.todos",
+            &[json!({ "task": "a" }), json!({ "task": "b", "done": true })],
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(
+            sql,
+            "INSERT INTO \"public\".\"todos\" AS inserted (\"task\", \"done\") \
+             SELECT \"task\", \"done\" FROM jsonb_populate_recordset(NULL::\"public\".\"todos\", $1::text::jsonb) \
+             RETURNING to_jsonb(inserted.*)::text"
+        );
+        assert_eq!(insert_rows_sql("todos", &[]).unwrap(), None);
+        assert!(insert_rows_sql("todos", &[json!([1, 2])]).is_err());
+    }
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!(quote_identifier("odd\"name"), "\"odd\"\"name\"");
+    }
+
+    #[test]
+    fn test_seeded_lookups() {
+        let seeded = SeededFixtures {
+            users: vec![SeededUser {
+                id: "1".to_string(),
+                email: None,
+                phone: Some("+15555550100".to_string()),
+                access_token: None,
+                refresh_token: None,
+                user: Value::Null,
+            }],
+            objects: Vec::new(),
+            rows: vec![SeededRows {
+                table: "todos".to_string(),
+                rows: vec![json!({ "id": 1 })],
+            }],
+        };
+
+        assert_eq!(
+            seeded.user("+15555550100").map(|user| user.id.as_str()),
+            Some("1")
+        );
+        assert!(seeded.user("alice@example.com").is_none());
+        assert_eq!(seeded.rows("todos"), [json!({ "id": 1 })]);
+        assert!(seeded.rows("other").is_empty());
+    }
+}
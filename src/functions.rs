@@ -68,10 +68,13 @@ use testcontainers_modules::testcontainers::core::{
 };
 use testcontainers_modules::testcontainers::{Image, TestcontainersError};
 
+use crate::overrides::{image_name, image_tag, pin_digest, with_registry};
+use crate::SupabaseVersion;
+
 /// Default image name for Supabase Edge Functions
 const NAME: &str = "supabase/edge-runtime";
 /// Default image tag version
-const TAG: &str = "v1.67.4";
+const TAG: &str = crate::consts::FUNCTIONS_TAG;
 /// Default port for Supabase Edge Functions
 pub const FUNCTIONS_PORT: u16 = 9000;
 /// Default path for functions inside the container
@@ -106,6 +109,8 @@ const DEFAULT_MAIN_SERVICE_PATH: &str = "/home/deno/functions";
 pub struct Functions {
     /// Environment variables to be passed to the container
     env_vars: BTreeMap<String, String>,
+    /// Docker image name
+    name: String,
     /// Docker image tag version
     tag: String,
     /// Path to the main service (functions directory) inside the container
@@ -229,6 +234,27 @@ impl Functions {
         self
     }
 
+    /// Sets the Docker image name, e.g. a fork or a copy in a private registry.
+    ///
+    /// `SUPABASE_TC_REGISTRY` still prefixes names without a registry.
+    pub fn with_image_name(mut self, name: impl Into<String>) -> Self {
+        self.name = with_registry(&name.into());
+        self
+    }
+
+    /// Pins the image to a digest such as `sha256:4c1e...`.
+    ///
+    /// Docker pulls the digest and ignores the tag; [`Functions::with_tag`] removes the pin.
+    pub fn with_digest(mut self, digest: impl AsRef<str>) -> Self {
+        self.tag = pin_digest(&self.tag, digest.as_ref());
+        self
+    }
+
+    /// Sets the tag from a [`SupabaseVersion`].
+    pub fn with_version(self, version: &SupabaseVersion) -> Self {
+        self.with_tag(version.functions())
+    }
+
     /// Adds a custom environment variable.
     ///
     /// Use this for Edge Functions configuration options not covered by other methods,
@@ -251,7 +277,8 @@ impl Default for Functions {
 
         Self {
             env_vars,
-            tag: TAG.to_string(),
+            name: image_name("FUNCTIONS", NAME),
+            tag: image_tag("FUNCTIONS", TAG),
             main_service_path: DEFAULT_MAIN_SERVICE_PATH.to_string(),
         }
     }
@@ -259,7 +286,7 @@ impl Default for Functions {
 
 impl Image for Functions {
     fn name(&self) -> &str {
-        NAME
+        &self.name
     }
 
     fn tag(&self) -> &str {
@@ -267,7 +294,9 @@ impl Image for Functions {
     }
 
     fn ready_conditions(&self) -> Vec<WaitFor> {
-        // Edge runtime logs when the server is ready to accept connections
+        // Edge runtime logs when the server is ready to accept connections. There is no
+        // health route to probe instead: every request is handled by the user's main
+        // function, so no `ReadyCheck` is offered.
         vec![WaitFor::message_on_stdout("Listening on")]
     }
 
@@ -333,6 +362,35 @@ mod tests {
         assert_eq!(functions.tag(), TAG);
     }
 
+    #[test]
+    fn test_default_tag_matches_tested_version() {
+        assert_eq!(
+            Functions::default().tag(),
+            SupabaseVersion::tested().functions()
+        );
+    }
+
+    #[test]
+    fn test_with_version_sets_tag() {
+        let version = SupabaseVersion::tested().with_functions("v1.60.0");
+        assert_eq!(Functions::default().with_version(&version).tag(), "v1.60.0");
+    }
+
+    #[test]
+    fn test_with_image_name() {
+        let functions = Functions::default().with_image_name("ghcr.io/acme/functions");
+        assert_eq!(functions.name(), "ghcr.io/acme/functions");
+    }
+
+    #[test]
+    fn test_with_digest_pins_tag() {
+        let functions = Functions::default()
+            .with_tag("v1")
+            .with_digest("sha256:abc");
+        assert_eq!(functions.tag(), "v1@sha256:abc");
+        assert_eq!(functions.with_tag("v2").tag(), "v2");
+    }
+
     #[test]
     fn test_functions_port_constant() {
         assert_eq!(FUNCTIONS_PORT, 9000);
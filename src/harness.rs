@@ -0,0 +1,365 @@
+/*! Runtime support for the [`supabase_test`](crate::supabase_test) attribute.
+
+A `#[supabase_test]` function becomes a `#[tokio::test]` that receives a [`TestStack`]:
+
+```rust,no_run
+use supabase_testcontainers_modules::{supabase_test, TestStack};
+
+#[supabase_test(services = [auth, postgrest], migrations = "supabase/migrations")]
+async fn lists_todos(stack: &TestStack) -> anyhow::Result<()> {
+    let todos = reqwest::get(format!("{}/todos", stack.rest_url().unwrap())).await?;
+    assert!(todos.status().is_success());
+    Ok(())
+}
+```
+
+# Architecture
+
+1. Every annotated test in a binary shares one [`SharedStack`], started by the first
+   test with the services and migrations it names. All annotated tests of a binary must
+   name the same ones; a test naming others fails with both configurations.
+2. Before the test body runs, the test is separated from the others by its isolation:
+   - `reset` (default): the test runs alone, after [`SharedStack::reset`]
+   - `database`: the test gets an [`IsolatedStack`] with a copy of the snapshot and its
+     own Auth and PostgREST, and runs in parallel with other `database` and `none` tests
+   - `none`: the test runs in parallel with other `database` and `none` tests on the
+     shared state as it is
+
+   `database` and `none` tests never overlap a `reset` test, whose reset would wipe the
+   shared state under them; `database` tests still share Storage.
+3. If Docker is unavailable, the test prints why and passes without running, unless
+   [`REQUIRE_DOCKER_ENV`] is set (e.g. in CI), in which case it fails.
+
+# Arguments
+
+| Argument | Values | Default |
+|----------|--------|---------|
+| `services` | `[auth, postgrest, storage]` (`rest` for `postgrest`) | `[]` (PostgreSQL only) |
+| `migrations` | Directory relative to the crate root | none |
+| `isolation` | `reset`, `database`, `none` | `reset` |
+
+The function may take no parameter, a `&TestStack` or a `TestStack`, and return `()` or
+a `Result` whose `Ok` value implements [`Default`].
+*/
+
+use std::sync::OnceLock;
+
+use anyhow::Context;
+use testcontainers::core::error::ClientError;
+use testcontainers::TestcontainersError;
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{
+    Auth, IsolatedStack, Isolation, MigrationRunner, PostgREST, SharedStack, Storage, SupabaseStack,
+};
+
+/// Environment variable that turns skipped tests into failures when Docker is unavailable
+pub const REQUIRE_DOCKER_ENV: &str = "SUPABASE_TC_REQUIRE_DOCKER";
+
+/// Configuration of the first test that started the shared stack
+static STARTED_CONFIG: OnceLock<String> = OnceLock::new();
+/// Held for writing by tests with [`TestIsolation::Reset`] and for reading by the others
+static SERIAL: RwLock<()> = RwLock::const_new(());
+
+/// A test's hold on [`SERIAL`]
+enum SerialGuard {
+    /// Held by a test with [`TestIsolation::Reset`]
+    Exclusive(#[allow(dead_code)] RwLockWriteGuard<'static, ()>),
+    /// Held by a test that may run in parallel with others
+    Shared(#[allow(dead_code)] RwLockReadGuard<'static, ()>),
+}
+
+/// How a test is separated from the others sharing the stack
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestIsolation {
+    /// Run alone, after a reset
+    Reset,
+    /// Run on a copy of the snapshot with per-test services
+    Database,
+    /// Run on the shared state
+    None,
+}
+
+/// What a `#[supabase_test]` function asks for; built by the macro
+#[doc(hidden)]
+#[derive(Debug, Clone)]
+pub struct TestConfig {
+    /// Full path of the test function
+    pub test: &'static str,
+    /// Services besides PostgreSQL: `auth`, `postgrest` or `storage`
+    pub services: &'static [&'static str],
+    /// Absolute migrations directory, if any
+    pub migrations: Option<&'static str>,
+    /// How the test is separated from the others
+    pub isolation: TestIsolation,
+}
+
+impl TestConfig {
+    /// Returns what must be equal for tests to share a stack
+    fn stack_key(&self) -> String {
+        format!(
+            "services = [{}], migrations = {}",
+            self.services.join(", "),
+            self.migrations.unwrap_or("none")
+        )
+    }
+
+    /// Returns the stack the config describes
+    fn stack(&self) -> anyhow::Result<SupabaseStack> {
+        let mut stack = SupabaseStack::new();
+        for service in self.services {
+            stack = match *service {
+                "auth" => stack.with_auth(Auth::default()),
+                "postgrest" => stack.with_postgrest(PostgREST::default()),
+                "storage" => stack.with_storage(Storage::default()),
+                other => anyhow::bail!("unknown service '{}'", other),
+            };
+        }
+        if let Some(dir) = self.migrations {
+            stack = stack.with_migrations(MigrationRunner::from_dir(dir)?);
+        }
+        Ok(stack)
+    }
+}
+
+/// Return types of `#[supabase_test]` functions
+#[doc(hidden)]
+pub trait TestReturn {
+    /// Returns the value a skipped test returns
+    fn skipped() -> Self;
+}
+
+impl TestReturn for () {
+    fn skipped() -> Self {}
+}
+
+impl<T: Default, E> TestReturn for Result<T, E> {
+    fn skipped() -> Self {
+        Ok(T::default())
+    }
+}
+
+/// The stack handed to a `#[supabase_test]` function
+///
+/// Accessors return the isolated values with `isolation = database`, and the shared
+/// stack's otherwise. Dropping it cleans up the isolation and lets the next test with
+/// `isolation = reset` run.
+pub struct TestStack {
+    /// The stack shared by the binary's tests
+    shared: &'static SharedStack,
+    /// This test's isolated state, with `isolation = database`
+    isolated: Option<IsolatedStack>,
+    /// Held while the test runs, keeping resets and other tests apart
+    _serial: SerialGuard,
+}
+
+impl TestStack {
+    /// Returns the stack shared by the binary's tests
+    pub fn shared(&self) -> &'static SharedStack {
+        self.shared
+    }
+
+    /// Returns this test's isolated state, with `isolation = database`
+    pub fn isolated(&self) -> Option<&IsolatedStack> {
+        self.isolated.as_ref()
+    }
+
+    /// Returns the JWT secret shared by all services
+    pub fn jwt_secret(&self) -> &str {
+        self.shared.jwt_secret()
+    }
+
+    /// Returns the API key for the `anon` role
+    pub fn anon_key(&self) -> &str {
+        match &self.isolated {
+            Some(isolated) => isolated.anon_key(),
+            None => self.shared.anon_key(),
+        }
+    }
+
+    /// Returns the API key for the `service_role` role
+    pub fn service_role_key(&self) -> &str {
+        match &self.isolated {
+            Some(isolated) => isolated.service_role_key(),
+            None => self.shared.service_role_key(),
+        }
+    }
+
+    /// Returns a superuser connection string for this test's data, reachable from the host
+    pub fn db_url(&self) -> &str {
+        match &self.isolated {
+            Some(isolated) => isolated.db_url(),
+            None => self.shared.db_url(),
+        }
+    }
+
+    /// Returns the Auth URL for this test, if Auth is part of the stack
+    pub fn auth_url(&self) -> Option<&str> {
+        match &self.isolated {
+            Some(isolated) => isolated.auth_url(),
+            None => self.shared.auth_url(),
+        }
+    }
+
+    /// Returns the PostgREST URL for this test, if PostgREST is part of the stack
+    pub fn rest_url(&self) -> Option<&str> {
+        match &self.isolated {
+            Some(isolated) => isolated.rest_url(),
+            None => self.shared.rest_url(),
+        }
+    }
+
+    /// Returns the Storage URL, if Storage is part of the stack
+    ///
+    /// Storage is shared even with `isolation = database`; use distinct bucket names.
+    pub fn storage_url(&self) -> Option<&str> {
+        self.shared.storage_url()
+    }
+}
+
+/// Prepares the stack for a test; `None` if the test is skipped
+///
+/// # Panics
+/// Panics if the stack cannot be started or isolated, if the test's configuration
+/// differs from the one the stack was started with, or if Docker is unavailable and
+/// [`REQUIRE_DOCKER_ENV`] is set.
+#[doc(hidden)]
+pub async fn setup(config: TestConfig) -> Option<TestStack> {
+    let key = config.stack_key();
+    let started = STARTED_CONFIG.get_or_init(|| key.clone());
+    if *started != key {
+        panic!(
+            "{} asks for a stack with {}, but the tests in this binary share one started with {}; \
+             give all #[supabase_test] functions in a test binary the same services and migrations",
+            config.test, key, started
+        );
+    }
+
+    match prepare(&config).await {
+        Ok(stack) => Some(stack),
+        Err(e) if docker_unavailable(&e) && !docker_required() => {
+            eprintln!(
+                "skipping {}: Docker is unavailable ({:#}); set {}=1 to fail instead",
+                config.test, e, REQUIRE_DOCKER_ENV
+            );
+            None
+        }
+        Err(e) => panic!("{}: {:#}", config.test, e),
+    }
+}
+
+/// Starts or joins the shared stack and isolates the test
+async fn prepare(config: &TestConfig) -> anyhow::Result<TestStack> {
+    let shared = config.stack()?.shared().await?;
+    let (serial, isolated) = match config.isolation {
+        TestIsolation::Reset => {
+            let serial = SerialGuard::Exclusive(SERIAL.write().await);
+            shared
+                .reset()
+                .await
+                .context("failed to reset the stack before the test")?;
+            (serial, None)
+        }
+        TestIsolation::Database => {
+            // The isolated copy still uses the shared Storage, which a reset empties
+            let serial = SerialGuard::Shared(SERIAL.read().await);
+            (serial, Some(shared.isolate(Isolation::Database).await?))
+        }
+        TestIsolation::None => (SerialGuard::Shared(SERIAL.read().await), None),
+    };
+    Ok(TestStack {
+        shared,
+        isolated,
+        _serial: serial,
+    })
+}
+
+/// Returns whether `error` says Docker cannot be reached
+///
+/// The client fails to initialize when the socket is missing; when the daemon does not
+/// answer, the first request fails to connect.
+fn docker_unavailable(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        let client = match cause.downcast_ref::<TestcontainersError>() {
+            Some(TestcontainersError::Client(client)) => client,
+            _ => match cause.downcast_ref::<ClientError>() {
+                Some(client) => client,
+                None => return false,
+            },
+        };
+        matches!(client, ClientError::Init(_))
+            || client.to_string().to_lowercase().contains("connect")
+    })
+}
+
+/// Returns whether [`REQUIRE_DOCKER_ENV`] asks for failures instead of skips
+fn docker_required() -> bool {
+    std::env::var(REQUIRE_DOCKER_ENV).is_ok_and(|value| !matches!(value.as_str(), "" | "0"))
+}
+
+#[cfg(test)]
+#[cfg(feature = "macros")]
+mod tests {
+    use super::*;
+    use testcontainers::core::error::ConfigurationError;
+
+    fn config(services: &'static [&'static str]) -> TestConfig {
+        TestConfig {
+            test: "tests::example",
+            services,
+            migrations: None,
+            isolation: TestIsolation::Reset,
+        }
+    }
+
+    #[test]
+    fn test_stack_key() {
+        let mut config = config(&["auth", "postgrest"]);
+        assert_eq!(
+            config.stack_key(),
+            "services = [auth, postgrest], migrations = none"
+        );
+
+        config.migrations = Some("/app/supabase/migrations");
+        config.isolation = TestIsolation::Database;
+        assert_eq!(
+            config.stack_key(),
+            "services = [auth, postgrest], migrations = /app/supabase/migrations"
+        );
+    }
+
+    #[test]
+    fn test_unknown_service() {
+        let error = config(&["realtime"]).stack().unwrap_err().to_string();
+
+        assert_eq!(error, "unknown service 'realtime'");
+    }
+
+    #[test]
+    fn test_skipped_values() {
+        <() as TestReturn>::skipped();
+        assert_eq!(<anyhow::Result<()>>::skipped().unwrap(), ());
+        assert_eq!(<Result<u32, String>>::skipped(), Ok(0));
+    }
+
+    #[test]
+    fn test_docker_unavailable() {
+        let socket_missing = std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "Socket not found: /var/run/docker.sock",
+        );
+        let init = TestcontainersError::Client(ClientError::Init(socket_missing.into()));
+        let error = anyhow::Error::from(init).context("failed to start PostgreSQL container");
+        assert!(docker_unavailable(&error));
+
+        let config =
+            ClientError::Configuration(ConfigurationError::InvalidDockerHost("tcp://".to_string()));
+        assert!(!docker_unavailable(&anyhow::Error::from(
+            TestcontainersError::Client(config)
+        )));
+        assert!(!docker_unavailable(&anyhow::anyhow!(
+            "setup SQL script 1 failed"
+        )));
+    }
+}
@@ -0,0 +1,80 @@
+//! HS256 tokens for the Supabase API keys.
+//!
+//! Supabase's anon and service role keys are JWTs signed with the project's JWT
+//! secret whose `role` claim selects the PostgreSQL role PostgREST and Storage switch
+//! to. [`role_jwt`] creates such a key for any role.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+/// Issuer of the keys, as in the Supabase CLI's demo keys
+const ISSUER: &str = "supabase-demo";
+/// Expiry of the keys (2032-11-12), as in the Supabase CLI's demo keys
+const EXPIRES_AT: u64 = 1983812996;
+
+/// Creates a long-lived API key for `role` signed with `secret`
+///
+/// # Example
+///
+/// ```rust
+/// use supabase_testcontainers_modules::role_jwt;
+///
+/// let anon_key = role_jwt("anon", "super-secret-jwt-token-for-testing-at-least-32-chars");
+/// assert_eq!(anon_key.split('.').count(), 3);
+/// ```
+pub fn role_jwt(role: &str, secret: &str) -> String {
+    sign_jwt(
+        &json!({ "iss": ISSUER, "role": role, "exp": EXPIRES_AT }),
+        secret,
+    )
+}
+
+/// Signs `claims` as an HS256 JWT with `secret`
+pub fn sign_jwt(claims: &Value, secret: &str) -> String {
+    let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"HS256","typ":"JWT"}"#);
+    let payload = URL_SAFE_NO_PAD.encode(claims.to_string());
+    let message = format!("{}.{}", header, payload);
+
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(message.as_bytes());
+    let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+
+    format!("{}.{}", message, signature)
+}
+
+#[cfg(test)]
+#[cfg(feature = "stack")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_jwt_matches_known_token() {
+        // Reference token computed independently (claims serialize with sorted keys)
+        let token = sign_jwt(
+            &json!({ "sub": "1234567890", "name": "John Doe", "iat": 1516239022 }),
+            "your-256-bit-secret",
+        );
+        assert_eq!(
+            token,
+            "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.\
+             eyJpYXQiOjE1MTYyMzkwMjIsIm5hbWUiOiJKb2huIERvZSIsInN1YiI6IjEyMzQ1Njc4OTAifQ.\
+             fdOPQ05ZfRhkST2-rIWgUpbqUsVhkkNVNcuG7Ki0s-8"
+        );
+    }
+
+    #[test]
+    fn test_role_jwt_claims() {
+        let token = role_jwt("service_role", "secret");
+        let payload = token.split('.').nth(1).unwrap();
+        let claims: Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).unwrap())
+            .expect("payload should be JSON");
+
+        assert_eq!(claims["role"], "service_role");
+        assert_eq!(claims["iss"], ISSUER);
+        assert_eq!(claims["exp"], EXPIRES_AT);
+    }
+}
@@ -4,38 +4,158 @@ This crate provides utilities for setting up and managing Supabase services
 in a containerized environment, primarily for testing purposes.
 */
 
+/// Reports a failure that cannot be returned, e.g. from a background task or `Drop`
+///
+/// Emits a `tracing` event at `$level` with the `tracing` feature; prints to stderr
+/// otherwise.
+#[allow(unused_macros)]
+macro_rules! report {
+    ($level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "tracing")]
+        ::tracing::$level!($($arg)+);
+        #[cfg(not(feature = "tracing"))]
+        eprintln!($($arg)+);
+    }};
+}
+
 #[cfg(feature = "analytics")]
-pub use analytics::{Analytics, ANALYTICS_PORT};
+pub use analytics::{
+    Analytics, AnalyticsClient, AnalyticsEndpoint, AnalyticsSource, QueryLanguage, ANALYTICS_PORT,
+    ANALYTICS_QUERY_TIMEOUT,
+};
+#[cfg(feature = "stack")]
+pub use artifacts::{ArtifactsGuard, ARTIFACTS_DIR_ENV};
 #[cfg(feature = "auth")]
 pub use auth::{Auth, AUTH_PORT};
+#[cfg(all(feature = "blocking", feature = "stack"))]
+pub use blocking::BlockingStack;
 #[cfg(feature = "const")]
 pub use consts::*;
+#[cfg(feature = "stack")]
+pub use fixtures::{
+    FixtureTarget, Fixtures, IdentityFixture, ObjectFixture, SeededFixtures, SeededObject,
+    SeededRows, SeededUser, UserFixture,
+};
 #[cfg(feature = "functions")]
 pub use functions::{Functions, FUNCTIONS_PORT};
 #[cfg(feature = "graphql")]
-pub use graphql::{GraphQL, GRAPHQL_PORT};
+pub use graphql::{
+    introspection_to_sdl, GraphQL, GraphQLClient, GraphQLDirective, GraphQLEndpoint, GraphQLError,
+    GraphQLRequest, GraphQLResponse, GRAPHQL_PORT,
+};
+#[cfg(feature = "macros")]
+pub use harness::{TestStack, REQUIRE_DOCKER_ENV};
+#[cfg(feature = "stack")]
+pub use isolation::{IsolatedStack, Isolation};
+#[cfg(feature = "stack")]
+pub use jwt::{role_jwt, sign_jwt};
+#[cfg(feature = "tracing")]
+pub use logs::{forward_logs, LogForwarder};
+#[cfg(feature = "stack")]
+pub use migrations::{Migration, MigrationRunner};
+#[cfg(feature = "const")]
+pub use overrides::REGISTRY_ENV;
+#[cfg(feature = "postgres_testcontainer")]
+pub use postgres::{LogicalReplication, REALTIME_PUBLICATION};
 #[cfg(feature = "postgrest")]
-pub use postgrest::{PostgREST, POSTGREST_PORT};
+pub use postgrest::{
+    OpenApiMode, PostgREST, PostgRESTMetrics, TxEnd, POSTGREST_ADMIN_PORT, POSTGREST_PORT,
+    POSTGREST_RELOAD_TIMEOUT,
+};
+#[cfg(feature = "project")]
+pub use project::SupabaseProject;
+#[cfg(feature = "http")]
+pub use ready::ReadyCheck;
 #[cfg(feature = "realtime")]
 pub use realtime::{Realtime, REALTIME_PORT};
+#[cfg(feature = "reuse")]
+pub use reuse::{Reusable, REUSE_HASH_LABEL};
+#[cfg(feature = "stack")]
+pub use shared::SharedStack;
+#[cfg(feature = "stack")]
+pub use snapshot::{DatabaseSnapshot, SnapshotStrategy};
+#[cfg(feature = "stack")]
+pub use stack::{RunningStack, StackService, SupabaseStack};
 #[cfg(feature = "storage")]
 pub use storage::{Storage, STORAGE_PORT};
+#[cfg(feature = "macros")]
+pub use supabase_testcontainers_macros::supabase_test;
+#[cfg(feature = "toxiproxy")]
+pub use toxiproxy::{
+    StackLink, Toxic, ToxicStream, Toxiproxy, ToxiproxyClient, ToxiproxyProxy, TOXIPROXY_API_PORT,
+};
+#[cfg(feature = "vector")]
+pub use vector::{LogSource, Vector, VECTOR_API_PORT};
+#[cfg(feature = "const")]
+pub use version::SupabaseVersion;
 
 #[cfg(feature = "analytics")]
 mod analytics;
+#[cfg(feature = "stack")]
+mod artifacts;
 #[cfg(feature = "auth")]
 mod auth;
+#[cfg(feature = "blocking")]
+mod blocking;
 #[cfg(feature = "const")]
 mod consts;
+#[cfg(any(
+    feature = "auth",
+    feature = "postgrest",
+    feature = "postgres_testcontainer"
+))]
+mod db;
 #[cfg(feature = "error")]
 mod error;
+#[cfg(feature = "stack")]
+mod fixtures;
 #[cfg(feature = "functions")]
 mod functions;
 #[cfg(feature = "graphql")]
 mod graphql;
+#[cfg(feature = "macros")]
+mod harness;
+#[cfg(feature = "stack")]
+mod isolation;
+#[cfg(feature = "stack")]
+mod jwt;
+#[cfg(feature = "tracing")]
+mod logs;
+#[cfg(feature = "stack")]
+mod migrations;
+#[cfg(feature = "const")]
+mod overrides;
+#[cfg(feature = "postgres_testcontainer")]
+mod postgres;
 #[cfg(feature = "postgrest")]
 mod postgrest;
+#[cfg(feature = "project")]
+mod project;
+#[cfg(feature = "http")]
+mod ready;
 #[cfg(feature = "realtime")]
 mod realtime;
+#[cfg(feature = "reuse")]
+mod reuse;
+#[cfg(feature = "stack")]
+mod shared;
+#[cfg(feature = "stack")]
+mod snapshot;
+#[cfg(feature = "stack")]
+mod stack;
 #[cfg(feature = "storage")]
 mod storage;
+#[cfg(feature = "toxiproxy")]
+mod toxiproxy;
+#[cfg(feature = "vector")]
+mod vector;
+#[cfg(feature = "const")]
+mod version;
+
+/// Paths the `#[supabase_test]` expansion refers to; not part of the public API
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::harness::{setup, TestConfig, TestIsolation, TestReturn};
+    pub use tokio;
+}
@@ -0,0 +1,237 @@
+/*! Container log lines as `tracing` events.
+
+With the `tracing` feature, every line a container writes becomes a `tracing` event, so
+service logs show up in the test's subscriber next to its own events. A
+[`SupabaseStack`](crate::SupabaseStack) forwards the logs of all its containers; other
+containers forward theirs through [`forward_logs`].
+
+Events have the target `supabase_testcontainers_modules::logs` and the fields:
+
+- `service`: the service name, such as `auth` or `rest`
+- `container_id`: the Docker container ID
+- `stream`: `stdout` or `stderr`
+- `message`: the line, or its `msg`/`message` field for JSON lines
+
+The level of JSON lines comes from their `level` field: names such as `"warning"` (as
+written by GoTrue) or pino's numbers (as written by Storage). Other lines are `INFO`.
+
+Failures the crate used to print to stderr, such as a dropped database connection,
+are `ERROR` events, and the bootstrap steps (starting a stack, schema setup, migrations,
+snapshots, fixtures) run in spans.
+
+# Example
+
+```rust,no_run
+use supabase_testcontainers_modules::{forward_logs, Auth};
+use testcontainers::runners::AsyncRunner;
+
+#[tokio::test]
+async fn auth_logs() -> anyhow::Result<()> {
+    // ... install a tracing subscriber ...
+    let auth = Auth::default().start().await?;
+    let _logs = forward_logs(&auth, "auth");
+
+    // ... GoTrue's log lines are now tracing events ...
+    Ok(())
+}
+```
+*/
+
+use std::borrow::Cow;
+use std::pin::Pin;
+
+use serde_json::Value;
+use testcontainers_modules::testcontainers::{ContainerAsync, Image};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+use tokio::task::JoinHandle;
+use tracing::Level;
+
+/// Forwards a container's log lines as `tracing` events until the handle is dropped
+///
+/// Must be called within a Tokio runtime. Forwarding ends when the container stops.
+pub fn forward_logs<I: Image>(container: &ContainerAsync<I>, service: &str) -> LogForwarder {
+    let tasks = [
+        ("stdout", container.stdout(true)),
+        ("stderr", container.stderr(true)),
+    ]
+    .map(|(stream, reader)| {
+        tokio::spawn(forward(
+            reader,
+            service.to_string(),
+            container.id().to_string(),
+            stream,
+        ))
+    });
+    LogForwarder { tasks }
+}
+
+/// Forwards a container's logs; returned by [`forward_logs`]
+///
+/// Dropping it stops the forwarding.
+#[must_use = "forwarding stops when the handle is dropped"]
+#[derive(Debug)]
+pub struct LogForwarder {
+    /// Tasks reading stdout and stderr
+    tasks: [JoinHandle<()>; 2],
+}
+
+impl Drop for LogForwarder {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Emits each line of `reader` until the stream ends
+async fn forward(
+    mut reader: Pin<Box<dyn AsyncBufRead + Send>>,
+    service: String,
+    container_id: String,
+    stream: &'static str,
+) {
+    let mut buffer = Vec::new();
+    // Errors end the stream like EOF: the container was stopped or removed
+    while let Ok(read) = reader.read_until(b'\n', &mut buffer).await {
+        if read == 0 {
+            break;
+        }
+        let line = String::from_utf8_lossy(&buffer);
+        emit(
+            &service,
+            &container_id,
+            stream,
+            line.trim_end_matches(['\r', '\n']),
+        );
+        buffer.clear();
+    }
+}
+
+/// Emits a container's log line as a `tracing` event
+pub(crate) fn emit(service: &str, container_id: &str, stream: &str, line: &str) {
+    let (level, message) = parse_line(line);
+    macro_rules! event {
+        ($level:expr) => {
+            tracing::event!(
+                target: "supabase_testcontainers_modules::logs",
+                $level,
+                service,
+                container_id,
+                stream,
+                "{}",
+                message
+            )
+        };
+    }
+    // Event levels must be constant
+    match level {
+        Level::ERROR => event!(Level::ERROR),
+        Level::WARN => event!(Level::WARN),
+        Level::INFO => event!(Level::INFO),
+        Level::DEBUG => event!(Level::DEBUG),
+        _ => event!(Level::TRACE),
+    }
+}
+
+/// Returns the level and message of a log line
+///
+/// JSON objects with a `level` field are parsed; their message is the `msg` or
+/// `message` field, or the whole line without one. Other lines are `INFO`.
+fn parse_line(line: &str) -> (Level, Cow<'_, str>) {
+    let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(line) else {
+        return (Level::INFO, Cow::Borrowed(line));
+    };
+    let level = match fields.get("level") {
+        Some(Value::String(name)) => level_from_name(name),
+        Some(Value::Number(number)) => number.as_u64().map(level_from_pino),
+        _ => None,
+    };
+    let message = ["msg", "message"]
+        .iter()
+        .find_map(|key| fields.get(*key).and_then(Value::as_str))
+        .map_or(Cow::Borrowed(line), |message| {
+            Cow::Owned(message.to_string())
+        });
+    (level.unwrap_or(Level::INFO), message)
+}
+
+/// Returns the level named `name`, as written by GoTrue (logrus) and others
+fn level_from_name(name: &str) -> Option<Level> {
+    match name.to_ascii_lowercase().as_str() {
+        "panic" | "fatal" | "error" | "err" => Some(Level::ERROR),
+        "warning" | "warn" => Some(Level::WARN),
+        "info" | "notice" => Some(Level::INFO),
+        "debug" => Some(Level::DEBUG),
+        "trace" => Some(Level::TRACE),
+        _ => None,
+    }
+}
+
+/// Returns the level of a pino level number, as written by Storage
+fn level_from_pino(number: u64) -> Level {
+    match number {
+        50.. => Level::ERROR,
+        40..=49 => Level::WARN,
+        30..=39 => Level::INFO,
+        20..=29 => Level::DEBUG,
+        _ => Level::TRACE,
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "tracing")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gotrue_line() {
+        let line = r#"{"component":"api","level":"warning","msg":"Request rate limited","time":"2024-01-01T00:00:00Z"}"#;
+
+        assert_eq!(
+            parse_line(line),
+            (Level::WARN, "Request rate limited".into())
+        );
+    }
+
+    #[test]
+    fn test_parse_storage_line() {
+        let line = r#"{"level":50,"time":"2024-01-01T00:00:00Z","msg":"Bucket not found"}"#;
+
+        assert_eq!(parse_line(line), (Level::ERROR, "Bucket not found".into()));
+    }
+
+    #[test]
+    fn test_parse_line_without_message() {
+        let line = r#"{"level":"debug","event":"connected"}"#;
+
+        assert_eq!(parse_line(line), (Level::DEBUG, line.into()));
+    }
+
+    #[test]
+    fn test_parse_escaped_message() {
+        let line = r#"{"level":"info","msg":"quoted \"name\""}"#;
+
+        assert_eq!(parse_line(line), (Level::INFO, r#"quoted "name""#.into()));
+    }
+
+    #[test]
+    fn test_parse_plain_lines() {
+        let line = "2024-01-01 00:00:00.000 UTC [1] LOG:  database system is ready";
+
+        assert_eq!(parse_line(line), (Level::INFO, line.into()));
+        assert_eq!(parse_line(r#"["not", "an object"]"#).0, Level::INFO);
+        assert_eq!(parse_line(r#"{"level":"verbose"}"#).0, Level::INFO);
+    }
+
+    #[test]
+    fn test_level_names_and_numbers() {
+        assert_eq!(level_from_name("FATAL"), Some(Level::ERROR));
+        assert_eq!(level_from_name("warn"), Some(Level::WARN));
+        assert_eq!(level_from_name("trace"), Some(Level::TRACE));
+        assert_eq!(level_from_pino(60), Level::ERROR);
+        assert_eq!(level_from_pino(40), Level::WARN);
+        assert_eq!(level_from_pino(30), Level::INFO);
+        assert_eq!(level_from_pino(20), Level::DEBUG);
+        assert_eq!(level_from_pino(10), Level::TRACE);
+    }
+}
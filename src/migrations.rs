@@ -0,0 +1,637 @@
+/*! Applying migrations with the Supabase CLI's tracking.
+
+[`MigrationRunner`] applies `<version>_<name>.sql` files from a directory, or SQL given as
+strings, in version order. Like `supabase db push`, it records every applied migration
+in `supabase_migrations.schema_migrations` and skips versions recorded there, so a
+database migrated by the runner looks the same to the CLI and to the project's own
+tooling.
+
+Each migration runs statement by statement in its own transaction. A failure names
+the migration, the statement's number and line, and PostgreSQL's error, and rolls the
+migration back.
+
+# Example
+
+```rust,no_run
+use supabase_testcontainers_modules::{MigrationRunner, PostgREST, SupabaseStack};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let migrations = MigrationRunner::from_dir("supabase/migrations")?
+        .with_sql("20990101000000", "test_helpers", "CREATE SCHEMA tests;");
+
+    // Applied while the stack starts, before the setup SQL and the snapshot
+    let stack = SupabaseStack::new()
+        .with_postgrest(PostgREST::default())
+        .with_migrations(migrations.clone())
+        .start()
+        .await?;
+
+    // Or applied to a running stack, reloading PostgREST's schema cache
+    let applied = stack.migrate(&migrations).await?;
+    assert!(applied.is_empty(), "already applied at start");
+    Ok(())
+}
+```
+*/
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+
+use crate::db::connect;
+
+/// Schema and table the Supabase CLI records applied migrations in
+const TRACKING_SQL: &str = "
+CREATE SCHEMA IF NOT EXISTS supabase_migrations;
+CREATE TABLE IF NOT EXISTS supabase_migrations.schema_migrations (version text NOT NULL PRIMARY KEY);
+ALTER TABLE supabase_migrations.schema_migrations ADD COLUMN IF NOT EXISTS statements text[];
+ALTER TABLE supabase_migrations.schema_migrations ADD COLUMN IF NOT EXISTS name text;
+";
+/// Longest statement excerpt quoted in errors, in characters
+const EXCERPT_LENGTH: usize = 80;
+
+/// One migration: a version, a name and its SQL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    /// Version the migration is recorded under, usually a timestamp
+    version: String,
+    /// Name after the version in the file name
+    name: String,
+    /// The migration's SQL
+    sql: String,
+}
+
+impl Migration {
+    /// Creates a migration from SQL
+    pub fn new(
+        version: impl Into<String>,
+        name: impl Into<String>,
+        sql: impl Into<String>,
+    ) -> Self {
+        Self {
+            version: version.into(),
+            name: name.into(),
+            sql: sql.into(),
+        }
+    }
+
+    /// Reads a migration from a `<version>_<name>.sql` file
+    ///
+    /// # Errors
+    /// Returns an error if the file name does not follow the pattern or the file
+    /// cannot be read.
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let Some((version, name)) = path
+            .file_name()
+            .and_then(|name| parse_file_name(&name.to_string_lossy()))
+        else {
+            bail!("{} is not named <version>_<name>.sql", path.display());
+        };
+        let sql = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        Ok(Self::new(version, name, sql))
+    }
+
+    /// Returns the version
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Returns the name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the SQL
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Returns the file name the migration has, or would have, in a migrations directory
+    pub fn file_name(&self) -> String {
+        format!("{}_{}.sql", self.version, self.name)
+    }
+
+    /// Returns the migration's statements
+    ///
+    /// Splits on semicolons outside of quotes, dollar quotes, comments and
+    /// `BEGIN ATOMIC ... END` bodies.
+    pub fn statements(&self) -> Vec<String> {
+        split_statements(&self.sql)
+            .into_iter()
+            .map(|statement| statement.sql)
+            .collect()
+    }
+}
+
+/// Applies migrations in version order, recording them like the Supabase CLI
+#[derive(Debug, Clone, Default)]
+pub struct MigrationRunner {
+    /// Migrations, sorted by version
+    migrations: Vec<Migration>,
+}
+
+impl MigrationRunner {
+    /// Creates a runner without migrations
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a runner with the migrations in `dir`
+    ///
+    /// See [`MigrationRunner::with_dir`].
+    pub fn from_dir(dir: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::new().with_dir(dir)
+    }
+
+    /// Adds the `<version>_<name>.sql` files in `dir`
+    ///
+    /// Other files are skipped, as the CLI does.
+    ///
+    /// # Errors
+    /// Returns an error if the directory or a migration cannot be read.
+    pub fn with_dir(mut self, dir: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let dir = dir.as_ref();
+        let entries =
+            fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+        let mut paths: Vec<PathBuf> = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            let is_migration = path
+                .file_name()
+                .is_some_and(|name| parse_file_name(&name.to_string_lossy()).is_some());
+            if is_migration && path.is_file() {
+                paths.push(path);
+            }
+        }
+        for path in paths {
+            self = self.with_migration(Migration::from_file(path)?);
+        }
+        Ok(self)
+    }
+
+    /// Adds a migration
+    ///
+    /// A migration with the same version replaces the earlier one.
+    pub fn with_migration(mut self, migration: Migration) -> Self {
+        self.migrations
+            .retain(|existing| existing.version != migration.version);
+        let at = self
+            .migrations
+            .partition_point(|existing| existing.version < migration.version);
+        self.migrations.insert(at, migration);
+        self
+    }
+
+    /// Adds a migration from SQL, e.g. embedded with `include_str!`
+    pub fn with_sql(
+        self,
+        version: impl Into<String>,
+        name: impl Into<String>,
+        sql: impl Into<String>,
+    ) -> Self {
+        self.with_migration(Migration::new(version, name, sql))
+    }
+
+    /// Returns the migrations in the order they are applied
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// Applies the migrations not yet recorded in `supabase_migrations.schema_migrations`
+    ///
+    /// Returns the versions applied. To reload PostgREST's schema cache afterwards, use
+    /// `RunningStack::migrate` or `PostgREST::reload_schema`.
+    ///
+    /// # Errors
+    /// Returns an error naming the migration, statement number and line of the first
+    /// statement that fails. That migration is rolled back; earlier ones stay applied.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+    pub async fn apply(&self, db_url: &str) -> anyhow::Result<Vec<String>> {
+        let mut client = connect(db_url).await?;
+        client
+            .batch_execute(TRACKING_SQL)
+            .await
+            .context("failed to create supabase_migrations.schema_migrations")?;
+        let recorded: Vec<String> = client
+            .query(
+                "SELECT version FROM supabase_migrations.schema_migrations",
+                &[],
+            )
+            .await?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+
+        let mut applied = Vec::new();
+        for migration in &self.migrations {
+            if recorded.contains(&migration.version) {
+                continue;
+            }
+            let statements = split_statements(&migration.sql);
+            let transaction = client.transaction().await?;
+            for (i, statement) in statements.iter().enumerate() {
+                if let Err(e) = transaction.batch_execute(&statement.sql).await {
+                    bail!(
+                        "migration {} failed at statement {} (line {}): {}: {}",
+                        migration.file_name(),
+                        i + 1,
+                        statement.line,
+                        excerpt(&statement.sql),
+                        db_error(&e)
+                    );
+                }
+            }
+            let sql: Vec<&str> = statements
+                .iter()
+                .map(|statement| statement.sql.as_str())
+                .collect();
+            transaction
+                .execute(
+                    "INSERT INTO supabase_migrations.schema_migrations (version, name, statements) \
+                     VALUES ($1, $2, $3)",
+                    &[&migration.version, &migration.name, &sql],
+                )
+                .await
+                .with_context(|| format!("failed to record migration {}", migration.file_name()))?;
+            transaction.commit().await?;
+            applied.push(migration.version.clone());
+        }
+        Ok(applied)
+    }
+
+    /// Applies the migrations not yet recorded without an async runtime
+    ///
+    /// See [`MigrationRunner::apply`].
+    ///
+    /// # Errors
+    /// Returns an error naming the migration, statement number and line of the first
+    /// statement that fails.
+    ///
+    /// # Panics
+    /// Panics if called from within an async runtime.
+    #[cfg(feature = "blocking")]
+    pub fn apply_blocking(&self, db_url: &str) -> anyhow::Result<Vec<String>> {
+        crate::blocking::block_on(self.apply(db_url))
+    }
+}
+
+/// A statement and the line of the migration it starts on
+#[derive(Debug, PartialEq, Eq)]
+struct Statement {
+    /// The statement, without its terminating semicolon
+    sql: String,
+    /// 1-based line of the statement's first character
+    line: usize,
+}
+
+/// Splits `<version>_<name>.sql` into its version and name
+fn parse_file_name(file_name: &str) -> Option<(String, String)> {
+    let stem = file_name.strip_suffix(".sql")?;
+    let (version, name) = stem.split_once('_')?;
+    (!version.is_empty() && version.bytes().all(|b| b.is_ascii_digit()))
+        .then(|| (version.to_string(), name.to_string()))
+}
+
+/// Splits `sql` into statements on semicolons outside quotes, comments and
+/// `BEGIN ATOMIC` bodies
+///
+/// Statements holding only comments and whitespace are dropped.
+fn split_statements(sql: &str) -> Vec<Statement> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut has_code = false;
+    let mut line = 1;
+    let mut start_line = None;
+    // Previous word, for spotting `BEGIN ATOMIC`
+    let mut previous_word = String::new();
+    // Nesting of `BEGIN ATOMIC` and `CASE` inside it
+    let mut atomic_depth = 0usize;
+    let mut i = 0;
+
+    let mut finish = |end: usize, start: usize, has_code: bool, start_line: Option<usize>| {
+        if has_code {
+            statements.push(Statement {
+                sql: chars[start..end]
+                    .iter()
+                    .collect::<String>()
+                    .trim()
+                    .to_string(),
+                line: start_line.unwrap_or(1),
+            });
+        }
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let begin = i;
+        match c {
+            '-' if next == Some('-') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            '/' if next == Some('*') => {
+                let mut depth = 0;
+                while i < chars.len() {
+                    if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+                        depth += 1;
+                        i += 2;
+                    } else if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                        depth -= 1;
+                        i += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else {
+                        if chars[i] == '\n' {
+                            line += 1;
+                        }
+                        i += 1;
+                    }
+                }
+                continue;
+            }
+            ';' if atomic_depth == 0 => {
+                finish(i, start, has_code, start_line);
+                i += 1;
+                start = i;
+                has_code = false;
+                start_line = None;
+                previous_word.clear();
+                continue;
+            }
+            '\n' => {
+                line += 1;
+                i += 1;
+                continue;
+            }
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        if !has_code {
+            has_code = true;
+            start_line = Some(line);
+        }
+        match c {
+            '\'' | '"' => {
+                // E'...' strings take backslash escapes
+                let escapes = c == '\''
+                    && i > 0
+                    && matches!(chars[i - 1], 'e' | 'E')
+                    && (i < 2 || !is_word_char(chars[i - 2]));
+                i += 1;
+                while i < chars.len() {
+                    if escapes && chars[i] == '\\' {
+                        i += 2;
+                        continue;
+                    }
+                    if chars[i] == '\n' {
+                        line += 1;
+                    }
+                    if chars[i] == c {
+                        if chars.get(i + 1) == Some(&c) {
+                            i += 2;
+                            continue;
+                        }
+                        break;
+                    }
+                    i += 1;
+                }
+                i += 1;
+            }
+            '$' if i == 0 || !is_word_char(chars[i - 1]) => match dollar_tag(&chars[i..]) {
+                Some(tag) => {
+                    i += tag.len();
+                    while i < chars.len() && !chars[i..].starts_with(&tag) {
+                        if chars[i] == '\n' {
+                            line += 1;
+                        }
+                        i += 1;
+                    }
+                    i = (i + tag.len()).min(chars.len());
+                }
+                None => i += 1,
+            },
+            c if is_word_char(c) => {
+                while i < chars.len() && is_word_char(chars[i]) {
+                    i += 1;
+                }
+                let word: String = chars[begin..i].iter().collect::<String>().to_uppercase();
+                match word.as_str() {
+                    "ATOMIC" if previous_word == "BEGIN" => atomic_depth += 1,
+                    "CASE" if atomic_depth > 0 => atomic_depth += 1,
+                    "END" if atomic_depth > 0 => atomic_depth -= 1,
+                    _ => {}
+                }
+                previous_word = word;
+            }
+            _ => i += 1,
+        }
+    }
+    finish(chars.len(), start, has_code, start_line);
+    statements
+}
+
+/// Returns the dollar-quote tag (`$$` or `$name$`) at the start of `chars`
+fn dollar_tag(chars: &[char]) -> Option<Vec<char>> {
+    let end = chars[1..].iter().position(|&c| c == '$')? + 1;
+    let name = &chars[1..end];
+    let valid = name.is_empty()
+        || (!name[0].is_ascii_digit() && name.iter().all(|&c| c.is_alphanumeric() || c == '_'));
+    valid.then(|| chars[..=end].to_vec())
+}
+
+/// Returns whether `c` can be part of an identifier or keyword
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Returns the start of `statement` on one line, for error messages
+fn excerpt(statement: &str) -> String {
+    let flat = statement.split_whitespace().collect::<Vec<_>>().join(" ");
+    if flat.chars().count() > EXCERPT_LENGTH {
+        format!(
+            "{}...",
+            flat.chars().take(EXCERPT_LENGTH).collect::<String>()
+        )
+    } else {
+        flat
+    }
+}
+
+/// Formats PostgreSQL's error message, with its detail and hint if present
+fn db_error(error: &tokio_postgres::Error) -> String {
+    match error.as_db_error() {
+        Some(db) => {
+            let mut message = db.message().to_string();
+            if let Some(detail) = db.detail() {
+                message.push_str(&format!(" (detail: {})", detail));
+            }
+            if let Some(hint) = db.hint() {
+                message.push_str(&format!(" (hint: {})", hint));
+            }
+            message
+        }
+        None => error.to_string(),
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "stack")]
+mod tests {
+    use super::*;
+
+    /// Returns the statements' SQL
+    fn sql(statements: Vec<Statement>) -> Vec<String> {
+        statements
+            .into_iter()
+            .map(|statement| statement.sql)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_file_name() {
+        assert_eq!(
+            parse_file_name("20240101000000_create_todos.sql"),
+            Some(("20240101000000".to_string(), "create_todos".to_string()))
+        );
+        assert_eq!(
+            parse_file_name("1_a_b.sql"),
+            Some(("1".to_string(), "a_b".to_string()))
+        );
+        assert_eq!(parse_file_name("README.md"), None);
+        assert_eq!(parse_file_name("create_todos.sql"), None);
+        assert_eq!(parse_file_name("20240101000000.sql"), None);
+    }
+
+    #[test]
+    fn test_runner_sorts_and_replaces_by_version() {
+        let runner = MigrationRunner::new()
+            .with_sql("3", "c", "SELECT 3;")
+            .with_sql("1", "a", "SELECT 1;")
+            .with_sql("2", "b", "SELECT 2;")
+            .with_sql("1", "a2", "SELECT 10;");
+
+        let names: Vec<&str> = runner.migrations().iter().map(Migration::name).collect();
+        assert_eq!(names, ["a2", "b", "c"]);
+    }
+
+    #[test]
+    fn test_from_dir_skips_other_files() {
+        let dir =
+            std::env::temp_dir().join(format!("supabase-tc-migrations-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("20240102000000_second.sql"), "SELECT 2;").unwrap();
+        fs::write(dir.join("20240101000000_first.sql"), "SELECT 1;").unwrap();
+        fs::write(dir.join("README.md"), "notes").unwrap();
+
+        let runner = MigrationRunner::from_dir(&dir).unwrap();
+        let files: Vec<String> = runner
+            .migrations()
+            .iter()
+            .map(Migration::file_name)
+            .collect();
+        assert_eq!(
+            files,
+            ["20240101000000_first.sql", "20240102000000_second.sql"]
+        );
+        assert_eq!(runner.migrations()[0].sql(), "SELECT 1;");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_split_simple_statements() {
+        let statements =
+            split_statements("CREATE TABLE a (id int);\n\nINSERT INTO a VALUES (1);\n");
+
+        assert_eq!(
+            statements,
+            [
+                Statement {
+                    sql: "CREATE TABLE a (id int)".to_string(),
+                    line: 1
+                },
+                Statement {
+                    sql: "INSERT INTO a VALUES (1)".to_string(),
+                    line: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_ignores_quoted_semicolons() {
+        let statements = split_statements(
+            "INSERT INTO a VALUES ('x;y', 'it''s;', E'\\';');\nSELECT \"odd;name\" FROM a;",
+        );
+
+        assert_eq!(
+            sql(statements),
+            [
+                "INSERT INTO a VALUES ('x;y', 'it''s;', E'\\';')",
+                "SELECT \"odd;name\" FROM a"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_ignores_dollar_quoted_bodies() {
+        let function = "CREATE FUNCTION f() RETURNS int AS $body$\nBEGIN\n  PERFORM 1;\n  RETURN $$;$$::int;\nEND;\n$body$ LANGUAGE plpgsql";
+        let statements = split_statements(&format!("{};\nSELECT $1;", function));
+
+        assert_eq!(sql(statements), [function, "SELECT $1"]);
+    }
+
+    #[test]
+    fn test_split_handles_comments() {
+        let statements = split_statements(
+            "-- header; not a statement\n/* block; /* nested; */ still; */\nSELECT 1; -- trailing;\n-- only a comment;\n",
+        );
+
+        assert_eq!(
+            statements,
+            [Statement {
+                sql: "-- header; not a statement\n/* block; /* nested; */ still; */\nSELECT 1"
+                    .to_string(),
+                line: 3
+            }]
+        );
+    }
+
+    #[test]
+    fn test_split_keeps_begin_atomic_bodies() {
+        let function = "CREATE FUNCTION f(x int) RETURNS int LANGUAGE sql\nBEGIN ATOMIC\n  SELECT CASE WHEN x > 0 THEN 1 ELSE 0 END;\n  SELECT 2;\nEND";
+        let statements = split_statements(&format!("{};\nSELECT 3;", function));
+
+        assert_eq!(sql(statements), [function, "SELECT 3"]);
+    }
+
+    #[test]
+    fn test_statement_lines() {
+        let statements =
+            split_statements("SELECT 1;\n/* c\n */\nSELECT\n2;  SELECT 'a\nb'; SELECT 4;");
+
+        let lines: Vec<usize> = statements.iter().map(|statement| statement.line).collect();
+        assert_eq!(lines, [1, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_excerpt() {
+        assert_eq!(excerpt("SELECT\n    1"), "SELECT 1");
+        let long = format!("SELECT '{}'", "x".repeat(100));
+        assert_eq!(excerpt(&long).chars().count(), EXCERPT_LENGTH + 3);
+    }
+}
@@ -0,0 +1,201 @@
+/*! Image names, tags and registries from the environment.
+
+Every image reads these variables when it is created with `default()`, so CI can pull
+through a registry mirror or pin patched images without changing test code:
+
+| Variable | Effect |
+|----------|--------|
+| `SUPABASE_TC_<SERVICE>_IMAGE` | Replaces the image name, e.g. `ghcr.io/acme/gotrue` |
+| `SUPABASE_TC_<SERVICE>_TAG` | Replaces the tag |
+| `SUPABASE_TC_<SERVICE>_DIGEST` | Pins the image to a digest, e.g. `sha256:4c1e...` |
+| `SUPABASE_TC_REGISTRY` | Prefixes image names that have no registry, e.g. `mirror.example.com` |
+
+`<SERVICE>` is one of `AUTH`, `POSTGREST`, `STORAGE`, `REALTIME`, `FUNCTIONS`,
+`ANALYTICS`, `GRAPHQL`, `VECTOR`, `TOXIPROXY` and `POSTGRES` (the logical replication
+preset).
+
+Builder methods called afterwards (`with_image_name`, `with_tag`, `with_digest`) take
+precedence over the environment. Empty variables are ignored.
+*/
+
+// The `const` feature alone enables none of the images using these helpers
+#![cfg_attr(
+    not(any(
+        feature = "analytics",
+        feature = "auth",
+        feature = "functions",
+        feature = "graphql",
+        feature = "postgres_testcontainer",
+        feature = "postgrest",
+        feature = "realtime",
+        feature = "storage",
+        feature = "toxiproxy",
+        feature = "vector"
+    )),
+    allow(dead_code)
+)]
+
+/// Prefix of all override variables
+const ENV_PREFIX: &str = "SUPABASE_TC";
+/// Registry prepended to image names without one
+pub const REGISTRY_ENV: &str = "SUPABASE_TC_REGISTRY";
+
+/// Returns the image name for `service`, applying the environment overrides
+pub(crate) fn image_name(service: &str, default: &str) -> String {
+    image_name_from(service, default, env)
+}
+
+/// Returns the image tag for `service`, applying the environment overrides
+pub(crate) fn image_tag(service: &str, default: &str) -> String {
+    image_tag_from(service, default, env)
+}
+
+/// Prefixes `name` with the registry from [`REGISTRY_ENV`], unless it names one
+pub(crate) fn with_registry(name: &str) -> String {
+    registry_prefixed(name, env(REGISTRY_ENV).as_deref())
+}
+
+/// Returns `tag` pinned to `digest`
+///
+/// A digest without an algorithm is taken to be SHA-256. A digest already on `tag`
+/// is replaced.
+pub(crate) fn pin_digest(tag: &str, digest: &str) -> String {
+    let tag = tag.split('@').next().unwrap_or(tag);
+    if digest.contains(':') {
+        format!("{}@{}", tag, digest)
+    } else {
+        format!("{}@sha256:{}", tag, digest)
+    }
+}
+
+/// [`image_name`] with the environment read through `env`
+fn image_name_from(service: &str, default: &str, env: impl Fn(&str) -> Option<String>) -> String {
+    let name = env(&variable(service, "IMAGE")).unwrap_or_else(|| default.to_string());
+    registry_prefixed(&name, env(REGISTRY_ENV).as_deref())
+}
+
+/// [`image_tag`] with the environment read through `env`
+fn image_tag_from(service: &str, default: &str, env: impl Fn(&str) -> Option<String>) -> String {
+    let tag = env(&variable(service, "TAG")).unwrap_or_else(|| default.to_string());
+    match env(&variable(service, "DIGEST")) {
+        Some(digest) => pin_digest(&tag, &digest),
+        None => tag,
+    }
+}
+
+/// Prefixes `name` with `registry`, unless `name` already names a registry
+///
+/// Docker Hub's official images get their implicit `library/` namespace, which
+/// registry mirrors expect.
+fn registry_prefixed(name: &str, registry: Option<&str>) -> String {
+    let Some(registry) = registry else {
+        return name.to_string();
+    };
+    match name.split_once('/') {
+        Some((first, _)) if first.contains('.') || first.contains(':') || first == "localhost" => {
+            name.to_string()
+        }
+        Some(_) => format!("{}/{}", registry.trim_end_matches('/'), name),
+        None => format!("{}/library/{}", registry.trim_end_matches('/'), name),
+    }
+}
+
+/// Returns the variable name for one of `service`'s overrides
+fn variable(service: &str, setting: &str) -> String {
+    format!("{}_{}_{}", ENV_PREFIX, service, setting)
+}
+
+/// Reads a variable, treating empty values as unset
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+#[cfg(feature = "const")]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Returns an environment lookup over `vars`
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_defaults_without_overrides() {
+        let env = lookup(&[]);
+
+        assert_eq!(
+            image_name_from("AUTH", "supabase/gotrue", &env),
+            "supabase/gotrue"
+        );
+        assert_eq!(image_tag_from("AUTH", "v2.183.0", &env), "v2.183.0");
+    }
+
+    #[test]
+    fn test_image_and_tag_overrides() {
+        let env = lookup(&[
+            ("SUPABASE_TC_AUTH_IMAGE", "ghcr.io/acme/gotrue"),
+            ("SUPABASE_TC_AUTH_TAG", "v2.183.0-patched"),
+        ]);
+
+        assert_eq!(
+            image_name_from("AUTH", "supabase/gotrue", &env),
+            "ghcr.io/acme/gotrue"
+        );
+        assert_eq!(image_tag_from("AUTH", "v2.183.0", &env), "v2.183.0-patched");
+        // Other services are unaffected
+        assert_eq!(image_tag_from("STORAGE", "v1.11.1", &env), "v1.11.1");
+    }
+
+    #[test]
+    fn test_digest_override() {
+        let env = lookup(&[("SUPABASE_TC_AUTH_DIGEST", "sha256:abc")]);
+
+        assert_eq!(
+            image_tag_from("AUTH", "v2.183.0", &env),
+            "v2.183.0@sha256:abc"
+        );
+    }
+
+    #[test]
+    fn test_registry_override() {
+        let env = lookup(&[("SUPABASE_TC_REGISTRY", "mirror.example.com/")]);
+
+        assert_eq!(
+            image_name_from("AUTH", "supabase/gotrue", &env),
+            "mirror.example.com/supabase/gotrue"
+        );
+        assert_eq!(
+            image_name_from("POSTGRES", "postgres", &env),
+            "mirror.example.com/library/postgres"
+        );
+    }
+
+    #[test]
+    fn test_registry_keeps_names_with_registry() {
+        for name in [
+            "ghcr.io/acme/gotrue",
+            "localhost:5000/gotrue",
+            "localhost/gotrue",
+        ] {
+            assert_eq!(registry_prefixed(name, Some("mirror.example.com")), name);
+        }
+    }
+
+    #[test]
+    fn test_pin_digest() {
+        assert_eq!(pin_digest("v1", "sha256:abc"), "v1@sha256:abc");
+        assert_eq!(pin_digest("v1", "abc"), "v1@sha256:abc");
+        assert_eq!(pin_digest("v1@sha256:old", "sha256:new"), "v1@sha256:new");
+    }
+
+    #[test]
+    fn test_variable_name() {
+        assert_eq!(variable("AUTH", "TAG"), "SUPABASE_TC_AUTH_TAG");
+    }
+}
@@ -0,0 +1,494 @@
+/*! PostgreSQL presets for Supabase services.
+
+This module configures the [`Postgres`] image from `testcontainers-modules` for
+services with database requirements beyond a stock PostgreSQL server.
+
+# Logical Replication
+
+Realtime streams changes through logical replication, which needs `wal_level=logical`,
+free replication slots and WAL senders, and the `supabase_realtime` publication.
+[`LogicalReplication`] passes those settings as server flags and creates the
+publication on first start; [`LogicalReplication::validate`] checks a running database
+and explains what is missing.
+
+# Example
+
+```rust,no_run
+use supabase_testcontainers_modules::{LogicalReplication, LOCAL_HOST};
+use testcontainers::runners::AsyncRunner;
+use testcontainers_modules::postgres::Postgres;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let postgres = LogicalReplication::default()
+        .with_max_replication_slots(5)
+        .apply(Postgres::default())
+        .start()
+        .await?;
+    let port = postgres.get_host_port_ipv4(5432).await?;
+
+    let db_url = format!("postgres://postgres:postgres@{}:{}/postgres", LOCAL_HOST, port);
+    LogicalReplication::validate(&db_url, "supabase_realtime").await?;
+
+    Ok(())
+}
+```
+*/
+
+use anyhow::{bail, Context};
+use testcontainers_modules::postgres::Postgres;
+use testcontainers_modules::testcontainers::{ContainerRequest, ImageExt};
+
+use crate::db::connect;
+use crate::overrides::{image_name, image_tag, pin_digest, with_registry};
+
+/// Publication Realtime subscribes to
+pub const REALTIME_PUBLICATION: &str = "supabase_realtime";
+/// Default PostgreSQL image for the preset
+const NAME: &str = "postgres";
+/// Default PostgreSQL tag for the preset
+const TAG: &str = "15-alpine";
+/// Default number of replication slots
+const DEFAULT_MAX_REPLICATION_SLOTS: u32 = 10;
+/// Default number of WAL sender processes
+const DEFAULT_MAX_WAL_SENDERS: u32 = 10;
+
+/// Logical replication settings for a PostgreSQL container.
+///
+/// # Default Configuration
+///
+/// The default configuration includes:
+/// - `wal_level=logical`
+/// - `max_replication_slots=10`
+/// - `max_wal_senders=10`
+/// - `fsync=off`, as in the [`Postgres`] image
+/// - Publication "supabase_realtime"
+/// - PostgreSQL tag "15-alpine"
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use supabase_testcontainers_modules::LogicalReplication;
+/// use testcontainers::ImageExt;
+/// use testcontainers_modules::postgres::Postgres;
+///
+/// let postgres = LogicalReplication::default()
+///     .with_max_wal_senders(4)
+///     .apply(Postgres::default().with_password("secret"))
+///     .with_network("realtime-network");
+/// ```
+#[derive(Debug, Clone)]
+pub struct LogicalReplication {
+    /// Value of `max_replication_slots`
+    max_replication_slots: u32,
+    /// Value of `max_wal_senders`
+    max_wal_senders: u32,
+    /// Publication created on first start, if any
+    publication: Option<String>,
+    /// PostgreSQL image name
+    name: String,
+    /// PostgreSQL image tag
+    tag: String,
+}
+
+impl LogicalReplication {
+    /// Creates the default logical replication settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets `max_replication_slots`
+    ///
+    /// Realtime creates one slot per tenant; the default is 10.
+    pub fn with_max_replication_slots(mut self, slots: u32) -> Self {
+        self.max_replication_slots = slots;
+        self
+    }
+
+    /// Sets `max_wal_senders`
+    ///
+    /// Every replication connection uses a WAL sender; the default is 10.
+    pub fn with_max_wal_senders(mut self, senders: u32) -> Self {
+        self.max_wal_senders = senders;
+        self
+    }
+
+    /// Sets the publication created on first start
+    ///
+    /// Default is "supabase_realtime".
+    pub fn with_publication(mut self, publication: impl Into<String>) -> Self {
+        self.publication = Some(publication.into());
+        self
+    }
+
+    /// Skips creating a publication
+    pub fn without_publication(mut self) -> Self {
+        self.publication = None;
+        self
+    }
+
+    /// Sets the PostgreSQL image tag
+    ///
+    /// Logical replication needs PostgreSQL 10 or later; Realtime supports 14+.
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = tag.into();
+        self
+    }
+
+    /// Sets the PostgreSQL image name, e.g. a copy in a private registry
+    ///
+    /// `SUPABASE_TC_REGISTRY` still prefixes names without a registry.
+    pub fn with_image_name(mut self, name: impl Into<String>) -> Self {
+        self.name = with_registry(&name.into());
+        self
+    }
+
+    /// Pins the PostgreSQL image to a digest such as `sha256:4c1e...`
+    ///
+    /// Docker pulls the digest and ignores the tag; [`LogicalReplication::with_tag`]
+    /// removes the pin.
+    pub fn with_digest(mut self, digest: impl AsRef<str>) -> Self {
+        self.tag = pin_digest(&self.tag, digest.as_ref());
+        self
+    }
+
+    /// Returns the server flags passed as the container command
+    pub fn cmd(&self) -> Vec<String> {
+        [
+            "wal_level=logical".to_string(),
+            format!("max_replication_slots={}", self.max_replication_slots),
+            format!("max_wal_senders={}", self.max_wal_senders),
+            "fsync=off".to_string(),
+        ]
+        .into_iter()
+        .flat_map(|setting| ["-c".to_string(), setting])
+        .collect()
+    }
+
+    /// Returns the init script creating the publication, if one is configured
+    pub fn init_sql(&self) -> Option<String> {
+        self.publication
+            .as_ref()
+            .map(|publication| format!("CREATE PUBLICATION {};\n", quote_ident(publication)))
+    }
+
+    /// Applies these settings to a [`Postgres`] image
+    ///
+    /// The returned request can be configured further (network, container name)
+    /// before it is started.
+    pub fn apply(&self, postgres: Postgres) -> ContainerRequest<Postgres> {
+        let postgres = match self.init_sql() {
+            Some(sql) => postgres.with_init_sql(sql.into_bytes()),
+            None => postgres,
+        };
+        postgres
+            .with_name(self.name.clone())
+            .with_tag(self.tag.clone())
+            .with_cmd(self.cmd())
+    }
+
+    /// Checks that the database at `db_url` can serve logical replication to Realtime
+    ///
+    /// Verifies `wal_level`, free replication slots and WAL senders, the connecting
+    /// role's `REPLICATION` privilege and that `publication` exists.
+    ///
+    /// # Errors
+    /// Returns an error describing every unmet requirement.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(publication = %publication), err))]
+    pub async fn validate(db_url: &str, publication: &str) -> anyhow::Result<()> {
+        let client = connect(db_url).await?;
+        let row = client
+            .query_one(
+                "SELECT current_setting('wal_level'), \
+                        current_setting('max_replication_slots')::int, \
+                        current_setting('max_wal_senders')::int, \
+                        (SELECT count(*)::int FROM pg_replication_slots), \
+                        (SELECT rolsuper OR rolreplication FROM pg_roles WHERE rolname = current_user), \
+                        EXISTS (SELECT 1 FROM pg_publication WHERE pubname = $1)",
+                &[&publication],
+            )
+            .await
+            .context("failed to read replication settings")?;
+
+        let status = ReplicationStatus {
+            wal_level: row.get(0),
+            max_replication_slots: row.get(1),
+            max_wal_senders: row.get(2),
+            used_replication_slots: row.get(3),
+            can_replicate: row.get::<_, Option<bool>>(4).unwrap_or(false),
+            has_publication: row.get(5),
+        };
+
+        let problems = status.problems(publication);
+        if !problems.is_empty() {
+            bail!(
+                "database cannot serve logical replication:\n  - {}",
+                problems.join("\n  - ")
+            );
+        }
+        Ok(())
+    }
+
+    /// Adds tables to `publication` so their changes are streamed
+    ///
+    /// Tables are given as `schema.table` or `table` (resolved with the search path).
+    ///
+    /// # Errors
+    /// Returns an error if the connection fails or a table does not exist.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+    pub async fn publish_tables(
+        db_url: &str,
+        publication: &str,
+        tables: &[&str],
+    ) -> anyhow::Result<()> {
+        if db_url.is_empty() {
+            bail!("database URL cannot be empty");
+        }
+        if tables.is_empty() {
+            return Ok(());
+        }
+
+        let client = connect(db_url).await?;
+        let tables: Vec<String> = tables
+            .iter()
+            .map(|table| {
+                table
+                    .split('.')
+                    .map(quote_ident)
+                    .collect::<Vec<_>>()
+                    .join(".")
+            })
+            .collect();
+        let sql = format!(
+            "ALTER PUBLICATION {} ADD TABLE {}",
+            quote_ident(publication),
+            tables.join(", ")
+        );
+        client
+            .batch_execute(&sql)
+            .await
+            .with_context(|| format!("failed to add tables to publication '{}'", publication))?;
+        Ok(())
+    }
+
+    /// Checks that the database can serve logical replication without an async runtime
+    ///
+    /// See [`LogicalReplication::validate`].
+    ///
+    /// # Errors
+    /// Returns an error describing every unmet requirement.
+    ///
+    /// # Panics
+    /// Panics if called from within an async runtime.
+    #[cfg(feature = "blocking")]
+    pub fn validate_blocking(db_url: &str, publication: &str) -> anyhow::Result<()> {
+        crate::blocking::block_on(Self::validate(db_url, publication))
+    }
+
+    /// Adds tables to `publication` without an async runtime
+    ///
+    /// See [`LogicalReplication::publish_tables`].
+    ///
+    /// # Errors
+    /// Returns an error if the connection fails or a table does not exist.
+    ///
+    /// # Panics
+    /// Panics if called from within an async runtime.
+    #[cfg(feature = "blocking")]
+    pub fn publish_tables_blocking(
+        db_url: &str,
+        publication: &str,
+        tables: &[&str],
+    ) -> anyhow::Result<()> {
+        crate::blocking::block_on(Self::publish_tables(db_url, publication, tables))
+    }
+}
+
+impl Default for LogicalReplication {
+    fn default() -> Self {
+        Self {
+            max_replication_slots: DEFAULT_MAX_REPLICATION_SLOTS,
+            max_wal_senders: DEFAULT_MAX_WAL_SENDERS,
+            publication: Some(REALTIME_PUBLICATION.to_string()),
+            name: image_name("POSTGRES", NAME),
+            tag: image_tag("POSTGRES", TAG),
+        }
+    }
+}
+
+/// Replication-related state read from a running database
+#[derive(Debug, Clone, PartialEq)]
+struct ReplicationStatus {
+    /// Current `wal_level`
+    wal_level: String,
+    /// Current `max_replication_slots`
+    max_replication_slots: i32,
+    /// Current `max_wal_senders`
+    max_wal_senders: i32,
+    /// Number of existing replication slots
+    used_replication_slots: i32,
+    /// Whether the connecting role is a superuser or has `REPLICATION`
+    can_replicate: bool,
+    /// Whether the publication exists
+    has_publication: bool,
+}
+
+impl ReplicationStatus {
+    /// Returns a description of every unmet requirement
+    fn problems(&self, publication: &str) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.wal_level != "logical" {
+            problems.push(format!(
+                "wal_level is '{}', expected 'logical' (start the server with -c wal_level=logical)",
+                self.wal_level
+            ));
+        }
+        if self.max_replication_slots <= self.used_replication_slots {
+            problems.push(format!(
+                "no free replication slots ({} of {} in use; raise max_replication_slots)",
+                self.used_replication_slots, self.max_replication_slots
+            ));
+        }
+        if self.max_wal_senders < 1 {
+            problems.push("max_wal_senders is 0 (raise max_wal_senders)".to_string());
+        }
+        if !self.can_replicate {
+            problems.push(
+                "the connecting role lacks the REPLICATION privilege (ALTER ROLE ... REPLICATION)"
+                    .to_string(),
+            );
+        }
+        if !self.has_publication {
+            problems.push(format!(
+                "publication '{}' does not exist (CREATE PUBLICATION {})",
+                publication,
+                quote_ident(publication)
+            ));
+        }
+        problems
+    }
+}
+
+/// Quotes an SQL identifier
+fn quote_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+#[cfg(feature = "postgres_testcontainer")]
+mod tests {
+    use super::*;
+
+    fn ready_status() -> ReplicationStatus {
+        ReplicationStatus {
+            wal_level: "logical".to_string(),
+            max_replication_slots: 10,
+            max_wal_senders: 10,
+            used_replication_slots: 0,
+            can_replicate: true,
+            has_publication: true,
+        }
+    }
+
+    #[test]
+    fn test_default_cmd() {
+        let cmd = LogicalReplication::default().cmd();
+        assert_eq!(
+            cmd,
+            vec![
+                "-c",
+                "wal_level=logical",
+                "-c",
+                "max_replication_slots=10",
+                "-c",
+                "max_wal_senders=10",
+                "-c",
+                "fsync=off",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_with_slots_and_senders() {
+        let cmd = LogicalReplication::default()
+            .with_max_replication_slots(3)
+            .with_max_wal_senders(4)
+            .cmd();
+        assert!(cmd.contains(&"max_replication_slots=3".to_string()));
+        assert!(cmd.contains(&"max_wal_senders=4".to_string()));
+    }
+
+    #[test]
+    fn test_default_init_sql_creates_realtime_publication() {
+        assert_eq!(
+            LogicalReplication::default().init_sql(),
+            Some("CREATE PUBLICATION \"supabase_realtime\";\n".to_string())
+        );
+    }
+
+    #[test]
+    fn test_with_publication() {
+        let replication = LogicalReplication::default().with_publication("changes");
+        assert_eq!(
+            replication.init_sql(),
+            Some("CREATE PUBLICATION \"changes\";\n".to_string())
+        );
+        assert_eq!(replication.without_publication().init_sql(), None);
+    }
+
+    #[test]
+    fn test_apply_sets_tag_and_cmd() {
+        let request = LogicalReplication::default()
+            .with_tag("16-alpine")
+            .apply(Postgres::default());
+
+        assert_eq!(request.descriptor(), "postgres:16-alpine");
+        let cmd: Vec<String> = request.cmd().map(|arg| arg.into_owned()).collect();
+        assert!(cmd.contains(&"wal_level=logical".to_string()));
+    }
+
+    #[test]
+    fn test_apply_sets_image_name_and_digest() {
+        let request = LogicalReplication::default()
+            .with_image_name("ghcr.io/acme/postgres")
+            .with_tag("16-alpine")
+            .with_digest("sha256:abc")
+            .apply(Postgres::default());
+
+        assert_eq!(
+            request.descriptor(),
+            "ghcr.io/acme/postgres:16-alpine@sha256:abc"
+        );
+    }
+
+    #[test]
+    fn test_ready_status_has_no_problems() {
+        assert!(ready_status().problems(REALTIME_PUBLICATION).is_empty());
+    }
+
+    #[test]
+    fn test_problems_are_reported() {
+        let status = ReplicationStatus {
+            wal_level: "replica".to_string(),
+            max_replication_slots: 2,
+            max_wal_senders: 0,
+            used_replication_slots: 2,
+            can_replicate: false,
+            has_publication: false,
+        };
+
+        let problems = status.problems(REALTIME_PUBLICATION);
+        assert_eq!(problems.len(), 5);
+        assert!(problems[0].contains("wal_level is 'replica'"));
+        assert!(problems[1].contains("2 of 2 in use"));
+        assert!(problems[2].contains("max_wal_senders"));
+        assert!(problems[3].contains("REPLICATION"));
+        assert!(problems[4].contains("publication 'supabase_realtime'"));
+    }
+
+    #[test]
+    fn test_quote_ident() {
+        assert_eq!(quote_ident("supabase_realtime"), "\"supabase_realtime\"");
+        assert_eq!(quote_ident("we\"ird"), "\"we\"\"ird\"");
+    }
+}
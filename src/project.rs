@@ -0,0 +1,860 @@
+/*! Configuration from a Supabase CLI project.
+
+Projects set up with `supabase init` keep their configuration in `supabase/config.toml`,
+their schema in `supabase/migrations` and their seed data in `supabase/seed.sql`.
+[`SupabaseProject`] reads them and maps them onto this crate's builders, so tests run
+against the configuration the project runs with locally.
+
+# Mapped Settings
+
+| config.toml | Builder |
+|-------------|---------|
+| `auth.enabled` | Auth is part of the stack unless `false` |
+| `auth.site_url` | `Auth::with_site_url` |
+| `auth.additional_redirect_urls` | `GOTRUE_URI_ALLOW_LIST` |
+| `auth.jwt_expiry` | `Auth::with_jwt_expiry` |
+| `auth.jwt_secret` | `SupabaseStack::with_jwt_secret` |
+| `auth.enable_signup` | `Auth::with_signup_disabled` |
+| `auth.enable_anonymous_sign_ins` | `Auth::with_anonymous_users` |
+| `auth.minimum_password_length` | `GOTRUE_PASSWORD_MIN_LENGTH` |
+| `auth.enable_refresh_token_rotation` | `GOTRUE_SECURITY_REFRESH_TOKEN_ROTATION_ENABLED` |
+| `auth.refresh_token_reuse_interval` | `GOTRUE_SECURITY_REFRESH_TOKEN_REUSE_INTERVAL` |
+| `auth.email.enable_confirmations` | `Auth::with_mailer_autoconfirm` |
+| `auth.sms.enable_confirmations` | `Auth::with_sms_autoconfirm` |
+| `api.enabled` | PostgREST is part of the stack unless `false` |
+| `api.schemas` | `PostgREST::with_schemas` |
+| `api.extra_search_path` | `PostgREST::with_db_extra_search_path` |
+| `api.max_rows` | `PostgREST::with_max_rows` |
+| `storage.enabled` | Storage is part of the stack unless `false` |
+| `storage.file_size_limit` | `Storage::with_file_size_limit` |
+| `storage.buckets.<id>` | Rows in `storage.buckets`, created after the seed |
+| `supabase/migrations` | `SupabaseStack::with_migrations`, tracked like the CLI does |
+| `db.seed.enabled`, `db.seed.sql_paths` | Seed files applied after the migrations |
+| `realtime.enabled` | [`SupabaseProject::realtime`] (with the `realtime` feature) |
+| `analytics.enabled`, `analytics.backend` | [`SupabaseProject::analytics`] (with the `analytics` feature) |
+| `edge_runtime.enabled`, `edge_runtime.secrets` | [`SupabaseProject::functions`] (with the `functions` feature) |
+| `functions.<name>.verify_jwt` | `Functions::with_verify_jwt` |
+
+String values of the form `env(NAME)` are read from the environment, as the CLI does.
+Other settings, including `db.major_version` and bucket `objects_path`, are not applied.
+`SupabaseStack` runs Auth, PostgREST and Storage; Realtime, Analytics and Functions are
+returned configured for the test to start.
+
+# Example
+
+```rust,no_run
+use supabase_testcontainers_modules::SupabaseStack;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    // Reads ./supabase/config.toml, applies the migrations and the seed
+    let stack = SupabaseStack::from_project_dir(".")?.start().await?;
+    Ok(())
+}
+```
+*/
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use toml::{Table, Value};
+
+#[cfg(feature = "analytics")]
+use crate::Analytics;
+#[cfg(feature = "functions")]
+use crate::Functions;
+#[cfg(feature = "realtime")]
+use crate::Realtime;
+use crate::{Auth, MigrationRunner, PostgREST, Storage, SupabaseStack};
+
+/// Directory `supabase init` creates in the project root
+const PROJECT_SUBDIR: &str = "supabase";
+/// Configuration file inside the Supabase directory
+const CONFIG_FILE: &str = "config.toml";
+/// Migrations directory inside the Supabase directory
+const MIGRATIONS_DIR: &str = "migrations";
+/// Functions directory inside the Supabase directory
+const FUNCTIONS_DIR: &str = "functions";
+/// Seed file used when `db.seed.sql_paths` is not set
+const DEFAULT_SEED_PATH: &str = "./seed.sql";
+
+/// A Supabase CLI project: `config.toml`, migrations, seed data and functions
+#[derive(Debug, Clone)]
+pub struct SupabaseProject {
+    /// The `supabase` directory
+    dir: PathBuf,
+    /// Parsed `config.toml`
+    config: Table,
+}
+
+impl SupabaseProject {
+    /// Loads the project in `path`
+    ///
+    /// `path` is either the project root, containing a `supabase` directory, or the
+    /// `supabase` directory itself.
+    ///
+    /// # Errors
+    /// Returns an error if there is no `config.toml` or it is not valid TOML.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let dir = if path.join(PROJECT_SUBDIR).join(CONFIG_FILE).is_file() {
+            path.join(PROJECT_SUBDIR)
+        } else if path.join(CONFIG_FILE).is_file() {
+            path.to_path_buf()
+        } else {
+            bail!(
+                "no {} in {} or {}",
+                CONFIG_FILE,
+                path.join(PROJECT_SUBDIR).display(),
+                path.display()
+            );
+        };
+        let config_path = dir.join(CONFIG_FILE);
+        let text = fs::read_to_string(&config_path)
+            .with_context(|| format!("failed to read {}", config_path.display()))?;
+        Ok(Self {
+            config: Self::parse(&text)
+                .with_context(|| format!("failed to parse {}", config_path.display()))?,
+            dir,
+        })
+    }
+
+    /// Parses the contents of a `config.toml`
+    fn parse(text: &str) -> anyhow::Result<Table> {
+        Ok(text.parse::<Table>()?)
+    }
+
+    /// Returns the `supabase` directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the `project_id` setting
+    pub fn project_id(&self) -> anyhow::Result<Option<String>> {
+        self.string("project_id")
+    }
+
+    /// Returns a runner for the migrations in `supabase/migrations`
+    pub fn migrations(&self) -> anyhow::Result<MigrationRunner> {
+        let dir = self.dir.join(MIGRATIONS_DIR);
+        if !dir.is_dir() {
+            return Ok(MigrationRunner::new());
+        }
+        MigrationRunner::from_dir(dir)
+    }
+
+    /// Returns the seed files, or none if `db.seed.enabled` is `false`
+    ///
+    /// `db.seed.sql_paths` is relative to the `supabase` directory and may use `*` and
+    /// `?` in file names. As with the CLI, paths matching no file are skipped.
+    pub fn seed_files(&self) -> anyhow::Result<Vec<PathBuf>> {
+        if !self.enabled("db.seed")? {
+            return Ok(Vec::new());
+        }
+        let patterns = self
+            .strings("db.seed.sql_paths")?
+            .unwrap_or_else(|| vec![DEFAULT_SEED_PATH.to_string()]);
+
+        let mut files = Vec::new();
+        for pattern in patterns {
+            let path = self.dir.join(&pattern);
+            let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+                continue;
+            };
+            let mut matches = if parent.is_dir() {
+                sql_files(parent, &name.to_string_lossy())?
+            } else {
+                Vec::new()
+            };
+            matches.sort();
+            for file in matches {
+                if !files.contains(&file) {
+                    files.push(file);
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    /// Returns `supabase/functions`, if the project has functions
+    pub fn functions_dir(&self) -> Option<PathBuf> {
+        let dir = self.dir.join(FUNCTIONS_DIR);
+        dir.is_dir().then_some(dir)
+    }
+
+    /// Returns the `auth.jwt_secret` setting
+    pub fn jwt_secret(&self) -> anyhow::Result<Option<String>> {
+        self.string("auth.jwt_secret")
+    }
+
+    /// Returns Auth configured from `[auth]`, or `None` if it is disabled
+    pub fn auth(&self) -> anyhow::Result<Option<Auth>> {
+        if !self.enabled("auth")? {
+            return Ok(None);
+        }
+        let mut auth = Auth::default();
+        if let Some(url) = self.string("auth.site_url")? {
+            auth = auth.with_site_url(url);
+        }
+        if let Some(urls) = self.strings("auth.additional_redirect_urls")? {
+            auth = auth.with_env("GOTRUE_URI_ALLOW_LIST", urls.join(","));
+        }
+        if let Some(seconds) = self.integer("auth.jwt_expiry")? {
+            auth = auth.with_jwt_expiry(seconds);
+        }
+        if let Some(enabled) = self.bool("auth.enable_signup")? {
+            auth = auth.with_signup_disabled(!enabled);
+        }
+        if let Some(enabled) = self.bool("auth.enable_anonymous_sign_ins")? {
+            auth = auth.with_anonymous_users(enabled);
+        }
+        if let Some(length) = self.integer::<u32>("auth.minimum_password_length")? {
+            auth = auth.with_env("GOTRUE_PASSWORD_MIN_LENGTH", length.to_string());
+        }
+        if let Some(enabled) = self.bool("auth.enable_refresh_token_rotation")? {
+            auth = auth.with_env(
+                "GOTRUE_SECURITY_REFRESH_TOKEN_ROTATION_ENABLED",
+                enabled.to_string(),
+            );
+        }
+        if let Some(seconds) = self.integer::<u32>("auth.refresh_token_reuse_interval")? {
+            auth = auth.with_env(
+                "GOTRUE_SECURITY_REFRESH_TOKEN_REUSE_INTERVAL",
+                seconds.to_string(),
+            );
+        }
+        if let Some(enabled) = self.bool("auth.email.enable_confirmations")? {
+            auth = auth.with_mailer_autoconfirm(!enabled);
+        }
+        if let Some(enabled) = self.bool("auth.sms.enable_confirmations")? {
+            auth = auth.with_sms_autoconfirm(!enabled);
+        }
+        Ok(Some(auth))
+    }
+
+    /// Returns PostgREST configured from `[api]`, or `None` if it is disabled
+    pub fn postgrest(&self) -> anyhow::Result<Option<PostgREST>> {
+        if !self.enabled("api")? {
+            return Ok(None);
+        }
+        let mut postgrest = PostgREST::default();
+        if let Some(schemas) = self.strings("api.schemas")? {
+            postgrest = postgrest.with_schemas(schemas);
+        }
+        if let Some(schemas) = self.strings("api.extra_search_path")? {
+            postgrest = postgrest.with_db_extra_search_path(schemas);
+        }
+        if let Some(max_rows) = self.integer("api.max_rows")? {
+            postgrest = postgrest.with_max_rows(max_rows);
+        }
+        Ok(Some(postgrest))
+    }
+
+    /// Returns Storage configured from `[storage]`, or `None` if it is disabled
+    pub fn storage(&self) -> anyhow::Result<Option<Storage>> {
+        if !self.enabled("storage")? {
+            return Ok(None);
+        }
+        let mut storage = Storage::default();
+        if let Some(limit) = self.size("storage.file_size_limit")? {
+            storage = storage.with_file_size_limit(limit);
+        }
+        Ok(Some(storage))
+    }
+
+    /// Returns SQL creating the buckets in `[storage.buckets]`, if there are any
+    ///
+    /// Existing buckets with the same id are updated.
+    pub fn buckets_sql(&self) -> anyhow::Result<Option<String>> {
+        let Some(buckets) = self.table("storage.buckets")? else {
+            return Ok(None);
+        };
+        let mut sql = String::new();
+        for id in buckets.keys() {
+            let key = format!("storage.buckets.{}", id);
+            let public = self.bool(&format!("{}.public", key))?.unwrap_or(false);
+            let limit = match self.size(&format!("{}.file_size_limit", key))? {
+                Some(limit) => limit.to_string(),
+                None => "NULL".to_string(),
+            };
+            let mime_types = match self.strings(&format!("{}.allowed_mime_types", key))? {
+                Some(types) => format!(
+                    "ARRAY[{}]::text[]",
+                    types
+                        .iter()
+                        .map(|mime| quote(mime))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                None => "NULL".to_string(),
+            };
+            sql.push_str(&format!(
+                "INSERT INTO storage.buckets (id, name, public, file_size_limit, allowed_mime_types) \
+                 VALUES ({id}, {id}, {}, {}, {}) \
+                 ON CONFLICT (id) DO UPDATE SET public = EXCLUDED.public, \
+                 file_size_limit = EXCLUDED.file_size_limit, \
+                 allowed_mime_types = EXCLUDED.allowed_mime_types;\n",
+                public,
+                limit,
+                mime_types,
+                id = quote(id),
+            ));
+        }
+        Ok((!sql.is_empty()).then_some(sql))
+    }
+
+    /// Returns Realtime if `[realtime]` enables it
+    #[cfg(feature = "realtime")]
+    pub fn realtime(&self) -> anyhow::Result<Option<Realtime>> {
+        Ok(self.enabled("realtime")?.then(Realtime::default))
+    }
+
+    /// Returns Analytics if `[analytics]` enables it
+    ///
+    /// # Errors
+    /// Returns an error if `analytics.backend` is not `postgres`, the only backend the
+    /// module supports.
+    #[cfg(feature = "analytics")]
+    pub fn analytics(&self) -> anyhow::Result<Option<Analytics>> {
+        if !self.enabled("analytics")? {
+            return Ok(None);
+        }
+        if let Some(backend) = self.string("analytics.backend")? {
+            if backend != "postgres" {
+                bail!(
+                    "analytics.backend '{}' is not supported; only 'postgres' is",
+                    backend
+                );
+            }
+        }
+        Ok(Some(Analytics::default()))
+    }
+
+    /// Returns Functions configured from `[edge_runtime]` and `[functions]`
+    ///
+    /// `edge_runtime.secrets` become environment variables. JWT verification is turned
+    /// off if any function sets `verify_jwt = false`, since the runtime applies it to
+    /// all functions. Mount [`SupabaseProject::functions_dir`] to serve the functions.
+    #[cfg(feature = "functions")]
+    pub fn functions(&self) -> anyhow::Result<Option<Functions>> {
+        if !self.enabled("edge_runtime")? {
+            return Ok(None);
+        }
+        let mut functions = Functions::default();
+        if let Some(secrets) = self.table("edge_runtime.secrets")? {
+            for name in secrets.keys() {
+                if let Some(value) = self.string(&format!("edge_runtime.secrets.{}", name))? {
+                    functions = functions.with_env(name, value);
+                }
+            }
+        }
+        if let Some(declared) = self.table("functions")? {
+            let mut verify_jwt = true;
+            for name in declared.keys() {
+                if self.bool(&format!("functions.{}.verify_jwt", name))? == Some(false) {
+                    verify_jwt = false;
+                }
+            }
+            functions = functions.with_verify_jwt(verify_jwt);
+        }
+        Ok(Some(functions))
+    }
+
+    /// Adds the project's services, JWT secret, migrations, seed and buckets to `stack`
+    ///
+    /// Services already on `stack` are replaced by the project's.
+    ///
+    /// # Errors
+    /// Returns an error if a setting has the wrong type or a SQL file cannot be read.
+    pub fn apply(&self, mut stack: SupabaseStack) -> anyhow::Result<SupabaseStack> {
+        if let Some(secret) = self.jwt_secret()? {
+            stack = stack.with_jwt_secret(secret);
+        }
+        if let Some(auth) = self.auth()? {
+            stack = stack.with_auth(auth);
+        }
+        if let Some(postgrest) = self.postgrest()? {
+            stack = stack.with_postgrest(postgrest);
+        }
+        let storage = self.storage()?;
+        let has_storage = storage.is_some();
+        if let Some(storage) = storage {
+            stack = stack.with_storage(storage);
+        }
+
+        stack = stack.with_migrations(self.migrations()?);
+        for path in self.seed_files()? {
+            let sql = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            stack = stack.with_setup_sql(sql);
+        }
+        if has_storage {
+            if let Some(sql) = self.buckets_sql()? {
+                stack = stack.with_setup_sql(sql);
+            }
+        }
+        Ok(stack)
+    }
+
+    /// Returns the value at a dotted `key`, if it is set
+    fn value(&self, key: &str) -> Option<&Value> {
+        let mut parts = key.split('.');
+        let mut value = self.config.get(parts.next()?)?;
+        for part in parts {
+            value = value.as_table()?.get(part)?;
+        }
+        Some(value)
+    }
+
+    /// Returns whether the section at `key` is enabled; sections are unless `enabled = false`
+    fn enabled(&self, key: &str) -> anyhow::Result<bool> {
+        Ok(self.bool(&format!("{}.enabled", key))?.unwrap_or(true))
+    }
+
+    /// Returns the table at `key`
+    fn table(&self, key: &str) -> anyhow::Result<Option<&Table>> {
+        match self.value(key) {
+            None => Ok(None),
+            Some(Value::Table(table)) => Ok(Some(table)),
+            Some(other) => bail!("{} must be a table, found {}", key, other.type_str()),
+        }
+    }
+
+    /// Returns the string at `key`, reading `env(NAME)` values from the environment
+    fn string(&self, key: &str) -> anyhow::Result<Option<String>> {
+        match self.value(key) {
+            None => Ok(None),
+            Some(Value::String(value)) => resolve_env(value).map(Some),
+            Some(other) => bail!("{} must be a string, found {}", key, other.type_str()),
+        }
+    }
+
+    /// Returns the list of strings at `key`
+    fn strings(&self, key: &str) -> anyhow::Result<Option<Vec<String>>> {
+        match self.value(key) {
+            None => Ok(None),
+            Some(Value::Array(values)) => values
+                .iter()
+                .map(|value| match value {
+                    Value::String(value) => resolve_env(value),
+                    other => bail!("{} must hold strings, found {}", key, other.type_str()),
+                })
+                .collect::<anyhow::Result<Vec<_>>>()
+                .map(Some),
+            Some(other) => bail!("{} must be an array, found {}", key, other.type_str()),
+        }
+    }
+
+    /// Returns the boolean at `key`
+    fn bool(&self, key: &str) -> anyhow::Result<Option<bool>> {
+        match self.value(key) {
+            None => Ok(None),
+            Some(Value::Boolean(value)) => Ok(Some(*value)),
+            Some(other) => bail!("{} must be a boolean, found {}", key, other.type_str()),
+        }
+    }
+
+    /// Returns the non-negative integer at `key`
+    fn integer<T: TryFrom<i64>>(&self, key: &str) -> anyhow::Result<Option<T>> {
+        match self.value(key) {
+            None => Ok(None),
+            Some(Value::Integer(value)) => match T::try_from(*value) {
+                Ok(value) => Ok(Some(value)),
+                Err(_) => bail!("{} is out of range: {}", key, value),
+            },
+            Some(other) => bail!("{} must be an integer, found {}", key, other.type_str()),
+        }
+    }
+
+    /// Returns the size in bytes at `key`, given as bytes or a string like `"50MiB"`
+    fn size(&self, key: &str) -> anyhow::Result<Option<u64>> {
+        match self.value(key) {
+            Some(Value::String(_)) => match self.string(key)? {
+                Some(size) => parse_size(&size)
+                    .map(Some)
+                    .with_context(|| format!("{} is not a size", key)),
+                None => Ok(None),
+            },
+            _ => self.integer(key),
+        }
+    }
+}
+
+/// Returns the files in `dir` whose names match `pattern`
+fn sql_files(dir: &Path, pattern: &str) -> anyhow::Result<Vec<PathBuf>> {
+    let entries = fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let matched = path
+            .file_name()
+            .is_some_and(|name| wildcard_match(pattern, &name.to_string_lossy()));
+        if matched && path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Matches `name` against `pattern`, where `*` matches any run of characters and `?`
+/// any single character
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the name position it was tried at
+    let mut star = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Reads `env(NAME)` values from the environment, returning other values unchanged
+fn resolve_env(value: &str) -> anyhow::Result<String> {
+    match value
+        .strip_prefix("env(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        Some(name) => {
+            std::env::var(name).with_context(|| format!("environment variable {} is not set", name))
+        }
+        None => Ok(value.to_string()),
+    }
+}
+
+/// Parses a size like `50MiB`, `5MB` or `1024` into bytes
+fn parse_size(size: &str) -> anyhow::Result<u64> {
+    let size = size.trim();
+    let split = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(split);
+    let number: u64 = number
+        .parse()
+        .with_context(|| format!("invalid size '{}'", size))?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1000,
+        "kib" => 1 << 10,
+        "mb" => 1000 * 1000,
+        "mib" => 1 << 20,
+        "gb" => 1000 * 1000 * 1000,
+        "gib" => 1 << 30,
+        other => bail!("unknown size unit '{}' in '{}'", other, size),
+    };
+    number
+        .checked_mul(multiplier)
+        .with_context(|| format!("size '{}' is too large", size))
+}
+
+/// Quotes `value` as a SQL string literal
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(test)]
+#[cfg(feature = "project")]
+mod tests {
+    use super::*;
+    use crate::Migration;
+    use testcontainers_modules::testcontainers::Image;
+
+    /// A `config.toml` using every mapped setting
+    const CONFIG: &str = r#"
+project_id = "todos"
+
+[api]
+schemas = ["public", "graphql_public"]
+extra_search_path = ["public", "extensions"]
+max_rows = 500
+
+[db.seed]
+sql_paths = ["./seed.sql", "./seeds/*.sql"]
+
+[storage]
+file_size_limit = "50MiB"
+
+[storage.buckets.avatars]
+public = true
+file_size_limit = "1MiB"
+allowed_mime_types = ["image/png", "image/jpeg"]
+
+[auth]
+site_url = "http://127.0.0.1:3000"
+additional_redirect_urls = ["https://127.0.0.1:3000", "http://localhost:3000"]
+jwt_expiry = 600
+enable_signup = false
+enable_anonymous_sign_ins = true
+
+[auth.email]
+enable_confirmations = false
+
+[realtime]
+enabled = false
+"#;
+
+    /// Returns a project parsed from `config` in a directory that does not exist
+    fn project(config: &str) -> SupabaseProject {
+        SupabaseProject {
+            dir: PathBuf::from("/nonexistent/supabase"),
+            config: SupabaseProject::parse(config).unwrap(),
+        }
+    }
+
+    /// Returns the value of `key` among an image's environment variables
+    fn env<I: Image>(image: &I, key: &str) -> Option<String> {
+        image
+            .env_vars()
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.into_owned())
+    }
+
+    /// Creates a temporary project directory holding `files`
+    fn project_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!(
+            "supabase-tc-project-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&root);
+        for (path, contents) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn test_auth_settings() {
+        let auth = project(CONFIG).auth().unwrap().unwrap();
+
+        assert_eq!(
+            env(&auth, "GOTRUE_SITE_URL").as_deref(),
+            Some("http://127.0.0.1:3000")
+        );
+        assert_eq!(
+            env(&auth, "GOTRUE_URI_ALLOW_LIST").as_deref(),
+            Some("https://127.0.0.1:3000,http://localhost:3000")
+        );
+        assert_eq!(env(&auth, "GOTRUE_JWT_EXP").as_deref(), Some("600"));
+        assert_eq!(env(&auth, "GOTRUE_DISABLE_SIGNUP").as_deref(), Some("true"));
+        assert_eq!(
+            env(&auth, "GOTRUE_EXTERNAL_ANONYMOUS_USERS_ENABLED").as_deref(),
+            Some("true")
+        );
+        assert_eq!(
+            env(&auth, "GOTRUE_MAILER_AUTOCONFIRM").as_deref(),
+            Some("true")
+        );
+    }
+
+    #[test]
+    fn test_api_settings() {
+        let postgrest = project(CONFIG).postgrest().unwrap().unwrap();
+
+        assert_eq!(
+            env(&postgrest, "PGRST_DB_SCHEMAS").as_deref(),
+            Some("public,graphql_public")
+        );
+        assert_eq!(
+            env(&postgrest, "PGRST_DB_EXTRA_SEARCH_PATH").as_deref(),
+            Some("public,extensions")
+        );
+        assert_eq!(env(&postgrest, "PGRST_DB_MAX_ROWS").as_deref(), Some("500"));
+    }
+
+    #[test]
+    fn test_storage_settings_and_buckets() {
+        let project = project(CONFIG);
+        let storage = project.storage().unwrap().unwrap();
+        assert_eq!(
+            env(&storage, "FILE_SIZE_LIMIT").as_deref(),
+            Some("52428800")
+        );
+
+        let sql = project.buckets_sql().unwrap().unwrap();
+        assert!(
+            sql.contains("VALUES ('avatars', 'avatars', true, 1048576, ARRAY['image/png', 'image/jpeg']::text[])"),
+            "{}",
+            sql
+        );
+        assert!(sql.contains("ON CONFLICT (id) DO UPDATE"), "{}", sql);
+    }
+
+    #[test]
+    fn test_disabled_sections() {
+        let project = project("[auth]\nenabled = false\n[api]\nenabled = false\n");
+
+        assert!(project.auth().unwrap().is_none());
+        assert!(project.postgrest().unwrap().is_none());
+        // Sections that are not mentioned are enabled, as with the CLI
+        assert!(project.storage().unwrap().is_some());
+        assert!(project.buckets_sql().unwrap().is_none());
+    }
+
+    #[test]
+    #[cfg(all(feature = "analytics", feature = "functions", feature = "realtime"))]
+    fn test_optional_services() {
+        let defaults = project(CONFIG);
+        assert!(defaults.realtime().unwrap().is_none());
+        assert!(defaults.analytics().unwrap().is_some());
+        assert!(defaults.functions().unwrap().is_some());
+
+        let project = project(
+            "[analytics]\nbackend = \"bigquery\"\n\n[edge_runtime.secrets]\nSTRIPE_KEY = \"sk_test\"\n\n[functions.hello]\nverify_jwt = false\n",
+        );
+        let error = project.analytics().unwrap_err().to_string();
+        assert!(error.contains("'bigquery' is not supported"), "{}", error);
+
+        let functions = project.functions().unwrap().unwrap();
+        assert_eq!(env(&functions, "STRIPE_KEY").as_deref(), Some("sk_test"));
+        assert_eq!(env(&functions, "VERIFY_JWT").as_deref(), Some("false"));
+    }
+
+    #[test]
+    fn test_wrong_types_are_reported() {
+        let error = project("[api]\nmax_rows = \"many\"\n")
+            .postgrest()
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "api.max_rows must be an integer, found string");
+
+        let error = project("[auth]\njwt_expiry = -1\n")
+            .auth()
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "auth.jwt_expiry is out of range: -1");
+    }
+
+    #[test]
+    fn test_env_values() {
+        std::env::set_var("SUPABASE_TC_PROJECT_TEST_SITE", "http://example.test");
+        let project = project(
+            "[auth]\nsite_url = \"env(SUPABASE_TC_PROJECT_TEST_SITE)\"\njwt_secret = \"env(SUPABASE_TC_PROJECT_TEST_UNSET)\"\n",
+        );
+
+        let auth = project.auth().unwrap().unwrap();
+        assert_eq!(
+            env(&auth, "GOTRUE_SITE_URL").as_deref(),
+            Some("http://example.test")
+        );
+        let error = project.jwt_secret().unwrap_err().to_string();
+        assert!(
+            error.contains("SUPABASE_TC_PROJECT_TEST_UNSET"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_load_migrations_and_seeds() {
+        let root = project_dir(
+            "load",
+            &[
+                ("supabase/config.toml", CONFIG),
+                ("supabase/migrations/20240102000000_second.sql", "SELECT 2;"),
+                ("supabase/migrations/20240101000000_first.sql", "SELECT 1;"),
+                ("supabase/migrations/README.md", "not a migration"),
+                ("supabase/seed.sql", "SELECT 'seed';"),
+                ("supabase/seeds/b.sql", "SELECT 'b';"),
+                ("supabase/seeds/a.sql", "SELECT 'a';"),
+            ],
+        );
+
+        // The project root and the supabase directory both work
+        for path in [root.clone(), root.join("supabase")] {
+            let project = SupabaseProject::load(&path).unwrap();
+            let names = |paths: Vec<PathBuf>| {
+                paths
+                    .iter()
+                    .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+                    .collect::<Vec<_>>()
+            };
+
+            assert_eq!(project.project_id().unwrap().as_deref(), Some("todos"));
+            let migrations = project.migrations().unwrap();
+            assert_eq!(
+                migrations
+                    .migrations()
+                    .iter()
+                    .map(Migration::file_name)
+                    .collect::<Vec<_>>(),
+                ["20240101000000_first.sql", "20240102000000_second.sql"]
+            );
+            assert_eq!(
+                names(project.seed_files().unwrap()),
+                ["seed.sql", "a.sql", "b.sql"]
+            );
+            assert!(project.functions_dir().is_none());
+        }
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_seed_disabled_and_missing_default() {
+        let root = project_dir(
+            "seed",
+            &[("supabase/config.toml", "[db.seed]\nenabled = false\n")],
+        );
+        let project = SupabaseProject::load(&root).unwrap();
+        assert!(project.seed_files().unwrap().is_empty());
+        assert!(project.migrations().unwrap().migrations().is_empty());
+
+        fs::write(root.join("supabase/config.toml"), "").unwrap();
+        let project = SupabaseProject::load(&root).unwrap();
+        // No seed.sql: nothing to apply
+        assert!(project.seed_files().unwrap().is_empty());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_load_without_config() {
+        let error = SupabaseProject::load("/nonexistent")
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("no config.toml in"), "{}", error);
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024").unwrap(), 1024);
+        assert_eq!(parse_size("50MiB").unwrap(), 50 * 1024 * 1024);
+        assert_eq!(parse_size("5MB").unwrap(), 5_000_000);
+        assert_eq!(parse_size("1 GiB").unwrap(), 1 << 30);
+        assert!(parse_size("5 parsecs").is_err());
+        assert!(parse_size("MiB").is_err());
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.sql", "seed.sql"));
+        assert!(wildcard_match("seed?.sql", "seed1.sql"));
+        assert!(wildcard_match("a*b*c", "axxbyyc"));
+        assert!(!wildcard_match("*.sql", "seed.sql.bak"));
+        assert!(!wildcard_match("seed.sql", "seed2.sql"));
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("it's"), "'it''s'");
+    }
+}
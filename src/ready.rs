@@ -0,0 +1,84 @@
+//! Readiness checks shared by the service images.
+//!
+//! Each HTTP-capable image accepts a [`ReadyCheck`] through its `with_ready_check`
+//! builder. The default is an HTTP probe of the service's health route, built on
+//! testcontainers' [`WaitFor::http`]; [`ReadyCheck::Log`] restores waiting for the
+//! image's startup log line.
+//!
+//! Functions has no check: edge-runtime serves no route of its own, as every request
+//! goes to the user's main function, so it keeps waiting for its "Listening on" line.
+
+use testcontainers_modules::testcontainers::core::wait::HttpWaitStrategy;
+use testcontainers_modules::testcontainers::core::{ContainerPort, WaitFor};
+
+/// How a container is considered ready after it starts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReadyCheck {
+    /// Wait for the image's startup log line
+    Log,
+    /// Wait until `GET <path>` on the service port returns 200
+    Http(String),
+}
+
+impl ReadyCheck {
+    /// Creates an HTTP check for `path`
+    pub fn http(path: impl Into<String>) -> Self {
+        ReadyCheck::Http(path.into())
+    }
+
+    /// Returns the wait condition for this check
+    ///
+    /// `port` is the container port HTTP checks are sent to and `log` is the
+    /// image's startup log condition.
+    // `http` alone enables none of the images using it
+    #[cfg_attr(
+        not(any(
+            feature = "analytics",
+            feature = "auth",
+            feature = "postgrest",
+            feature = "realtime",
+            feature = "storage",
+            feature = "toxiproxy",
+            feature = "vector"
+        )),
+        allow(dead_code)
+    )]
+    pub(crate) fn wait_for(&self, port: u16, log: WaitFor) -> WaitFor {
+        match self {
+            ReadyCheck::Log => log,
+            ReadyCheck::Http(path) => WaitFor::http(
+                HttpWaitStrategy::new(path.as_str())
+                    .with_port(ContainerPort::Tcp(port))
+                    .with_expected_status_code(200u16),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "http")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_constructor() {
+        assert_eq!(
+            ReadyCheck::http("/health"),
+            ReadyCheck::Http("/health".into())
+        );
+    }
+
+    #[test]
+    fn test_log_check_uses_log_condition() {
+        let condition =
+            ReadyCheck::Log.wait_for(4000, WaitFor::message_on_stdout("Server started"));
+        assert!(matches!(condition, WaitFor::Log(_)));
+    }
+
+    #[test]
+    fn test_http_check_uses_http_condition() {
+        let condition = ReadyCheck::http("/health")
+            .wait_for(4000, WaitFor::message_on_stdout("Server started"));
+        assert!(matches!(condition, WaitFor::Http(_)));
+    }
+}
@@ -68,10 +68,13 @@ use testcontainers_modules::testcontainers::core::{
 };
 use testcontainers_modules::testcontainers::{Image, TestcontainersError};
 
+use crate::overrides::{image_name, image_tag, pin_digest, with_registry};
+use crate::{ReadyCheck, SupabaseVersion};
+
 /// Default image name for Supabase Realtime
 const NAME: &str = "supabase/realtime";
 /// Default image tag version
-const TAG: &str = "v2.33.58";
+const TAG: &str = crate::consts::REALTIME_TAG;
 /// Default port for Supabase Realtime WebSocket server
 pub const REALTIME_PORT: u16 = 4000;
 
@@ -106,8 +109,12 @@ pub const REALTIME_PORT: u16 = 4000;
 pub struct Realtime {
     /// Environment variables to be passed to the container
     env_vars: BTreeMap<String, String>,
+    /// Docker image name
+    name: String,
     /// Docker image tag version
     tag: String,
+    /// How the container is considered ready
+    ready_check: ReadyCheck,
 }
 
 impl Realtime {
@@ -311,6 +318,37 @@ impl Realtime {
         self
     }
 
+    /// Sets the Docker image name, e.g. a fork or a copy in a private registry.
+    ///
+    /// `SUPABASE_TC_REGISTRY` still prefixes names without a registry.
+    pub fn with_image_name(mut self, name: impl Into<String>) -> Self {
+        self.name = with_registry(&name.into());
+        self
+    }
+
+    /// Pins the image to a digest such as `sha256:4c1e...`.
+    ///
+    /// Docker pulls the digest and ignores the tag; [`Realtime::with_tag`] removes the pin.
+    pub fn with_digest(mut self, digest: impl AsRef<str>) -> Self {
+        self.tag = pin_digest(&self.tag, digest.as_ref());
+        self
+    }
+
+    /// Sets the tag from a [`SupabaseVersion`].
+    pub fn with_version(self, version: &SupabaseVersion) -> Self {
+        self.with_tag(version.realtime())
+    }
+
+    /// Sets how the container is considered ready.
+    ///
+    /// Default is [`ReadyCheck::Http`] on `/api/ping`, which answers once the Phoenix
+    /// endpoint is serving requests. [`ReadyCheck::Log`] waits for the startup log line
+    /// instead.
+    pub fn with_ready_check(mut self, check: ReadyCheck) -> Self {
+        self.ready_check = check;
+        self
+    }
+
     /// Adds a custom environment variable.
     ///
     /// Use this for Realtime configuration options not covered by other methods.
@@ -356,14 +394,16 @@ impl Default for Realtime {
 
         Self {
             env_vars,
-            tag: TAG.to_string(),
+            name: image_name("REALTIME", NAME),
+            tag: image_tag("REALTIME", TAG),
+            ready_check: ReadyCheck::http("/api/ping"),
         }
     }
 }
 
 impl Image for Realtime {
     fn name(&self) -> &str {
-        NAME
+        &self.name
     }
 
     fn tag(&self) -> &str {
@@ -371,8 +411,10 @@ impl Image for Realtime {
     }
 
     fn ready_conditions(&self) -> Vec<WaitFor> {
-        // Realtime logs "Realtime has started" when the Phoenix endpoint is ready
-        vec![WaitFor::message_on_stdout("Realtime has started")]
+        vec![self.ready_check.wait_for(
+            REALTIME_PORT,
+            WaitFor::message_on_stdout("Realtime has started"),
+        )]
     }
 
     fn expose_ports(&self) -> &[ContainerPort] {
@@ -455,6 +497,33 @@ mod tests {
         assert_eq!(realtime.tag(), TAG);
     }
 
+    #[test]
+    fn test_default_tag_matches_tested_version() {
+        assert_eq!(
+            Realtime::default().tag(),
+            SupabaseVersion::tested().realtime()
+        );
+    }
+
+    #[test]
+    fn test_with_version_sets_tag() {
+        let version = SupabaseVersion::tested().with_realtime("v2.30.0");
+        assert_eq!(Realtime::default().with_version(&version).tag(), "v2.30.0");
+    }
+
+    #[test]
+    fn test_with_image_name() {
+        let realtime = Realtime::default().with_image_name("ghcr.io/acme/realtime");
+        assert_eq!(realtime.name(), "ghcr.io/acme/realtime");
+    }
+
+    #[test]
+    fn test_with_digest_pins_tag() {
+        let realtime = Realtime::default().with_tag("v1").with_digest("sha256:abc");
+        assert_eq!(realtime.tag(), "v1@sha256:abc");
+        assert_eq!(realtime.with_tag("v2").tag(), "v2");
+    }
+
     #[test]
     fn test_realtime_port_constant() {
         assert_eq!(REALTIME_PORT, 4000);
@@ -740,6 +809,15 @@ mod tests {
         let realtime = Realtime::default();
         let conditions = realtime.ready_conditions();
         assert_eq!(conditions.len(), 1);
+        assert!(matches!(conditions[0], WaitFor::Http(_)));
+    }
+
+    #[test]
+    fn test_with_ready_check_log() {
+        let realtime = Realtime::default().with_ready_check(ReadyCheck::Log);
+        let conditions = realtime.ready_conditions();
+        assert_eq!(conditions.len(), 1);
+        assert!(matches!(conditions[0], WaitFor::Log(_)));
     }
 
     #[test]
@@ -0,0 +1,317 @@
+/*! Reusing containers across test runs.
+
+testcontainers can keep a container running after the test binary exits and attach to
+it on the next run, but only finds it by name, network and labels. Reusing a container
+whose configuration changed since would silently test against the old one.
+
+[`Reusable::reusable`] hashes a container request's configuration (image, tag,
+environment, command, entrypoint, network, ports, mounts and the files copied into the
+container, by target path and contents) and puts the hash into the
+container's name and a label. The next run with the same configuration attaches to the
+running container; a changed configuration starts a new one.
+
+# Features
+
+- Works with every image in this crate, and any other testcontainers image
+- Reused containers are never removed; stop them with `docker rm -f` when done
+- `SupabaseStack::with_reuse` (with the `stack` feature) reuses a whole stack
+
+# Example
+
+```rust,no_run
+use supabase_testcontainers_modules::Reusable;
+use testcontainers::runners::AsyncRunner;
+use testcontainers_modules::postgres::Postgres;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    // Starts PostgreSQL on the first run, attaches to it on the following ones
+    let postgres = Postgres::default().reusable().start().await?;
+    Ok(())
+}
+```
+*/
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+use testcontainers_modules::testcontainers::core::CopyToContainer;
+use testcontainers_modules::testcontainers::{ContainerRequest, Image, ImageExt, ReuseDirective};
+
+/// Label holding the configuration hash
+pub const REUSE_HASH_LABEL: &str = "supabase-testcontainers.config-hash";
+/// Prefix of generated container names
+const NAME_PREFIX: &str = "supabase-tc";
+/// Hex characters of the hash used in names and labels
+const HASH_LENGTH: usize = 12;
+
+/// Marks container requests for reuse across test runs
+///
+/// Implemented for every image and container request.
+pub trait Reusable<I: Image> {
+    /// Reuses a running container with the same configuration, if there is one
+    ///
+    /// The container is named after the configuration hash: `<name>-<hash>` when a
+    /// name was set, `supabase-tc-<image>-<hash>` otherwise.
+    fn reusable(self) -> ContainerRequest<I>;
+}
+
+impl<R, I> Reusable<I> for R
+where
+    R: Into<ContainerRequest<I>>,
+    I: Image,
+{
+    fn reusable(self) -> ContainerRequest<I> {
+        let request = self.into();
+        let hash = request_hash(&request);
+        let name = match request.container_name() {
+            Some(name) => format!("{}-{}", name, hash),
+            None => format!("{}-{}-{}", NAME_PREFIX, image_basename(&request), hash),
+        };
+        request
+            .with_label(REUSE_HASH_LABEL, &hash)
+            .with_container_name(name)
+            .with_reuse(ReuseDirective::Always)
+    }
+}
+
+/// Hashes the parts of a request that make up the container's configuration
+fn request_hash<I: Image>(request: &ContainerRequest<I>) -> String {
+    let env_vars: BTreeMap<String, String> = request
+        .env_vars()
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    let labels: BTreeMap<&String, &String> = request
+        .labels()
+        .iter()
+        .filter(|(key, _)| key.as_str() != REUSE_HASH_LABEL)
+        .collect();
+    let cmd: Vec<String> = request.cmd().map(|arg| arg.into_owned()).collect();
+    let mounts: Vec<_> = request.mounts().collect();
+    let copies: Vec<String> = request.copy_to_sources().map(copy_fingerprint).collect();
+
+    config_hash(&format!(
+        "image={}\nentrypoint={:?}\ncmd={:?}\nenv={:?}\nlabels={:?}\nnetwork={:?}\nports={:?}\n\
+         mounts={:?}\ncopies={:?}\n",
+        request.descriptor(),
+        request.entrypoint(),
+        cmd,
+        env_vars,
+        labels,
+        request.network(),
+        request.ports(),
+        mounts,
+        copies,
+    ))
+}
+
+/// Describes a file copied into the container by its target path and contents
+///
+/// testcontainers exposes a copy only through `Debug`, which holds the target and
+/// either the data or the source path; file sources are hashed by their contents.
+fn copy_fingerprint(copy: &CopyToContainer) -> String {
+    let debug = format!("{:?}", copy);
+    match file_source(&debug) {
+        Some(path) => {
+            let mut hasher = Sha256::new();
+            hash_path(&path, &mut hasher);
+            format!("{} contents={:x}", debug, hasher.finalize())
+        }
+        None => debug,
+    }
+}
+
+/// Returns the source path from the `Debug` output of a file copy
+fn file_source(debug: &str) -> Option<PathBuf> {
+    let literal = debug.split_once("source: File(\"")?.1;
+    let mut path = String::new();
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(PathBuf::from(path)),
+            '\\' => match chars.next()? {
+                'n' => path.push('\n'),
+                'r' => path.push('\r'),
+                't' => path.push('\t'),
+                '0' => path.push('\0'),
+                'u' => {
+                    let code: String = chars.by_ref().skip(1).take_while(|c| *c != '}').collect();
+                    path.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+                }
+                escaped => path.push(escaped),
+            },
+            c => path.push(c),
+        }
+    }
+    None
+}
+
+/// Feeds the contents of a file, or of a directory's files with their names, to `hasher`
+///
+/// Unreadable paths are hashed by their error; starting the container reports them.
+fn hash_path(path: &Path, hasher: &mut Sha256) {
+    if path.is_dir() {
+        let mut entries: Vec<PathBuf> = match std::fs::read_dir(path) {
+            Ok(entries) => entries.filter_map(|e| Some(e.ok()?.path())).collect(),
+            Err(e) => return hasher.update(e.kind().to_string()),
+        };
+        entries.sort();
+        for entry in entries {
+            hasher.update(entry.file_name().unwrap_or_default().as_encoded_bytes());
+            hash_path(&entry, hasher);
+        }
+    } else {
+        match std::fs::read(path) {
+            Ok(bytes) => hasher.update(bytes),
+            Err(e) => hasher.update(e.kind().to_string()),
+        }
+    }
+}
+
+/// Returns a short, stable hex hash of `config`
+pub(crate) fn config_hash(config: &str) -> String {
+    Sha256::digest(config.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>()[..HASH_LENGTH]
+        .to_string()
+}
+
+/// Returns the image name without its registry and organization
+fn image_basename<I: Image>(request: &ContainerRequest<I>) -> String {
+    let name = request.image().name();
+    name.rsplit('/').next().unwrap_or(name).to_string()
+}
+
+#[cfg(test)]
+#[cfg(feature = "reuse")]
+mod tests {
+    use super::*;
+    use testcontainers_modules::postgres::Postgres;
+
+    #[test]
+    fn test_config_hash_is_stable() {
+        assert_eq!(config_hash("a"), config_hash("a"));
+        assert_ne!(config_hash("a"), config_hash("b"));
+        assert_eq!(config_hash("a").len(), HASH_LENGTH);
+        // SHA-256 of "a"
+        assert_eq!(config_hash("a"), "ca978112ca1b");
+    }
+
+    #[test]
+    fn test_reusable_sets_name_label_and_directive() {
+        let request = Postgres::default().reusable();
+        let hash = request.labels().get(REUSE_HASH_LABEL).cloned().unwrap();
+
+        assert_eq!(request.reuse(), ReuseDirective::Always);
+        assert_eq!(
+            request.container_name().as_deref(),
+            Some(format!("supabase-tc-postgres-{}", hash).as_str())
+        );
+    }
+
+    #[test]
+    fn test_reusable_keeps_given_name() {
+        let request = Postgres::default().with_container_name("my-db").reusable();
+
+        let name = request.container_name().clone().unwrap();
+        assert!(name.starts_with("my-db-"), "{}", name);
+    }
+
+    #[test]
+    fn test_hash_changes_with_configuration() {
+        let hash = |request: ContainerRequest<Postgres>| {
+            request.reusable().labels().get(REUSE_HASH_LABEL).cloned()
+        };
+        let base = hash(Postgres::default().into());
+
+        assert_eq!(base, hash(Postgres::default().into()));
+        assert_ne!(base, hash(Postgres::default().with_tag("16-alpine")));
+        assert_ne!(base, hash(Postgres::default().with_env_var("A", "1")));
+        assert_ne!(base, hash(Postgres::default().with_cmd(["-c", "fsync=on"])));
+        assert_ne!(base, hash(Postgres::default().with_network("other")));
+    }
+
+    #[test]
+    fn test_hash_changes_with_copied_files_and_mounts() {
+        use testcontainers_modules::testcontainers::core::Mount;
+
+        let hash = |request: ContainerRequest<Postgres>| {
+            request.reusable().labels().get(REUSE_HASH_LABEL).cloned()
+        };
+        let data = |bytes: &[u8]| Postgres::default().with_copy_to("/init.sql", bytes.to_vec());
+        assert_eq!(hash(data(b"SELECT 1")), hash(data(b"SELECT 1")));
+        assert_ne!(hash(data(b"SELECT 1")), hash(data(b"SELECT 2")));
+        assert_ne!(
+            hash(data(b"SELECT 1")),
+            hash(Postgres::default().with_copy_to("/other.sql", b"SELECT 1".to_vec()))
+        );
+
+        // File sources are hashed by contents, not only by path
+        let dir = std::env::temp_dir().join(format!("reuse-hash-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("init \"quoted\".sql");
+        let copied = || Postgres::default().with_copy_to("/init.sql", file.as_path());
+        std::fs::write(&file, "SELECT 1").unwrap();
+        let before = hash(copied());
+        assert_eq!(before, hash(copied()));
+        std::fs::write(&file, "SELECT 2").unwrap();
+        assert_ne!(before, hash(copied()));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_ne!(
+            hash(Postgres::default().into()),
+            hash(Postgres::default().with_mount(Mount::tmpfs_mount("/var/lib/postgresql/data")))
+        );
+    }
+
+    #[test]
+    fn test_file_source() {
+        assert_eq!(
+            file_source(r#"CopyToContainer { target: "/t", source: File("/a \"b\"\\c") }"#),
+            Some(PathBuf::from(r#"/a "b"\c"#))
+        );
+        assert_eq!(
+            file_source(r#"CopyToContainer { target: "/t", source: Data([1, 2]) }"#),
+            None
+        );
+    }
+
+    #[test]
+    fn test_file_source_reads_copy_debug_output() {
+        // Guards the parsing above against changes of testcontainers' Debug output
+        let path = PathBuf::from("/tmp/dir \"quoted\"/seed.sql");
+        let copy = CopyToContainer::new(path.clone(), "/docker-entrypoint-initdb.d/seed.sql");
+        assert_eq!(file_source(&format!("{:?}", copy)), Some(path));
+
+        let data = CopyToContainer::new(b"SELECT 1;".to_vec(), "/seed.sql");
+        assert_eq!(file_source(&format!("{:?}", data)), None);
+    }
+
+    #[test]
+    #[cfg(feature = "toxiproxy")]
+    fn test_hash_changes_with_toxiproxy_proxies() {
+        use crate::Toxiproxy;
+
+        let hash =
+            |toxiproxy: Toxiproxy| toxiproxy.reusable().labels().get(REUSE_HASH_LABEL).cloned();
+        let proxied = |upstream: &str| Toxiproxy::default().with_proxy("db", 15432, upstream);
+
+        assert_eq!(hash(proxied("db:5432")), hash(proxied("db:5432")));
+        // Same ports, different upstream: only the copied proxy list differs
+        assert_ne!(hash(proxied("db:5432")), hash(proxied("other-db:5432")));
+        assert_ne!(hash(Toxiproxy::default()), hash(proxied("db:5432")));
+    }
+
+    #[test]
+    fn test_hash_ignores_previous_hash_label() {
+        let once = Postgres::default().reusable();
+        let hash = once.labels().get(REUSE_HASH_LABEL).cloned();
+        let name = once.container_name().clone().unwrap();
+        let twice = once.with_container_name("fixed").reusable();
+
+        assert_eq!(twice.labels().get(REUSE_HASH_LABEL).cloned(), hash);
+        assert_ne!(twice.container_name().clone().unwrap(), name);
+    }
+}
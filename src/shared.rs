@@ -0,0 +1,395 @@
+/*! One [`SupabaseStack`] shared by every test in a binary.
+
+`#[tokio::test]` gives each test its own runtime, which is shut down when the test
+returns. Containers and database connections created on one test's runtime break once
+that runtime is gone, so a stack cannot simply be stored in a `static` and reused.
+
+[`SupabaseStack::shared`] starts the stack on first use on a background runtime that
+lives as long as the process, and returns a [`SharedStack`] handle. The handle's
+operations run on that runtime too, so they can be awaited from any test's runtime.
+The containers are removed when the process exits.
+
+# Architecture
+
+1. The first call to [`SupabaseStack::shared`] starts the stack on the background runtime
+2. Later calls return the same [`SharedStack`]; their configuration is ignored
+3. [`SharedStack::reset`], [`SharedStack::take_snapshot`] and [`SharedStack::isolate`]
+   run on the background runtime
+4. An `atexit` handler stops the containers when the test binary exits
+
+A failed start is not cached; the next call tries again.
+
+# Example
+
+```rust,no_run
+use supabase_testcontainers_modules::{Auth, PostgREST, SharedStack, SupabaseStack};
+
+async fn stack() -> anyhow::Result<&'static SharedStack> {
+    SupabaseStack::new()
+        .with_auth(Auth::default())
+        .with_postgrest(PostgREST::default())
+        .shared()
+        .await
+}
+
+#[tokio::test]
+async fn first_test() -> anyhow::Result<()> {
+    let stack = stack().await?;
+    stack.reset().await?;
+    // ... run the test against stack.rest_url() ...
+    Ok(())
+}
+
+#[tokio::test]
+async fn second_test() -> anyhow::Result<()> {
+    // Same containers as first_test
+    let stack = stack().await?;
+    stack.reset().await?;
+    Ok(())
+}
+```
+*/
+
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::path::PathBuf;
+use std::sync::{Arc, Once, OnceLock};
+
+use anyhow::Context;
+use tokio::runtime::{Builder, Runtime};
+use tokio::sync::{OnceCell, RwLock};
+
+use crate::{
+    IsolatedStack, Isolation, MigrationRunner, RunningStack, SupabaseStack, ToxiproxyClient,
+};
+
+/// Worker threads of the background runtime
+const RUNTIME_WORKERS: usize = 2;
+
+/// Runtime owning the shared stack's containers and connections
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+/// The shared stack, once started
+static SHARED: OnceCell<SharedStack> = OnceCell::const_new();
+/// Guards registration of the exit handler
+static EXIT_HANDLER: Once = Once::new();
+
+/// Handle to the stack shared by all tests in a binary
+///
+/// Obtained from [`SupabaseStack::shared`]. Accessors return values captured at start;
+/// operations run on the background runtime that owns the containers.
+pub struct SharedStack {
+    /// The stack; taken by the exit handler
+    stack: Arc<RwLock<Option<RunningStack>>>,
+    /// Docker network the containers share
+    network: String,
+    /// Database container name on the network
+    db_host: String,
+    /// JWT secret shared by all services
+    jwt_secret: String,
+    /// API key for the `anon` role
+    anon_key: String,
+    /// API key for the `service_role` role
+    service_role_key: String,
+    /// Connection string for the database, reachable from the host
+    db_url: String,
+    /// Auth URL, reachable from the host
+    auth_url: Option<String>,
+    /// PostgREST URL, reachable from the host
+    rest_url: Option<String>,
+    /// Storage URL, reachable from the host
+    storage_url: Option<String>,
+    /// Toxiproxy API URL, reachable from the host
+    toxiproxy_url: Option<String>,
+}
+
+impl SharedStack {
+    /// Starts `stack` on the background runtime unless the shared stack is running
+    pub(crate) async fn start(stack: SupabaseStack) -> anyhow::Result<&'static SharedStack> {
+        SHARED
+            .get_or_try_init(|| async move {
+                let running = on_runtime(stack.start())
+                    .await
+                    .context("failed to start the shared stack")?;
+                EXIT_HANDLER.call_once(|| {
+                    // SAFETY: `stop_at_exit` is an `extern "C" fn()` that does not unwind
+                    if unsafe { libc::atexit(stop_at_exit) } != 0 {
+                        report!(error, "failed to register shared stack cleanup; containers may be left running");
+                    }
+                });
+                Ok(SharedStack::new(running))
+            })
+            .await
+    }
+
+    /// Returns the shared stack if it has been started
+    pub fn get() -> Option<&'static SharedStack> {
+        SHARED.get()
+    }
+
+    /// Wraps a running stack, capturing its accessors
+    fn new(stack: RunningStack) -> Self {
+        Self {
+            network: stack.network().to_string(),
+            db_host: stack.db_host().to_string(),
+            jwt_secret: stack.jwt_secret().to_string(),
+            anon_key: stack.anon_key().to_string(),
+            service_role_key: stack.service_role_key().to_string(),
+            db_url: stack.db_url().to_string(),
+            auth_url: stack.auth_url().map(str::to_string),
+            rest_url: stack.rest_url().map(str::to_string),
+            storage_url: stack.storage_url().map(str::to_string),
+            toxiproxy_url: stack.toxiproxy_url().map(str::to_string),
+            stack: Arc::new(RwLock::new(Some(stack))),
+        }
+    }
+
+    /// Returns the Docker network the containers share
+    pub fn network(&self) -> &str {
+        &self.network
+    }
+
+    /// Returns the database host name on the stack's network
+    pub fn db_host(&self) -> &str {
+        &self.db_host
+    }
+
+    /// Returns the JWT secret shared by all services
+    pub fn jwt_secret(&self) -> &str {
+        &self.jwt_secret
+    }
+
+    /// Returns the API key for the `anon` role
+    pub fn anon_key(&self) -> &str {
+        &self.anon_key
+    }
+
+    /// Returns the API key for the `service_role` role
+    pub fn service_role_key(&self) -> &str {
+        &self.service_role_key
+    }
+
+    /// Returns a superuser connection string for the database, reachable from the host
+    pub fn db_url(&self) -> &str {
+        &self.db_url
+    }
+
+    /// Returns the Auth URL, if Auth is part of the stack
+    pub fn auth_url(&self) -> Option<&str> {
+        self.auth_url.as_deref()
+    }
+
+    /// Returns the PostgREST URL, if PostgREST is part of the stack
+    pub fn rest_url(&self) -> Option<&str> {
+        self.rest_url.as_deref()
+    }
+
+    /// Returns the Storage URL, if Storage is part of the stack
+    pub fn storage_url(&self) -> Option<&str> {
+        self.storage_url.as_deref()
+    }
+
+    /// Returns the Toxiproxy API URL, if the links are proxied
+    pub fn toxiproxy_url(&self) -> Option<&str> {
+        self.toxiproxy_url.as_deref()
+    }
+
+    /// Returns a client injecting faults into the stack's links, if they are proxied
+    ///
+    /// See [`RunningStack::toxiproxy_client`]. Faults affect every test using the
+    /// shared stack.
+    pub fn toxiproxy_client(&self) -> Option<ToxiproxyClient> {
+        self.toxiproxy_url.as_deref().map(ToxiproxyClient::new)
+    }
+
+    /// Returns the stack to its snapshot
+    ///
+    /// Waits for running [`SharedStack::take_snapshot`] calls; see [`RunningStack::reset`].
+    /// Tests that reset the shared stack should not run in parallel with tests using it;
+    /// use [`SharedStack::isolate`] for those.
+    ///
+    /// # Errors
+    /// Returns an error if any of the reset steps fails.
+    pub async fn reset(&self) -> anyhow::Result<()> {
+        let stack = self.stack.clone();
+        on_runtime(async move {
+            let stack = stack.read_owned().await;
+            running(&stack)?.reset().await
+        })
+        .await
+    }
+
+    /// Applies the migrations not yet applied to the shared database
+    ///
+    /// See [`RunningStack::migrate`].
+    ///
+    /// # Errors
+    /// Returns an error naming the migration and statement that failed, or if the
+    /// schema cache reload fails.
+    pub async fn migrate(&self, migrations: &MigrationRunner) -> anyhow::Result<Vec<String>> {
+        let stack = self.stack.clone();
+        let migrations = migrations.clone();
+        on_runtime(async move {
+            let stack = stack.read_owned().await;
+            running(&stack)?.migrate(&migrations).await
+        })
+        .await
+    }
+
+    /// Replaces the snapshot with the current state
+    ///
+    /// See [`RunningStack::take_snapshot`].
+    ///
+    /// # Errors
+    /// Returns an error if the database snapshot or the Storage archive cannot be taken.
+    pub async fn take_snapshot(&self) -> anyhow::Result<()> {
+        let stack = self.stack.clone();
+        on_runtime(async move {
+            let mut stack = stack.write_owned().await;
+            stack
+                .as_mut()
+                .context("the shared stack has been stopped")?
+                .take_snapshot()
+                .await
+        })
+        .await
+    }
+
+    /// Isolates a test from others sharing this stack
+    ///
+    /// See [`RunningStack::isolate`]. The returned [`IsolatedStack`] is cleaned up on
+    /// the background runtime, wherever it is dropped.
+    ///
+    /// # Errors
+    /// Returns an error if the database or schemas cannot be created or a per-test
+    /// container fails to start.
+    pub async fn isolate(&self, isolation: Isolation) -> anyhow::Result<IsolatedStack> {
+        let stack = self.stack.clone();
+        on_runtime(async move {
+            let stack = stack.read_owned().await;
+            let isolated = running(&stack)?.isolate(isolation).await?;
+            Ok(isolated.with_runtime(runtime().handle().clone()))
+        })
+        .await
+    }
+
+    /// Writes the containers' logs (and the database dump, if enabled) for `test_name`
+    ///
+    /// See [`RunningStack::write_artifacts`]. The logs cover every test that used the
+    /// shared stack so far.
+    ///
+    /// # Errors
+    /// Returns an error if the files cannot be written or the dump fails.
+    pub async fn write_artifacts(&self, test_name: &str) -> anyhow::Result<PathBuf> {
+        let stack = self.stack.clone();
+        let test_name = test_name.to_string();
+        on_runtime(async move {
+            let stack = stack.read_owned().await;
+            running(&stack)?.write_artifacts(&test_name).await
+        })
+        .await
+    }
+}
+
+/// Returns the running stack, unless the exit handler has stopped it
+fn running(stack: &Option<RunningStack>) -> anyhow::Result<&RunningStack> {
+    stack.as_ref().context("the shared stack has been stopped")
+}
+
+/// Returns the background runtime, building it on first use
+fn runtime() -> &'static Runtime {
+    RUNTIME.get_or_init(|| {
+        Builder::new_multi_thread()
+            .worker_threads(RUNTIME_WORKERS)
+            .thread_name("supabase-shared-stack")
+            .enable_all()
+            .build()
+            .expect("failed to build the shared stack runtime")
+    })
+}
+
+/// Runs `future` on the background runtime and waits for it from any runtime
+async fn on_runtime<T, F>(future: F) -> anyhow::Result<T>
+where
+    T: Send + 'static,
+    F: Future<Output = anyhow::Result<T>> + Send + 'static,
+{
+    runtime()
+        .spawn(future)
+        .await
+        .context("shared stack task panicked")?
+}
+
+/// Stops the shared stack's containers when the process exits
+extern "C" fn stop_at_exit() {
+    let Some(shared) = SHARED.get() else {
+        return;
+    };
+    // Unwinding out of an `extern "C" fn` aborts the process; nothing observes the
+    // stack after a panic here, so it is unwind safe
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        let stack = shared.stack.clone();
+        let task = runtime().spawn(async move {
+            drop(stack.write().await.take());
+        });
+        runtime().block_on(task)
+    }));
+    if !matches!(result, Ok(Ok(()))) {
+        report!(
+            error,
+            "failed to stop the shared stack; containers may be left running"
+        );
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "stack")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_is_reused() {
+        assert!(std::ptr::eq(runtime(), runtime()));
+    }
+
+    #[tokio::test]
+    async fn test_on_runtime_runs_on_background_runtime() {
+        let name = on_runtime(async { Ok(std::thread::current().name().map(str::to_string)) })
+            .await
+            .unwrap();
+
+        assert_eq!(name.as_deref(), Some("supabase-shared-stack"));
+    }
+
+    #[test]
+    fn test_on_runtime_from_separate_runtimes() {
+        for _ in 0..2 {
+            let value = tokio::runtime::Builder::new_current_thread()
+                .build()
+                .unwrap()
+                .block_on(on_runtime(async { Ok(42) }))
+                .unwrap();
+            assert_eq!(value, 42);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_on_runtime_reports_panics() {
+        let result: anyhow::Result<()> = on_runtime(async { panic!("boom") }).await;
+
+        assert!(result.unwrap_err().to_string().contains("panicked"));
+    }
+
+    #[test]
+    fn test_running_reports_stopped_stack() {
+        let Err(error) = running(&None) else {
+            panic!("expected an error for a stopped stack");
+        };
+
+        assert!(error.to_string().contains("stopped"));
+    }
+
+    #[test]
+    fn test_not_started() {
+        assert!(SharedStack::get().is_none());
+    }
+}
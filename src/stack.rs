@@ -0,0 +1,1747 @@
+/*! Supabase services sharing one database, with snapshot-based resets.
+
+[`SupabaseStack`] starts PostgreSQL and the selected services on one Docker network,
+wires their database URLs, JWT secret and API keys together, applies setup SQL
+(migrations and seed data), and takes a snapshot. [`RunningStack::reset`] returns
+everything to that snapshot, so a test binary can start the stack once and give each
+test a known state.
+
+# Architecture
+
+1. Start PostgreSQL with the [`LogicalReplication`] preset
+2. Create the `anon`, `authenticated`, `service_role` and `supabase_storage_admin` roles
+3. Start Auth (after [`Auth::init_db_schema`]), PostgREST and Storage
+4. Apply the migrations and the setup SQL and reload PostgREST's schema cache
+5. Take a [`DatabaseSnapshot`] and archive Storage's files
+
+[`RunningStack::reset`] restores the database snapshot, restores Storage's files and
+reloads PostgREST's schema cache. [`SupabaseStack::shared`] starts one stack per test
+binary and shares it across tests. [`RunningStack::restart_service`] and its siblings
+stop, start and pause single services mid-test. [`RunningStack::artifacts_guard`]
+keeps the containers' logs when a test fails.
+
+# Example
+
+```rust,no_run
+use supabase_testcontainers_modules::{Auth, PostgREST, Storage, SupabaseStack};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let stack = SupabaseStack::new()
+        .with_auth(Auth::default())
+        .with_postgrest(PostgREST::default())
+        .with_storage(Storage::default())
+        .with_setup_sql("CREATE TABLE public.todos (id serial PRIMARY KEY, task text);")
+        .start()
+        .await?;
+
+    // ... run a test against stack.rest_url() with stack.anon_key() ...
+
+    // Undo everything the test changed
+    stack.reset().await?;
+
+    Ok(())
+}
+```
+*/
+
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use testcontainers_modules::postgres::Postgres;
+use testcontainers_modules::testcontainers::bollard::Docker;
+use testcontainers_modules::testcontainers::runners::AsyncRunner;
+use testcontainers_modules::testcontainers::{ContainerAsync, ContainerRequest, Image, ImageExt};
+
+use crate::artifacts::{
+    artifacts_dir, exec_with, test_dir_name, LogCollector, DUMP_FILE, LOG_FILE,
+};
+use crate::db::connect;
+#[cfg(feature = "reuse")]
+use crate::reuse::config_hash;
+use crate::snapshot::exec;
+#[cfg(feature = "reuse")]
+use crate::Reusable;
+#[cfg(feature = "project")]
+use crate::SupabaseProject;
+use crate::{
+    role_jwt, ArtifactsGuard, Auth, DatabaseSnapshot, IsolatedStack, Isolation, LogicalReplication,
+    MigrationRunner, PostgREST, ReadyCheck, SharedStack, SnapshotStrategy, StackLink, Storage,
+    SupabaseVersion, Toxiproxy, ToxiproxyClient, AUTH_PORT, POSTGREST_ADMIN_PORT, POSTGREST_PORT,
+    STORAGE_PORT,
+};
+
+/// Prefix of generated network names
+const NETWORK_PREFIX: &str = "supabase-stack";
+/// PostgreSQL port inside the network
+pub(crate) const POSTGRES_PORT: u16 = 5432;
+/// Default JWT secret, shared with [`Auth`]'s default
+const DEFAULT_JWT_SECRET: &str = "super-secret-jwt-token-for-testing-at-least-32-chars";
+/// Password of the `supabase_auth_admin` role Auth connects as
+pub(crate) const AUTH_ADMIN_PASSWORD: &str = "auth-admin-password";
+/// Archive of Storage's files inside the Storage container
+const STORAGE_ARCHIVE: &str = "/tmp/storage.snapshot.tar";
+/// Storage runs its migrations before it reports ready
+const STORAGE_STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+/// How long a service started again may take to pass its readiness check
+const RESTART_TIMEOUT: Duration = Duration::from_secs(60);
+/// Interval between readiness polls of a service started again
+const RESTART_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Roles and grants the services expect, as created by Supabase's PostgreSQL image
+const ROLES_SQL: &str = r#"
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT FROM pg_roles WHERE rolname = 'anon') THEN
+        CREATE ROLE anon NOLOGIN NOINHERIT;
+    END IF;
+    IF NOT EXISTS (SELECT FROM pg_roles WHERE rolname = 'authenticated') THEN
+        CREATE ROLE authenticated NOLOGIN NOINHERIT;
+    END IF;
+    IF NOT EXISTS (SELECT FROM pg_roles WHERE rolname = 'service_role') THEN
+        CREATE ROLE service_role NOLOGIN NOINHERIT BYPASSRLS;
+    END IF;
+    IF NOT EXISTS (SELECT FROM pg_roles WHERE rolname = 'supabase_storage_admin') THEN
+        CREATE ROLE supabase_storage_admin NOLOGIN;
+    END IF;
+END
+$$;
+
+CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
+
+GRANT USAGE ON SCHEMA public TO anon, authenticated, service_role;
+ALTER DEFAULT PRIVILEGES IN SCHEMA public GRANT ALL ON TABLES TO anon, authenticated, service_role;
+ALTER DEFAULT PRIVILEGES IN SCHEMA public GRANT ALL ON SEQUENCES TO anon, authenticated, service_role;
+ALTER DEFAULT PRIVILEGES IN SCHEMA public GRANT ALL ON FUNCTIONS TO anon, authenticated, service_role;
+"#;
+
+/// A service of a [`RunningStack`] whose container can be stopped and started
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackService {
+    /// PostgreSQL
+    Db,
+    /// Auth (GoTrue)
+    Auth,
+    /// PostgREST
+    Rest,
+    /// Storage
+    Storage,
+}
+
+impl StackService {
+    /// Returns the service's name, the suffix of its container name
+    pub fn name(&self) -> &'static str {
+        match self {
+            StackService::Db => "db",
+            StackService::Auth => "auth",
+            StackService::Rest => "rest",
+            StackService::Storage => "storage",
+        }
+    }
+}
+
+impl std::fmt::Display for StackService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Docker operation on a service's container
+#[derive(Debug, Clone, Copy)]
+enum Lifecycle {
+    Stop,
+    Kill,
+    Start,
+    Pause,
+    Unpause,
+}
+
+impl Lifecycle {
+    /// Returns the operation's name for error messages
+    fn as_str(&self) -> &'static str {
+        match self {
+            Lifecycle::Stop => "stop",
+            Lifecycle::Kill => "kill",
+            Lifecycle::Start => "start",
+            Lifecycle::Pause => "pause",
+            Lifecycle::Unpause => "unpause",
+        }
+    }
+}
+
+/// Counter making generated network names unique within a process
+static STACK_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Builder for a set of Supabase services sharing one PostgreSQL database
+///
+/// The stack sets each service's database URL, network, container name and JWT
+/// secret; other settings on the images passed in are kept.
+///
+/// # Default Configuration
+///
+/// The default configuration includes:
+/// - PostgreSQL only (add services with the `with_*` methods)
+/// - [`LogicalReplication`] defaults for PostgreSQL
+/// - JWT secret shared with [`Auth`]'s default
+/// - [`SnapshotStrategy::Auto`]
+/// - A generated network name
+#[derive(Debug, Clone)]
+pub struct SupabaseStack {
+    /// PostgreSQL preset
+    postgres: LogicalReplication,
+    /// Auth image, if Auth is part of the stack
+    auth: Option<Auth>,
+    /// PostgREST image, if PostgREST is part of the stack
+    postgrest: Option<PostgREST>,
+    /// Storage image, if Storage is part of the stack
+    storage: Option<Storage>,
+    /// Toxiproxy image, if the services' links are proxied
+    toxiproxy: Option<Toxiproxy>,
+    /// JWT secret shared by all services
+    jwt_secret: String,
+    /// Migrations applied after the services started, before the setup SQL
+    migrations: MigrationRunner,
+    /// SQL applied after the migrations, before the snapshot
+    setup_sql: Vec<String>,
+    /// How the database snapshot is taken
+    snapshot_strategy: SnapshotStrategy,
+    /// Docker network name; generated when `None`
+    network: Option<String>,
+    /// Whether containers are reused across test runs
+    reuse: bool,
+    /// Whether service ports are mapped to host ports chosen up front
+    stable_ports: bool,
+    /// Tag set applied to the services at start, if any
+    version: Option<SupabaseVersion>,
+    /// Directory failed tests' artifacts are written to, if set
+    artifacts_dir: Option<PathBuf>,
+    /// Whether the artifacts include a dump of the database
+    database_dump: bool,
+}
+
+impl SupabaseStack {
+    /// Creates a stack with PostgreSQL only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Configures PostgreSQL
+    pub fn with_postgres(mut self, postgres: LogicalReplication) -> Self {
+        self.postgres = postgres;
+        self
+    }
+
+    /// Adds Auth (GoTrue) to the stack
+    pub fn with_auth(mut self, auth: Auth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Adds PostgREST to the stack
+    pub fn with_postgrest(mut self, postgrest: PostgREST) -> Self {
+        self.postgrest = Some(postgrest);
+        self
+    }
+
+    /// Adds Storage to the stack
+    pub fn with_storage(mut self, storage: Storage) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Places Toxiproxy on the links between the services and PostgreSQL, and between
+    /// the host and the services
+    ///
+    /// A proxy is created for every [`StackLink`] whose service is part of the stack, and
+    /// the services' URLs point through it. Faults are injected at runtime through
+    /// [`RunningStack::toxiproxy_client`]; [`RunningStack::reset`] removes them. Setup,
+    /// snapshots and resets connect to PostgreSQL directly, as do the per-test services
+    /// of [`RunningStack::isolate`].
+    pub fn with_toxiproxy(mut self, toxiproxy: Toxiproxy) -> Self {
+        self.toxiproxy = Some(toxiproxy);
+        self
+    }
+
+    /// Sets the JWT secret shared by all services (at least 32 characters)
+    pub fn with_jwt_secret(mut self, secret: impl Into<String>) -> Self {
+        self.jwt_secret = secret.into();
+        self
+    }
+
+    /// Adds migrations applied once the services are running, before the setup SQL
+    ///
+    /// They are recorded in `supabase_migrations.schema_migrations` like the Supabase
+    /// CLI does. Migrations added by earlier calls are kept; a migration with the same
+    /// version replaces the earlier one.
+    pub fn with_migrations(mut self, migrations: MigrationRunner) -> Self {
+        for migration in migrations.migrations() {
+            self.migrations = self.migrations.with_migration(migration.clone());
+        }
+        self
+    }
+
+    /// Adds SQL applied once the services are running, before the snapshot is taken
+    ///
+    /// Scripts run in the order they are added, typically migrations followed by seed data.
+    pub fn with_setup_sql(mut self, sql: impl Into<String>) -> Self {
+        self.setup_sql.push(sql.into());
+        self
+    }
+
+    /// Sets how the database snapshot is taken
+    pub fn with_snapshot_strategy(mut self, strategy: SnapshotStrategy) -> Self {
+        self.snapshot_strategy = strategy;
+        self
+    }
+
+    /// Sets the Docker network name; container names are derived from it
+    pub fn with_network(mut self, network: impl Into<String>) -> Self {
+        self.network = Some(network.into());
+        self
+    }
+
+    /// Sets whether the containers' ports are mapped to free host ports chosen up front
+    ///
+    /// Docker assigns new host ports when a container with randomly published ports is
+    /// started again, and the URLs are then updated after a service starts again. With
+    /// this enabled the stack picks free host ports itself, and they survive
+    /// [`RunningStack::restart_service`]. The ports are found by binding and releasing
+    /// them, so another process may take one before the container binds it; the start
+    /// then fails. Ports free locally may also be taken when Docker runs on another
+    /// machine. Default is `false`; reused stacks keep their ports either way.
+    pub fn with_stable_ports(mut self, stable_ports: bool) -> Self {
+        self.stable_ports = stable_ports;
+        self
+    }
+
+    /// Sets the directory failed tests' artifacts are written to
+    ///
+    /// Each test gets a subdirectory named after it. Overrides [`ARTIFACTS_DIR_ENV`](crate::ARTIFACTS_DIR_ENV);
+    /// see [`ArtifactsGuard`].
+    pub fn with_artifacts_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.artifacts_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets whether failed tests' artifacts include a `pg_dump` of the database
+    ///
+    /// Off by default; the dump is plain SQL of the whole `postgres` database.
+    pub fn with_database_dump(mut self, database_dump: bool) -> Self {
+        self.database_dump = database_dump;
+        self
+    }
+
+    /// Starts the services with the tags of `version`
+    ///
+    /// Applied at start, so it overrides tags set on the images passed to the `with_*`
+    /// methods. PostgreSQL keeps the [`LogicalReplication`] tag, so the version's
+    /// PostgreSQL tag is neither applied nor checked.
+    pub fn with_version(mut self, version: SupabaseVersion) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Keeps the containers running after the test binary exits and reuses them
+    ///
+    /// The network name is derived from a hash of the stack's configuration, so a
+    /// changed configuration starts a new stack. A reused stack skips the setup SQL
+    /// and resets to the snapshot taken by the run that started it. See [`Reusable`].
+    ///
+    /// If a run fails before the snapshot is taken, remove the stack's containers with
+    /// `docker rm -f` before the next run.
+    #[cfg(feature = "reuse")]
+    pub fn with_reuse(mut self, reuse: bool) -> Self {
+        self.reuse = reuse;
+        self
+    }
+
+    /// Creates a stack from the Supabase CLI project in `path`
+    ///
+    /// Reads `supabase/config.toml` and adds the enabled services, the migrations, the
+    /// seed files and the storage buckets. See [`SupabaseProject`] for the settings
+    /// applied; the other `with_*` methods can adjust the stack afterwards.
+    ///
+    /// # Errors
+    /// Returns an error if the project cannot be read or a setting has the wrong type.
+    #[cfg(feature = "project")]
+    pub fn from_project_dir(path: impl AsRef<std::path::Path>) -> anyhow::Result<Self> {
+        SupabaseProject::load(path)?.apply(Self::new())
+    }
+
+    /// Starts the stack, applies the setup SQL and takes the initial snapshot
+    ///
+    /// # Errors
+    /// Returns an error if the [`SupabaseVersion`] is incompatible, the PostgREST
+    /// configuration is invalid (see [`PostgREST::validate`]), a container fails to
+    /// start, the setup SQL fails or the snapshot cannot be taken.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+    pub async fn start(mut self) -> anyhow::Result<RunningStack> {
+        if let Some(version) = &self.version {
+            version.validate_stack()?;
+            self.auth = self.auth.map(|auth| auth.with_version(version));
+            self.postgrest = self
+                .postgrest
+                .map(|postgrest| postgrest.with_version(version));
+            self.storage = self.storage.map(|storage| storage.with_version(version));
+        }
+        if let Some(postgrest) = &self.postgrest {
+            postgrest
+                .validate()
+                .context("invalid PostgREST configuration")?;
+        }
+        let reuse = self.reuse;
+        // Reused containers keep the ports of the run that started them
+        let stable_ports = self.stable_ports && !reuse;
+        let network = match &self.network {
+            Some(network) => network.clone(),
+            #[cfg(feature = "reuse")]
+            None if reuse => reused_network_name(&self),
+            None => generate_network_name(),
+        };
+        let postgres = reuse_if(
+            pin_ports(
+                self.postgres
+                    .apply(Postgres::default())
+                    .with_network(&network)
+                    .with_container_name(format!("{}-db", network)),
+                stable_ports,
+            )?,
+            reuse,
+        );
+        let db_host = container_name(&postgres)?;
+        let anon_key = role_jwt("anon", &self.jwt_secret);
+        let service_role_key = role_jwt("service_role", &self.jwt_secret);
+
+        let postgres = postgres
+            .start()
+            .await
+            .context("failed to start PostgreSQL container")?;
+        let db_url = format!(
+            "postgres://postgres:postgres@{}:{}/postgres",
+            postgres.get_host().await?,
+            postgres.get_host_port_ipv4(POSTGRES_PORT).await?
+        );
+        execute(&db_url, ROLES_SQL)
+            .await
+            .context("failed to create Supabase roles")?;
+
+        let links: Vec<StackLink> = StackLink::ALL
+            .into_iter()
+            .filter(|link| self.has_link(*link))
+            .collect();
+        let (toxiproxy, toxiproxy_host) = match self.toxiproxy.take() {
+            Some(toxiproxy) => {
+                let request = toxiproxy_request(toxiproxy, &links, &network, &db_host, reuse);
+                let toxiproxy_host = container_name(&request)?;
+                let container = request
+                    .start()
+                    .await
+                    .context("failed to start Toxiproxy container")?;
+                (Some(container), Some(toxiproxy_host))
+            }
+            None => (None, None),
+        };
+        let db_address = |link| link_db_address(link, toxiproxy_host.as_deref(), &db_host);
+
+        // A reused database already holds the setup and the snapshot
+        let existing = if reuse {
+            DatabaseSnapshot::find(&postgres, &db_url, self.snapshot_strategy)
+                .await
+                .context("failed to look up the reused stack's snapshot")?
+        } else {
+            None
+        };
+
+        let (auth, auth_host) = match self.auth {
+            Some(auth) => {
+                let auth = auth
+                    .with_db_url(format!(
+                        "postgres://supabase_auth_admin:{}@{}/postgres",
+                        AUTH_ADMIN_PASSWORD,
+                        db_address(StackLink::AuthDb)
+                    ))
+                    .with_jwt_secret(&self.jwt_secret);
+                let auth = match existing {
+                    Some(_) => auth,
+                    None => auth.init_db_schema(&db_url, AUTH_ADMIN_PASSWORD).await?,
+                };
+                let request = reuse_if(
+                    pin_ports(
+                        auth.with_network(&network)
+                            .with_container_name(format!("{}-auth", network)),
+                        stable_ports,
+                    )?,
+                    reuse,
+                );
+                let auth_host = container_name(&request)?;
+                let container = request
+                    .start()
+                    .await
+                    .context("failed to start Auth container")?;
+                (Some(container), Some(auth_host))
+            }
+            None => (None, None),
+        };
+
+        let (postgrest, rest_host) = match self.postgrest {
+            Some(postgrest) => {
+                let request = reuse_if(
+                    pin_ports(
+                        postgrest
+                            .with_postgres_connection(&format!(
+                                "postgres://postgres:postgres@{}/postgres",
+                                db_address(StackLink::RestDb)
+                            ))
+                            .with_jwt_secret(&self.jwt_secret)
+                            .with_network(&network)
+                            .with_container_name(format!("{}-rest", network)),
+                        stable_ports,
+                    )?,
+                    reuse,
+                );
+                let rest_host = container_name(&request)?;
+                let container = request
+                    .start()
+                    .await
+                    .context("failed to start PostgREST container")?;
+                (Some(container), Some(rest_host))
+            }
+            None => (None, None),
+        };
+
+        let (storage, storage_host) = match self.storage {
+            Some(storage) => {
+                let storage = storage
+                    .with_database_url(format!(
+                        "postgres://postgres:postgres@{}/postgres",
+                        db_address(StackLink::StorageDb)
+                    ))
+                    .with_jwt_secret(&self.jwt_secret)
+                    .with_anon_key(&anon_key)
+                    .with_service_key(&service_role_key);
+                let storage = match &rest_host {
+                    Some(rest_host) => storage
+                        .with_postgrest_url(format!("http://{}:{}", rest_host, POSTGREST_PORT)),
+                    None => storage,
+                };
+                let request = reuse_if(
+                    pin_ports(
+                        storage
+                            .with_startup_timeout(STORAGE_STARTUP_TIMEOUT)
+                            .with_network(&network)
+                            .with_container_name(format!("{}-storage", network)),
+                        stable_ports,
+                    )?,
+                    reuse,
+                );
+                let storage_host = container_name(&request)?;
+                let container = request
+                    .start()
+                    .await
+                    .context("failed to start Storage container")?;
+                (Some(container), Some(storage_host))
+            }
+            None => (None, None),
+        };
+
+        if let Some(toxiproxy) = &toxiproxy {
+            let mut upstreams = Vec::new();
+            for link in &links {
+                let host = match link {
+                    StackLink::Auth => auth_host.as_deref(),
+                    StackLink::Rest => rest_host.as_deref(),
+                    StackLink::Storage => storage_host.as_deref(),
+                    StackLink::AuthDb | StackLink::RestDb | StackLink::StorageDb => None,
+                };
+                if let Some(host) = host {
+                    upstreams.push((*link, link_upstream(*link, host)));
+                }
+            }
+            create_service_proxies(toxiproxy, upstreams).await?;
+        }
+
+        let reused = existing.is_some();
+        let snapshot = match existing {
+            Some(snapshot) => snapshot,
+            None => {
+                self.migrations.apply(&db_url).await?;
+                for (i, sql) in self.setup_sql.iter().enumerate() {
+                    execute(&db_url, sql)
+                        .await
+                        .with_context(|| format!("setup SQL script {} failed", i + 1))?;
+                }
+                if let Some(postgrest) = &postgrest {
+                    PostgREST::reload_schema(postgrest, &db_url).await?;
+                }
+                DatabaseSnapshot::create(&postgres, &db_url, self.snapshot_strategy)
+                    .await
+                    .context("failed to snapshot the database")?
+            }
+        };
+        let storage_path = match &storage {
+            // The archive was taken by the run that started the container
+            Some(storage) if reused => storage.image().file_storage_path().map(str::to_string),
+            Some(storage) => archive_storage_files(storage).await?,
+            None => None,
+        };
+
+        let stack = RunningStack {
+            auth_url: match &auth {
+                Some(auth) => {
+                    Some(host_url(auth, AUTH_PORT, toxiproxy.as_ref(), StackLink::Auth).await?)
+                }
+                None => None,
+            },
+            rest_url: match &postgrest {
+                Some(postgrest) => Some(
+                    host_url(
+                        postgrest,
+                        POSTGREST_PORT,
+                        toxiproxy.as_ref(),
+                        StackLink::Rest,
+                    )
+                    .await?,
+                ),
+                None => None,
+            },
+            storage_url: match &storage {
+                Some(storage) => Some(
+                    host_url(
+                        storage,
+                        STORAGE_PORT,
+                        toxiproxy.as_ref(),
+                        StackLink::Storage,
+                    )
+                    .await?,
+                ),
+                None => None,
+            },
+            toxiproxy_url: match &toxiproxy {
+                Some(toxiproxy) => Some(
+                    ToxiproxyClient::from_container(toxiproxy)
+                        .await?
+                        .base_url()
+                        .to_string(),
+                ),
+                None => None,
+            },
+            network,
+            db_host,
+            jwt_secret: self.jwt_secret,
+            anon_key,
+            service_role_key,
+            db_url,
+            postgres,
+            auth,
+            postgrest,
+            rest_host,
+            storage,
+            toxiproxy,
+            snapshot_strategy: self.snapshot_strategy,
+            snapshot,
+            storage_path,
+            logs: LogCollector::new(),
+            artifacts_dir: artifacts_dir(self.artifacts_dir.as_deref()),
+            database_dump: self.database_dump,
+            stable_ports,
+        };
+        stack.follow_logs();
+        if reused {
+            // Undo whatever the previous run left behind
+            stack
+                .reset()
+                .await
+                .context("failed to reset the reused stack")?;
+        }
+        Ok(stack)
+    }
+
+    /// Starts the stack once per process and returns the handle shared by all tests
+    ///
+    /// The first call starts the stack on a background runtime; later calls return the
+    /// same [`SharedStack`] and ignore their configuration. The containers are removed
+    /// when the process exits. See [`SharedStack`] for using it across test runtimes.
+    ///
+    /// # Errors
+    /// Returns an error if the stack fails to start; the next call tries again.
+    pub async fn shared(self) -> anyhow::Result<&'static SharedStack> {
+        SharedStack::start(self).await
+    }
+
+    /// Returns whether the service at either end of `link` is part of the stack
+    fn has_link(&self, link: StackLink) -> bool {
+        match link {
+            StackLink::AuthDb | StackLink::Auth => self.auth.is_some(),
+            StackLink::RestDb | StackLink::Rest => self.postgrest.is_some(),
+            StackLink::StorageDb | StackLink::Storage => self.storage.is_some(),
+        }
+    }
+}
+
+impl Default for SupabaseStack {
+    fn default() -> Self {
+        Self {
+            postgres: LogicalReplication::default(),
+            auth: None,
+            postgrest: None,
+            storage: None,
+            toxiproxy: None,
+            jwt_secret: DEFAULT_JWT_SECRET.to_string(),
+            migrations: MigrationRunner::new(),
+            setup_sql: Vec::new(),
+            snapshot_strategy: SnapshotStrategy::default(),
+            network: None,
+            reuse: false,
+            stable_ports: false,
+            version: None,
+            artifacts_dir: None,
+            database_dump: false,
+        }
+    }
+}
+
+/// A running [`SupabaseStack`]
+///
+/// Dropping it stops all containers.
+pub struct RunningStack {
+    /// Docker network the containers share
+    network: String,
+    /// Database container name on the network
+    db_host: String,
+    /// JWT secret shared by all services
+    jwt_secret: String,
+    /// API key for the `anon` role
+    anon_key: String,
+    /// API key for the `service_role` role
+    service_role_key: String,
+    /// Connection string for the database, reachable from the host
+    db_url: String,
+    /// PostgreSQL container
+    postgres: ContainerAsync<Postgres>,
+    /// Auth container, if part of the stack
+    auth: Option<ContainerAsync<Auth>>,
+    /// PostgREST container, if part of the stack
+    postgrest: Option<ContainerAsync<PostgREST>>,
+    /// PostgREST container name on the network, if part of the stack
+    rest_host: Option<String>,
+    /// Storage container, if part of the stack
+    storage: Option<ContainerAsync<Storage>>,
+    /// Toxiproxy container, if the links are proxied
+    toxiproxy: Option<ContainerAsync<Toxiproxy>>,
+    /// Auth URL, reachable from the host
+    auth_url: Option<String>,
+    /// PostgREST URL, reachable from the host
+    rest_url: Option<String>,
+    /// Storage URL, reachable from the host
+    storage_url: Option<String>,
+    /// Toxiproxy API URL, reachable from the host
+    toxiproxy_url: Option<String>,
+    /// How snapshots are taken
+    snapshot_strategy: SnapshotStrategy,
+    /// Database state [`RunningStack::reset`] restores
+    snapshot: DatabaseSnapshot,
+    /// Storage's file directory, if its files are archived
+    storage_path: Option<String>,
+    /// Logs of the containers
+    logs: LogCollector,
+    /// Directory failed tests' artifacts are written to
+    artifacts_dir: PathBuf,
+    /// Whether the artifacts include a dump of the database
+    database_dump: bool,
+    /// Whether service ports are mapped to host ports chosen up front
+    stable_ports: bool,
+}
+
+impl RunningStack {
+    /// Returns the Docker network the containers share
+    pub fn network(&self) -> &str {
+        &self.network
+    }
+
+    /// Returns the database host name on the stack's network
+    pub fn db_host(&self) -> &str {
+        &self.db_host
+    }
+
+    /// Returns the JWT secret shared by all services
+    pub fn jwt_secret(&self) -> &str {
+        &self.jwt_secret
+    }
+
+    /// Returns the API key for the `anon` role
+    pub fn anon_key(&self) -> &str {
+        &self.anon_key
+    }
+
+    /// Returns the API key for the `service_role` role
+    pub fn service_role_key(&self) -> &str {
+        &self.service_role_key
+    }
+
+    /// Returns a superuser connection string for the database, reachable from the host
+    pub fn db_url(&self) -> &str {
+        &self.db_url
+    }
+
+    /// Returns the PostgreSQL container
+    pub fn postgres(&self) -> &ContainerAsync<Postgres> {
+        &self.postgres
+    }
+
+    /// Returns the Auth container, if Auth is part of the stack
+    pub fn auth(&self) -> Option<&ContainerAsync<Auth>> {
+        self.auth.as_ref()
+    }
+
+    /// Returns the PostgREST container, if PostgREST is part of the stack
+    pub fn postgrest(&self) -> Option<&ContainerAsync<PostgREST>> {
+        self.postgrest.as_ref()
+    }
+
+    /// Returns the Storage container, if Storage is part of the stack
+    pub fn storage(&self) -> Option<&ContainerAsync<Storage>> {
+        self.storage.as_ref()
+    }
+
+    /// Returns the Toxiproxy container, if the links are proxied
+    pub fn toxiproxy(&self) -> Option<&ContainerAsync<Toxiproxy>> {
+        self.toxiproxy.as_ref()
+    }
+
+    /// Returns the Auth URL, if Auth is part of the stack
+    pub fn auth_url(&self) -> Option<&str> {
+        self.auth_url.as_deref()
+    }
+
+    /// Returns the PostgREST URL, if PostgREST is part of the stack
+    pub fn rest_url(&self) -> Option<&str> {
+        self.rest_url.as_deref()
+    }
+
+    /// Returns the Storage URL, if Storage is part of the stack
+    pub fn storage_url(&self) -> Option<&str> {
+        self.storage_url.as_deref()
+    }
+
+    /// Returns the Toxiproxy API URL, if the links are proxied
+    pub fn toxiproxy_url(&self) -> Option<&str> {
+        self.toxiproxy_url.as_deref()
+    }
+
+    /// Returns a client injecting faults into the stack's links, if they are proxied
+    ///
+    /// Proxies are named after [`StackLink`]s.
+    pub fn toxiproxy_client(&self) -> Option<ToxiproxyClient> {
+        self.toxiproxy_url.as_deref().map(ToxiproxyClient::new)
+    }
+
+    /// Returns the snapshot [`RunningStack::reset`] restores
+    pub fn snapshot(&self) -> &DatabaseSnapshot {
+        &self.snapshot
+    }
+
+    /// Replaces the snapshot with the current state
+    ///
+    /// Use this after applying further migrations or seed data that every test
+    /// should start from.
+    ///
+    /// # Errors
+    /// Returns an error if the database snapshot or the Storage archive cannot be taken.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+    pub async fn take_snapshot(&mut self) -> anyhow::Result<()> {
+        self.snapshot =
+            DatabaseSnapshot::create(&self.postgres, &self.db_url, self.snapshot_strategy)
+                .await
+                .context("failed to snapshot the database")?;
+        if let Some(storage) = &self.storage {
+            self.storage_path = archive_storage_files(storage).await?;
+        }
+        Ok(())
+    }
+
+    /// Applies the migrations not yet applied to the stack's database
+    ///
+    /// Reloads PostgREST's schema cache if any were applied. The snapshot is not
+    /// changed; call [`RunningStack::take_snapshot`] to keep the migrations across
+    /// resets. Returns the versions applied.
+    ///
+    /// # Errors
+    /// Returns an error naming the migration and statement that failed, or if the
+    /// schema cache reload fails.
+    pub async fn migrate(&self, migrations: &MigrationRunner) -> anyhow::Result<Vec<String>> {
+        let applied = migrations.apply(&self.db_url).await?;
+        if let (false, Some(postgrest)) = (applied.is_empty(), &self.postgrest) {
+            PostgREST::reload_schema(postgrest, &self.db_url).await?;
+        }
+        Ok(applied)
+    }
+
+    /// Isolates a test from others sharing this stack
+    ///
+    /// See [`Isolation`] for what each mode creates. The returned [`IsolatedStack`]
+    /// removes everything again when dropped.
+    ///
+    /// # Errors
+    /// Returns an error if the database or schemas cannot be created or a per-test
+    /// container fails to start.
+    pub async fn isolate(&self, isolation: Isolation) -> anyhow::Result<IsolatedStack> {
+        IsolatedStack::start(self, isolation).await
+    }
+
+    /// Stops a service's container
+    ///
+    /// The container keeps its state; [`RunningStack::start_service`] brings it back.
+    /// Until then, clients see refused connections.
+    ///
+    /// # Errors
+    /// Returns an error if the service is not part of the stack or Docker fails.
+    pub async fn stop_service(&self, service: StackService) -> anyhow::Result<()> {
+        self.lifecycle(service, Lifecycle::Stop).await
+    }
+
+    /// Kills a service's container without a graceful shutdown
+    ///
+    /// Like [`RunningStack::stop_service`], but the process gets no time to close its
+    /// connections, as after a crash.
+    ///
+    /// # Errors
+    /// Returns an error if the service is not part of the stack or Docker fails.
+    pub async fn kill_service(&self, service: StackService) -> anyhow::Result<()> {
+        self.lifecycle(service, Lifecycle::Kill).await
+    }
+
+    /// Starts a stopped service's container and waits until it is ready again
+    ///
+    /// The service's readiness check runs again (for PostgreSQL, a query). Unless
+    /// [`SupabaseStack::with_stable_ports`] is enabled, the URLs are updated to the new
+    /// host ports.
+    ///
+    /// # Errors
+    /// Returns an error if the service is not part of the stack, Docker fails or the
+    /// service is not ready within a minute.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(%service), err))]
+    pub async fn start_service(&mut self, service: StackService) -> anyhow::Result<()> {
+        self.lifecycle(service, Lifecycle::Start).await?;
+        self.follow_service_logs(service);
+        self.update_urls(service).await?;
+        self.wait_until_ready(service)
+            .await
+            .with_context(|| format!("{} did not become ready after starting", service))
+    }
+
+    /// Stops and starts a service's container
+    ///
+    /// The container's configuration is kept; use
+    /// [`RunningStack::restart_postgrest_with`] to start PostgREST with a new one. See
+    /// [`RunningStack::start_service`].
+    ///
+    /// # Errors
+    /// Returns an error if the service is not part of the stack, Docker fails or the
+    /// service is not ready within a minute.
+    pub async fn restart_service(&mut self, service: StackService) -> anyhow::Result<()> {
+        self.stop_service(service).await?;
+        self.start_service(service).await
+    }
+
+    /// Replaces the PostgREST container with one running `postgrest`
+    ///
+    /// The old container is removed and `postgrest` starts under the same name on the
+    /// stack's network, connected to PostgreSQL the same way and with the stack's JWT
+    /// secret, so Storage and Toxiproxy keep reaching it. With
+    /// [`SupabaseStack::with_stable_ports`] it keeps the host ports; otherwise the URLs
+    /// are updated. The start waits for `postgrest`'s readiness check. The replacement
+    /// is not reused across test runs, even in a reused stack.
+    ///
+    /// # Errors
+    /// Returns an error if PostgREST is not part of the stack, `postgrest` fails
+    /// [`PostgREST::validate`], Docker fails or PostgREST does not become ready.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+    pub async fn restart_postgrest_with(&mut self, postgrest: PostgREST) -> anyhow::Result<()> {
+        let old = present(&self.postgrest, StackService::Rest)?;
+        let name = self
+            .rest_host
+            .clone()
+            .context("PostgREST container has no name")?;
+        let mut postgrest = postgrest.with_jwt_secret(&self.jwt_secret);
+        if let Some(connection) = old.image().postgres_connection() {
+            postgrest = postgrest.with_postgres_connection(connection);
+        }
+        postgrest
+            .validate()
+            .context("invalid PostgREST configuration")?;
+
+        let mut request = postgrest
+            .with_network(&self.network)
+            .with_container_name(&name);
+        if self.stable_ports {
+            for port in request.image().expose_ports().to_vec() {
+                let host_port = old.get_host_port_ipv4(port).await?;
+                request = request.with_mapped_port(host_port, port);
+            }
+        }
+
+        if let Some(old) = self.postgrest.take() {
+            old.rm()
+                .await
+                .context("failed to remove PostgREST container")?;
+        }
+        let container = request
+            .start()
+            .await
+            .context("failed to start PostgREST container")?;
+        self.postgrest = Some(container);
+        self.follow_service_logs(StackService::Rest);
+        self.update_urls(StackService::Rest).await?;
+        self.wait_until_ready(StackService::Rest)
+            .await
+            .context("rest did not become ready after replacing its image")
+    }
+
+    /// Pauses a service's processes
+    ///
+    /// Connections stay open but get no answer, like a hung service.
+    ///
+    /// # Errors
+    /// Returns an error if the service is not part of the stack or Docker fails.
+    pub async fn pause_service(&self, service: StackService) -> anyhow::Result<()> {
+        self.lifecycle(service, Lifecycle::Pause).await
+    }
+
+    /// Resumes a paused service's processes
+    ///
+    /// # Errors
+    /// Returns an error if the service is not part of the stack or Docker fails.
+    pub async fn unpause_service(&self, service: StackService) -> anyhow::Result<()> {
+        self.lifecycle(service, Lifecycle::Unpause).await
+    }
+
+    /// Writes the containers' logs (and the database dump, if enabled) for `test_name`
+    ///
+    /// Creates a directory named after the test in the artifacts directory, holding
+    /// `stack.log` and, with [`SupabaseStack::with_database_dump`], `database.sql`.
+    /// Returns that directory. Use [`RunningStack::artifacts_guard`] to write them only
+    /// when the test panics.
+    ///
+    /// # Errors
+    /// Returns an error if the files cannot be written or the dump fails; the logs are
+    /// written before the dump is taken.
+    pub async fn write_artifacts(&self, test_name: &str) -> anyhow::Result<PathBuf> {
+        self.write_artifacts_with(test_name, None).await
+    }
+
+    /// [`RunningStack::write_artifacts`], taking the dump with `docker` if given
+    ///
+    /// Without `docker`, the dump runs through the client the containers were started
+    /// with, whose connections belong to the runtime they were started on.
+    pub(crate) async fn write_artifacts_with(
+        &self,
+        test_name: &str,
+        docker: Option<&Docker>,
+    ) -> anyhow::Result<PathBuf> {
+        let dir = self.artifacts_dir.join(test_dir_name(test_name));
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+        let log_path = dir.join(LOG_FILE);
+        std::fs::write(&log_path, self.logs.render())
+            .with_context(|| format!("failed to write {}", log_path.display()))?;
+        if self.database_dump {
+            let cmd = ["pg_dump", "-U", "postgres", "postgres"].map(str::to_string);
+            let dump = match docker {
+                Some(docker) => exec_with(docker, self.postgres.id(), &cmd).await,
+                None => exec(&self.postgres, cmd.to_vec()).await,
+            }
+            .context("failed to dump the database")?;
+            let dump_path = dir.join(DUMP_FILE);
+            std::fs::write(&dump_path, dump)
+                .with_context(|| format!("failed to write {}", dump_path.display()))?;
+        }
+        Ok(dir)
+    }
+
+    /// Returns a guard writing the artifacts for `test_name` if the test panics
+    ///
+    /// The artifacts are written by [`RunningStack::write_artifacts`] when the guard
+    /// is dropped during a panic, which is how a failed assertion ends a test. Errors
+    /// are printed to stderr.
+    pub fn artifacts_guard(&self, test_name: impl Into<String>) -> ArtifactsGuard<'_> {
+        ArtifactsGuard::new(self, test_name.into())
+    }
+
+    /// Follows the logs of all containers
+    fn follow_logs(&self) {
+        for service in [
+            StackService::Db,
+            StackService::Auth,
+            StackService::Rest,
+            StackService::Storage,
+        ] {
+            self.follow_service_logs(service);
+        }
+        if let Some(toxiproxy) = &self.toxiproxy {
+            self.logs.follow(toxiproxy, "toxiproxy");
+        }
+    }
+
+    /// Follows the logs of a service's container, if it is part of the stack
+    fn follow_service_logs(&self, service: StackService) {
+        let name = service.name();
+        match service {
+            StackService::Db => self.logs.follow(&self.postgres, name),
+            StackService::Auth => self.auth.iter().for_each(|c| self.logs.follow(c, name)),
+            StackService::Rest => self
+                .postgrest
+                .iter()
+                .for_each(|c| self.logs.follow(c, name)),
+            StackService::Storage => self.storage.iter().for_each(|c| self.logs.follow(c, name)),
+        }
+    }
+
+    /// Runs a Docker operation on a service's container
+    async fn lifecycle(&self, service: StackService, action: Lifecycle) -> anyhow::Result<()> {
+        let result = match service {
+            StackService::Db => lifecycle(&self.postgres, action).await,
+            StackService::Auth => lifecycle(present(&self.auth, service)?, action).await,
+            StackService::Rest => lifecycle(present(&self.postgrest, service)?, action).await,
+            StackService::Storage => lifecycle(present(&self.storage, service)?, action).await,
+        };
+        result.with_context(|| format!("failed to {} {}", action.as_str(), service))
+    }
+
+    /// Updates a service's URLs to the host ports of its container
+    async fn update_urls(&mut self, service: StackService) -> anyhow::Result<()> {
+        // Proxied URLs point at Toxiproxy, which keeps running
+        let proxied = self.toxiproxy.is_some();
+        match service {
+            StackService::Db => {
+                self.db_url = format!(
+                    "postgres://postgres:postgres@{}:{}/postgres",
+                    self.postgres.get_host().await?,
+                    self.postgres.get_host_port_ipv4(POSTGRES_PORT).await?
+                );
+            }
+            StackService::Auth if !proxied => {
+                let auth = present(&self.auth, service)?;
+                self.auth_url = Some(service_url(auth, AUTH_PORT).await?);
+            }
+            StackService::Rest if !proxied => {
+                let postgrest = present(&self.postgrest, service)?;
+                self.rest_url = Some(service_url(postgrest, POSTGREST_PORT).await?);
+            }
+            StackService::Storage if !proxied => {
+                let storage = present(&self.storage, service)?;
+                self.storage_url = Some(service_url(storage, STORAGE_PORT).await?);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Polls a service until it passes its readiness check
+    async fn wait_until_ready(&self, service: StackService) -> anyhow::Result<()> {
+        match service {
+            StackService::Db => {
+                let db_url = self.db_url.clone();
+                poll_until_ready(|| {
+                    let db_url = db_url.clone();
+                    async move { execute(&db_url, "SELECT 1").await.is_ok() }
+                })
+                .await
+            }
+            StackService::Auth => {
+                let auth = present(&self.auth, service)?;
+                wait_for_check(auth, auth.image().ready_check(), AUTH_PORT).await
+            }
+            StackService::Rest => {
+                let postgrest = present(&self.postgrest, service)?;
+                let check = postgrest.image().ready_check();
+                wait_for_check(postgrest, check, POSTGREST_ADMIN_PORT).await
+            }
+            StackService::Storage => {
+                let storage = present(&self.storage, service)?;
+                wait_for_check(storage, storage.image().ready_check(), STORAGE_PORT).await
+            }
+        }
+    }
+
+    /// Returns the stack to its snapshot
+    ///
+    /// Removes Toxiproxy's toxics and enables its proxies, restores the database,
+    /// replaces Storage's files with the archived ones and reloads PostgREST's schema
+    /// cache.
+    ///
+    /// # Errors
+    /// Returns an error if any of the steps fails.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+    pub async fn reset(&self) -> anyhow::Result<()> {
+        if let Some(toxiproxy) = self.toxiproxy_client() {
+            toxiproxy.reset().await?;
+        }
+        self.snapshot
+            .restore(&self.postgres, &self.db_url)
+            .await
+            .context("failed to restore the database snapshot")?;
+        if let (Some(storage), Some(path)) = (&self.storage, &self.storage_path) {
+            exec(
+                storage,
+                vec![
+                    "sh".to_string(),
+                    "-c".to_string(),
+                    r#"find "$1" -mindepth 1 -delete && tar -xf "$2" -C "$1""#.to_string(),
+                    "sh".to_string(),
+                    path.clone(),
+                    STORAGE_ARCHIVE.to_string(),
+                ],
+            )
+            .await
+            .context("failed to restore Storage files")?;
+        }
+        if let Some(postgrest) = &self.postgrest {
+            PostgREST::reload_schema(postgrest, &self.db_url).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Archives Storage's files, returning their directory
+///
+/// Returns `None` when Storage does not use the file backend.
+async fn archive_storage_files(
+    storage: &ContainerAsync<Storage>,
+) -> anyhow::Result<Option<String>> {
+    let Some(path) = storage.image().file_storage_path().map(str::to_string) else {
+        return Ok(None);
+    };
+    exec(
+        storage,
+        vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            r#"mkdir -p "$1" && tar -cf "$2" -C "$1" ."#.to_string(),
+            "sh".to_string(),
+            path.clone(),
+            STORAGE_ARCHIVE.to_string(),
+        ],
+    )
+    .await
+    .context("failed to archive Storage files")?;
+    Ok(Some(path))
+}
+
+/// Returns a service's container, or an error if it is not part of the stack
+fn present<I: Image>(
+    container: &Option<ContainerAsync<I>>,
+    service: StackService,
+) -> anyhow::Result<&ContainerAsync<I>> {
+    container
+        .as_ref()
+        .with_context(|| format!("{} is not part of the stack", service))
+}
+
+/// Runs a Docker operation on a container
+async fn lifecycle<I: Image>(
+    container: &ContainerAsync<I>,
+    action: Lifecycle,
+) -> testcontainers_modules::testcontainers::core::error::Result<()> {
+    match action {
+        Lifecycle::Stop => container.stop().await,
+        Lifecycle::Kill => container.stop_with_timeout(Some(0)).await,
+        Lifecycle::Start => container.start().await,
+        Lifecycle::Pause => container.pause().await,
+        Lifecycle::Unpause => container.unpause().await,
+    }
+}
+
+/// Polls a container's readiness check on the container `port` it applies to
+///
+/// testcontainers only waits for readiness on the first start. A log check waits for
+/// the port to accept connections instead, as the startup line is already logged.
+async fn wait_for_check<I: Image>(
+    container: &ContainerAsync<I>,
+    check: &ReadyCheck,
+    port: u16,
+) -> anyhow::Result<()> {
+    let host = container.get_host().await?.to_string();
+    let port = container.get_host_port_ipv4(port).await?;
+    let http = reqwest::Client::new();
+    poll_until_ready(|| {
+        let (host, http) = (host.clone(), http.clone());
+        async move {
+            match check {
+                ReadyCheck::Log => tokio::net::TcpStream::connect((host.as_str(), port))
+                    .await
+                    .is_ok(),
+                ReadyCheck::Http(path) => http
+                    .get(format!("http://{}:{}{}", host, port, path))
+                    .send()
+                    .await
+                    .is_ok_and(|response| response.status() == 200),
+            }
+        }
+    })
+    .await
+}
+
+/// Polls `ready` until it returns true or [`RESTART_TIMEOUT`] passes
+async fn poll_until_ready<F, Fut>(mut ready: F) -> anyhow::Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = bool>,
+{
+    let deadline = tokio::time::Instant::now() + RESTART_TIMEOUT;
+    loop {
+        if ready().await {
+            return Ok(());
+        }
+        if tokio::time::Instant::now() >= deadline {
+            bail!("not ready after {:?}", RESTART_TIMEOUT);
+        }
+        tokio::time::sleep(RESTART_POLL_INTERVAL).await;
+    }
+}
+
+/// Returns the host URL of a service, through its Toxiproxy link if there is one
+async fn host_url<I: Image>(
+    container: &ContainerAsync<I>,
+    port: u16,
+    toxiproxy: Option<&ContainerAsync<Toxiproxy>>,
+    link: StackLink,
+) -> anyhow::Result<String> {
+    match toxiproxy {
+        Some(toxiproxy) => service_url(toxiproxy, link.listen_port()).await,
+        None => service_url(container, port).await,
+    }
+}
+
+/// Builds the Toxiproxy request for `links`
+///
+/// Only the database links get a proxy at startup; the service links get their listen
+/// ports, and [`create_service_proxies`] adds their proxies once the services run. With
+/// reuse a container's name follows its configuration, and the services' configuration
+/// names Toxiproxy, so Toxiproxy's configuration cannot name the services.
+fn toxiproxy_request(
+    toxiproxy: Toxiproxy,
+    links: &[StackLink],
+    network: &str,
+    db_host: &str,
+    reuse: bool,
+) -> ContainerRequest<Toxiproxy> {
+    let toxiproxy = links.iter().fold(toxiproxy, |toxiproxy, link| match link {
+        StackLink::AuthDb | StackLink::RestDb | StackLink::StorageDb => toxiproxy.with_proxy(
+            link.proxy_name(),
+            link.listen_port(),
+            link_upstream(*link, db_host),
+        ),
+        StackLink::Auth | StackLink::Rest | StackLink::Storage => {
+            toxiproxy.with_exposed_port(link.listen_port())
+        }
+    });
+    reuse_if(
+        toxiproxy
+            .with_network(network)
+            .with_container_name(format!("{}-toxiproxy", network)),
+        reuse,
+    )
+}
+
+/// Creates the proxies of the service links that Toxiproxy does not have yet
+///
+/// A reused Toxiproxy kept them from the run that started it.
+async fn create_service_proxies(
+    toxiproxy: &ContainerAsync<Toxiproxy>,
+    upstreams: Vec<(StackLink, String)>,
+) -> anyhow::Result<()> {
+    let client = ToxiproxyClient::from_container(toxiproxy).await?;
+    let existing = client.proxies().await?;
+    for (link, upstream) in upstreams {
+        if !existing.iter().any(|proxy| proxy.name == link.proxy_name()) {
+            client
+                .create_proxy(link.proxy_name(), link.listen_port(), &upstream)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns where a service reaches PostgreSQL, through Toxiproxy if it is in the stack
+fn link_db_address(link: StackLink, toxiproxy_host: Option<&str>, db_host: &str) -> String {
+    match toxiproxy_host {
+        Some(toxiproxy_host) => format!("{}:{}", toxiproxy_host, link.listen_port()),
+        None => format!("{}:{}", db_host, POSTGRES_PORT),
+    }
+}
+
+/// Returns the address Toxiproxy forwards `link` to, given the container it leads to
+fn link_upstream(link: StackLink, host: &str) -> String {
+    let port = match link {
+        StackLink::AuthDb | StackLink::RestDb | StackLink::StorageDb => POSTGRES_PORT,
+        StackLink::Auth => AUTH_PORT,
+        StackLink::Rest => POSTGREST_PORT,
+        StackLink::Storage => STORAGE_PORT,
+    };
+    format!("{}:{}", host, port)
+}
+
+/// Returns the host URL of a service port
+async fn service_url<I: Image>(container: &ContainerAsync<I>, port: u16) -> anyhow::Result<String> {
+    let host = container.get_host().await?;
+    let port = container.get_host_port_ipv4(port).await?;
+    Ok(format!("http://{}:{}", host, port))
+}
+
+/// Runs SQL against the database
+pub(crate) async fn execute(db_url: &str, sql: &str) -> anyhow::Result<()> {
+    let client = connect(db_url).await?;
+    client.batch_execute(sql).await?;
+    Ok(())
+}
+
+/// Marks `request` for reuse across test runs when `reuse` is set
+fn reuse_if<I: Image>(request: ContainerRequest<I>, reuse: bool) -> ContainerRequest<I> {
+    #[cfg(feature = "reuse")]
+    if reuse {
+        return request.reusable();
+    }
+    #[cfg(not(feature = "reuse"))]
+    let _ = reuse;
+    request
+}
+
+/// Maps every port `request`'s image exposes to a free host port, if `stable`
+///
+/// Ports are looked up together, so a request never gets the same port twice. Another
+/// process may take a port before the container binds it; the start then fails.
+fn pin_ports<I: Image>(
+    request: ContainerRequest<I>,
+    stable: bool,
+) -> anyhow::Result<ContainerRequest<I>> {
+    if !stable {
+        return Ok(request);
+    }
+    let ports = request.image().expose_ports().to_vec();
+    let listeners = ports
+        .iter()
+        .map(|_| TcpListener::bind(("0.0.0.0", 0)))
+        .collect::<std::io::Result<Vec<_>>>()
+        .context("failed to find a free host port")?;
+    let mut request = request;
+    for (port, listener) in ports.into_iter().zip(&listeners) {
+        request = request.with_mapped_port(listener.local_addr()?.port(), port);
+    }
+    Ok(request)
+}
+
+/// Returns the container name set on `request`
+fn container_name<I: Image>(request: &ContainerRequest<I>) -> anyhow::Result<String> {
+    request
+        .container_name()
+        .clone()
+        .context("container request has no name")
+}
+
+/// Derives a network name from the stack's configuration, stable across runs
+///
+/// Only the settings that shape the containers and the database are hashed; where
+/// artifacts go, for example, does not recreate a reused stack.
+#[cfg(feature = "reuse")]
+fn reused_network_name(stack: &SupabaseStack) -> String {
+    let config = format!(
+        "{:?}",
+        (
+            &stack.postgres,
+            &stack.auth,
+            &stack.postgrest,
+            &stack.storage,
+            &stack.toxiproxy,
+            &stack.jwt_secret,
+            &stack.migrations,
+            &stack.setup_sql,
+            &stack.snapshot_strategy,
+            &stack.version,
+        )
+    );
+    format!("{}-{}", NETWORK_PREFIX, config_hash(&config))
+}
+
+/// Generates a network name unique to this process and moment
+fn generate_network_name() -> String {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+    let counter = STACK_COUNTER.fetch_add(1, Ordering::SeqCst);
+    format!("{}-{}-{}", NETWORK_PREFIX, timestamp, counter)
+}
+
+#[cfg(test)]
+#[cfg(feature = "stack")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_configuration() {
+        let stack = SupabaseStack::default();
+
+        assert!(stack.auth.is_none());
+        assert!(stack.postgrest.is_none());
+        assert!(stack.storage.is_none());
+        assert!(stack.toxiproxy.is_none());
+        assert_eq!(stack.jwt_secret, DEFAULT_JWT_SECRET);
+        assert_eq!(stack.snapshot_strategy, SnapshotStrategy::Auto);
+        assert!(stack.network.is_none());
+        assert!(!stack.reuse);
+        assert!(!stack.stable_ports);
+    }
+
+    #[test]
+    fn test_default_jwt_secret_matches_auth() {
+        let auth = Auth::default();
+        let secret = auth
+            .env_vars()
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .find(|(key, _)| key == "GOTRUE_JWT_SECRET")
+            .map(|(_, value)| value.into_owned());
+        assert_eq!(secret.as_deref(), Some(DEFAULT_JWT_SECRET));
+    }
+
+    #[test]
+    fn test_builder_methods() {
+        let stack = SupabaseStack::new()
+            .with_auth(Auth::default())
+            .with_postgrest(PostgREST::default())
+            .with_storage(Storage::default())
+            .with_jwt_secret("another-secret-with-at-least-32-characters")
+            .with_setup_sql("CREATE TABLE a ();")
+            .with_setup_sql("CREATE TABLE b ();")
+            .with_snapshot_strategy(SnapshotStrategy::Dump)
+            .with_network("my-network")
+            .with_version(SupabaseVersion::tested());
+
+        assert!(stack.auth.is_some());
+        assert!(stack.postgrest.is_some());
+        assert!(stack.storage.is_some());
+        assert_eq!(
+            stack.jwt_secret,
+            "another-secret-with-at-least-32-characters"
+        );
+        assert_eq!(
+            stack.setup_sql,
+            vec!["CREATE TABLE a ();", "CREATE TABLE b ();"]
+        );
+        assert_eq!(stack.snapshot_strategy, SnapshotStrategy::Dump);
+        assert_eq!(stack.network.as_deref(), Some("my-network"));
+        assert_eq!(stack.version, Some(SupabaseVersion::tested()));
+    }
+
+    #[test]
+    fn test_with_migrations_accumulates() {
+        let stack = SupabaseStack::new()
+            .with_migrations(MigrationRunner::new().with_sql("2", "b", "SELECT 2;"))
+            .with_migrations(
+                MigrationRunner::new()
+                    .with_sql("1", "a", "SELECT 1;")
+                    .with_sql("2", "b2", "SELECT 22;"),
+            );
+
+        let names: Vec<&str> = stack
+            .migrations
+            .migrations()
+            .iter()
+            .map(|migration| migration.name())
+            .collect();
+        assert_eq!(names, ["a", "b2"]);
+    }
+
+    #[tokio::test]
+    async fn test_start_validates_postgrest() {
+        let Err(error) = SupabaseStack::new()
+            .with_postgrest(PostgREST::default().with_env("PGRST_DB_SCHEMA", "api"))
+            .start()
+            .await
+        else {
+            panic!("started with an unknown PostgREST setting");
+        };
+
+        assert_eq!(error.to_string(), "invalid PostgREST configuration");
+        assert_eq!(
+            error.root_cause().to_string(),
+            "unknown PostgREST setting PGRST_DB_SCHEMA"
+        );
+    }
+
+    #[test]
+    fn test_toxiproxy_links_follow_services() {
+        let stack = SupabaseStack::new()
+            .with_postgrest(PostgREST::default())
+            .with_toxiproxy(Toxiproxy::default());
+
+        let links: Vec<StackLink> = StackLink::ALL
+            .into_iter()
+            .filter(|link| stack.has_link(*link))
+            .collect();
+        assert_eq!(links, [StackLink::RestDb, StackLink::Rest]);
+        assert!(stack.toxiproxy.is_some());
+    }
+
+    #[test]
+    fn test_pin_ports_maps_every_exposed_port() {
+        use testcontainers_modules::testcontainers::core::ContainerPort;
+
+        let request = pin_ports(PostgREST::default().with_network("net"), true).unwrap();
+
+        let ports = request.ports().expect("ports are mapped");
+        let mut container_ports: Vec<_> = ports.iter().map(|port| port.container_port()).collect();
+        container_ports.sort_by_key(|port| port.as_u16());
+        assert_eq!(
+            container_ports,
+            [
+                ContainerPort::Tcp(POSTGREST_PORT),
+                ContainerPort::Tcp(POSTGREST_ADMIN_PORT)
+            ]
+        );
+        assert_ne!(ports[0].host_port(), ports[1].host_port());
+        assert!(ports.iter().all(|port| port.host_port() != 0));
+    }
+
+    #[test]
+    fn test_pin_ports_disabled() {
+        let request = pin_ports(PostgREST::default().with_network("net"), false).unwrap();
+
+        assert!(request.ports().is_none());
+    }
+
+    #[test]
+    fn test_stack_service_names() {
+        assert_eq!(StackService::Db.to_string(), "db");
+        assert_eq!(StackService::Rest.name(), "rest");
+    }
+
+    #[test]
+    fn test_link_upstreams() {
+        assert_eq!(link_upstream(StackLink::AuthDb, "net-db"), "net-db:5432");
+        assert_eq!(link_upstream(StackLink::StorageDb, "net-db"), "net-db:5432");
+        assert_eq!(link_upstream(StackLink::Auth, "net-auth"), "net-auth:9999");
+        assert_eq!(link_upstream(StackLink::Rest, "net-rest"), "net-rest:3000");
+        assert_eq!(
+            link_upstream(StackLink::Storage, "net-storage"),
+            "net-storage:5000"
+        );
+        assert_eq!(
+            link_db_address(StackLink::RestDb, None, "net-db"),
+            "net-db:5432"
+        );
+        assert_eq!(
+            link_db_address(StackLink::RestDb, Some("net-toxiproxy"), "net-db"),
+            "net-toxiproxy:15433"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "reuse")]
+    fn test_reused_toxiproxy_links_use_container_names() {
+        use testcontainers_modules::testcontainers::core::ContainerPort;
+
+        let links = [StackLink::RestDb, StackLink::Rest];
+        let request = toxiproxy_request(Toxiproxy::default(), &links, "net", "net-db-1a2b", true);
+        let toxiproxy_host = container_name(&request).unwrap();
+
+        // The reused name carries a hash, so services must be pointed at it
+        assert!(toxiproxy_host.starts_with("net-toxiproxy-"));
+        assert_eq!(
+            link_db_address(StackLink::RestDb, Some(&toxiproxy_host), "net-db-1a2b"),
+            format!("{}:15433", toxiproxy_host)
+        );
+        // The service proxies wait for the services' names, but their ports are exposed
+        assert_eq!(
+            request.image().proxies().get("rest_db"),
+            Some(&(15433, "net-db-1a2b:5432".to_string()))
+        );
+        assert!(request.image().proxies().get("rest").is_none());
+        assert!(request
+            .image()
+            .expose_ports()
+            .contains(&ContainerPort::Tcp(13000)));
+
+        let rest = reuse_if(
+            PostgREST::default()
+                .with_network("net")
+                .with_container_name("net-rest"),
+            true,
+        );
+        let rest_host = container_name(&rest).unwrap();
+        assert!(rest_host.starts_with("net-rest-"));
+        assert_eq!(
+            link_upstream(StackLink::Rest, &rest_host),
+            format!("{}:3000", rest_host)
+        );
+        assert_eq!(
+            container_name(&toxiproxy_request(
+                Toxiproxy::default(),
+                &links,
+                "net",
+                "net-db-1a2b",
+                true
+            ))
+            .unwrap(),
+            toxiproxy_host
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "reuse")]
+    fn test_reused_network_name_follows_configuration() {
+        let stack = SupabaseStack::new().with_reuse(true);
+        let name = reused_network_name(&stack);
+
+        assert!(name.starts_with(NETWORK_PREFIX));
+        assert_eq!(name, reused_network_name(&stack.clone()));
+        assert_eq!(
+            name,
+            reused_network_name(
+                &stack
+                    .clone()
+                    .with_artifacts_dir("/tmp/artifacts")
+                    .with_database_dump(true)
+            )
+        );
+        assert_ne!(
+            name,
+            reused_network_name(&stack.with_setup_sql("CREATE TABLE a ();"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_incompatible_version_fails_before_starting() {
+        let version = SupabaseVersion::tested().with_auth("v1.0.0");
+        let Err(error) = SupabaseStack::new()
+            .with_auth(Auth::default())
+            .with_version(version)
+            .start()
+            .await
+        else {
+            panic!("expected an incompatible version to be rejected");
+        };
+
+        assert!(error.to_string().contains("Auth v1.0.0"), "{}", error);
+    }
+
+    #[test]
+    fn test_reuse_if_disabled_keeps_request() {
+        let request = reuse_if(Postgres::default().with_container_name("db"), false);
+
+        assert_eq!(request.container_name().as_deref(), Some("db"));
+        assert_eq!(container_name(&request).unwrap(), "db");
+    }
+
+    #[test]
+    fn test_generated_network_names_are_unique() {
+        let first = generate_network_name();
+        let second = generate_network_name();
+
+        assert!(first.starts_with(NETWORK_PREFIX));
+        assert_ne!(first, second);
+    }
+}
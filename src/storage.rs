@@ -68,10 +68,13 @@ use testcontainers_modules::testcontainers::core::{
 };
 use testcontainers_modules::testcontainers::{Image, TestcontainersError};
 
+use crate::overrides::{image_name, image_tag, pin_digest, with_registry};
+use crate::{ReadyCheck, SupabaseVersion};
+
 /// Default image name for Supabase Storage
 const NAME: &str = "supabase/storage-api";
 /// Default image tag version
-const TAG: &str = "v1.11.1";
+const TAG: &str = crate::consts::STORAGE_TAG;
 /// Default port for Supabase Storage API
 pub const STORAGE_PORT: u16 = 5000;
 
@@ -104,8 +107,12 @@ pub const STORAGE_PORT: u16 = 5000;
 pub struct Storage {
     /// Environment variables to be passed to the container
     env_vars: BTreeMap<String, String>,
+    /// Docker image name
+    name: String,
     /// Docker image tag version
     tag: String,
+    /// How the container is considered ready
+    ready_check: ReadyCheck,
 }
 
 impl Storage {
@@ -200,6 +207,17 @@ impl Storage {
         self
     }
 
+    /// Returns the local file storage path, or `None` when a non-file backend is used
+    pub fn file_storage_path(&self) -> Option<&str> {
+        match self.env_vars.get("STORAGE_BACKEND").map(String::as_str) {
+            Some("file") => self
+                .env_vars
+                .get("FILE_STORAGE_BACKEND_PATH")
+                .map(String::as_str),
+            _ => None,
+        }
+    }
+
     /// Sets the expiration time for upload signed URLs in seconds
     pub fn with_upload_signed_url_expiration(mut self, seconds: u32) -> Self {
         self.env_vars.insert(
@@ -231,6 +249,43 @@ impl Storage {
         self
     }
 
+    /// Sets the Docker image name, e.g. a fork or a copy in a private registry
+    ///
+    /// `SUPABASE_TC_REGISTRY` still prefixes names without a registry
+    pub fn with_image_name(mut self, name: impl Into<String>) -> Self {
+        self.name = with_registry(&name.into());
+        self
+    }
+
+    /// Pins the image to a digest such as `sha256:4c1e...`
+    ///
+    /// Docker pulls the digest and ignores the tag; [`Storage::with_tag`] removes the pin
+    pub fn with_digest(mut self, digest: impl AsRef<str>) -> Self {
+        self.tag = pin_digest(&self.tag, digest.as_ref());
+        self
+    }
+
+    /// Sets the tag from a [`SupabaseVersion`]
+    pub fn with_version(self, version: &SupabaseVersion) -> Self {
+        self.with_tag(version.storage())
+    }
+
+    /// Sets how the container is considered ready
+    ///
+    /// Default is [`ReadyCheck::Http`] on `/status`, the route Supabase's compose
+    /// healthcheck uses, which answers once the server is listening.
+    /// [`ReadyCheck::Log`] waits for the startup log line instead.
+    pub fn with_ready_check(mut self, check: ReadyCheck) -> Self {
+        self.ready_check = check;
+        self
+    }
+
+    /// Returns how the container is considered ready
+    #[cfg(feature = "stack")]
+    pub(crate) fn ready_check(&self) -> &ReadyCheck {
+        &self.ready_check
+    }
+
     /// Adds a custom environment variable
     ///
     /// Use this for Storage configuration options not covered by other methods.
@@ -263,14 +318,16 @@ impl Default for Storage {
 
         Self {
             env_vars,
-            tag: TAG.to_string(),
+            name: image_name("STORAGE", NAME),
+            tag: image_tag("STORAGE", TAG),
+            ready_check: ReadyCheck::http("/status"),
         }
     }
 }
 
 impl Image for Storage {
     fn name(&self) -> &str {
-        NAME
+        &self.name
     }
 
     fn tag(&self) -> &str {
@@ -278,8 +335,10 @@ impl Image for Storage {
     }
 
     fn ready_conditions(&self) -> Vec<WaitFor> {
-        // Storage-api logs JSON format: {"msg":"[Server] Started Successfully",...}
-        vec![WaitFor::message_on_stdout("[Server] Started Successfully")]
+        vec![self.ready_check.wait_for(
+            STORAGE_PORT,
+            WaitFor::message_on_stdout("[Server] Started Successfully"),
+        )]
     }
 
     fn expose_ports(&self) -> &[ContainerPort] {
@@ -348,6 +407,33 @@ mod tests {
         assert_eq!(storage.tag(), "v1.11.1");
     }
 
+    #[test]
+    fn test_default_tag_matches_tested_version() {
+        assert_eq!(
+            Storage::default().tag(),
+            SupabaseVersion::tested().storage()
+        );
+    }
+
+    #[test]
+    fn test_with_version_sets_tag() {
+        let version = SupabaseVersion::tested().with_storage("v1.10.0");
+        assert_eq!(Storage::default().with_version(&version).tag(), "v1.10.0");
+    }
+
+    #[test]
+    fn test_with_image_name() {
+        let storage = Storage::default().with_image_name("ghcr.io/acme/storage");
+        assert_eq!(storage.name(), "ghcr.io/acme/storage");
+    }
+
+    #[test]
+    fn test_with_digest_pins_tag() {
+        let storage = Storage::default().with_tag("v1").with_digest("sha256:abc");
+        assert_eq!(storage.tag(), "v1@sha256:abc");
+        assert_eq!(storage.with_tag("v2").tag(), "v2");
+    }
+
     #[test]
     fn test_storage_port_constant() {
         assert_eq!(STORAGE_PORT, 5000);
@@ -451,6 +537,13 @@ mod tests {
             storage.env_vars.get("FILE_STORAGE_BACKEND_PATH"),
             Some(&"/data/storage".to_string())
         );
+        assert_eq!(storage.file_storage_path(), Some("/data/storage"));
+    }
+
+    #[test]
+    fn test_file_storage_path_with_s3_backend() {
+        let storage = Storage::default().with_storage_backend("s3");
+        assert_eq!(storage.file_storage_path(), None);
     }
 
     #[test]
@@ -565,5 +658,14 @@ mod tests {
         let storage = Storage::default();
         let conditions = storage.ready_conditions();
         assert_eq!(conditions.len(), 1);
+        assert!(matches!(conditions[0], WaitFor::Http(_)));
+    }
+
+    #[test]
+    fn test_with_ready_check_log() {
+        let storage = Storage::default().with_ready_check(ReadyCheck::Log);
+        let conditions = storage.ready_conditions();
+        assert_eq!(conditions.len(), 1);
+        assert!(matches!(conditions[0], WaitFor::Log(_)));
     }
 }
@@ -0,0 +1,859 @@
+/*! Toxiproxy container management module for fault injection.
+
+This module provides a testcontainer implementation for
+[Toxiproxy](https://github.com/Shopify/toxiproxy), a TCP proxy that injects network
+faults. Placed between a service and its database, or between the host and a service,
+it simulates latency, slow links, hangs and dropped connections without real outages.
+
+# Features
+
+- Proxies declared up front are created at startup and their ports exposed
+- [`ToxiproxyClient`] adds and removes toxics and cuts links at runtime
+- [`Toxic`] constructors for latency, bandwidth limits, timeouts and connection resets
+- [`StackLink`] names the proxies `SupabaseStack::with_toxiproxy` places in the stack
+
+# Architecture
+
+Each proxy listens on a port of the Toxiproxy container and forwards connections to
+its upstream. Toxics attached to a proxy alter the traffic in one direction: upstream
+(client to server) or downstream (server to client). Disabling a proxy closes its open
+connections and refuses new ones until it is enabled again.
+
+# Example
+
+```rust,no_run
+use std::time::Duration;
+
+use supabase_testcontainers_modules::{Toxic, Toxiproxy, ToxiproxyClient};
+use testcontainers::{runners::AsyncRunner, ImageExt};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    // PostgreSQL runs as "db" on "my-network"
+    let toxiproxy = Toxiproxy::default()
+        .with_proxy("db", 15432, "db:5432")
+        .with_network("my-network")
+        .start()
+        .await?;
+
+    let client = ToxiproxyClient::from_container(&toxiproxy).await?;
+    client
+        .add_toxic("db", &Toxic::latency(Duration::from_millis(500)))
+        .await?;
+    // ... services connecting to toxiproxy:15432 now see slow queries ...
+
+    client.set_enabled("db", false).await?; // Drop every connection
+    client.reset().await?; // Remove all toxics and enable all proxies
+    Ok(())
+}
+```
+
+# Configuration
+
+The [`Toxiproxy`] struct provides builder methods for common configuration options:
+
+- [`Toxiproxy::with_proxy`] - Create a proxy at startup and expose its port
+- [`Toxiproxy::with_ready_check`] - How the container is considered ready
+
+See the struct documentation for the full list of options.
+*/
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use serde_json::{json, Value};
+use testcontainers_modules::testcontainers::core::{
+    ContainerPort, ContainerState, CopyToContainer, ExecCommand, WaitFor,
+};
+use testcontainers_modules::testcontainers::{ContainerAsync, Image, TestcontainersError};
+
+use crate::overrides::{image_name, image_tag, pin_digest, with_registry};
+use crate::ReadyCheck;
+
+/// Default image name for Toxiproxy
+const NAME: &str = "ghcr.io/shopify/toxiproxy";
+/// Default image tag version
+const TAG: &str = "2.9.0";
+/// Default port for the Toxiproxy API
+pub const TOXIPROXY_API_PORT: u16 = 8474;
+/// Path of the generated proxy configuration inside the container
+const CONFIG_PATH: &str = "/config/toxiproxy.json";
+
+/// A link in a stack that Toxiproxy can sit on
+///
+/// `SupabaseStack::with_toxiproxy` creates a proxy for every link whose service is part
+/// of the stack. Pass a link wherever a [`ToxiproxyClient`] method takes a proxy name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StackLink {
+    /// Auth to PostgreSQL
+    AuthDb,
+    /// PostgREST to PostgreSQL
+    RestDb,
+    /// Storage to PostgreSQL
+    StorageDb,
+    /// Host to Auth
+    Auth,
+    /// Host to PostgREST
+    Rest,
+    /// Host to Storage
+    Storage,
+}
+
+impl StackLink {
+    /// Every link
+    pub const ALL: [StackLink; 6] = [
+        StackLink::AuthDb,
+        StackLink::RestDb,
+        StackLink::StorageDb,
+        StackLink::Auth,
+        StackLink::Rest,
+        StackLink::Storage,
+    ];
+
+    /// Returns the name of the link's proxy
+    pub fn proxy_name(&self) -> &'static str {
+        match self {
+            StackLink::AuthDb => "auth_db",
+            StackLink::RestDb => "rest_db",
+            StackLink::StorageDb => "storage_db",
+            StackLink::Auth => "auth",
+            StackLink::Rest => "rest",
+            StackLink::Storage => "storage",
+        }
+    }
+
+    /// Returns the port the link's proxy listens on inside the Toxiproxy container
+    pub fn listen_port(&self) -> u16 {
+        match self {
+            StackLink::AuthDb => 15432,
+            StackLink::RestDb => 15433,
+            StackLink::StorageDb => 15434,
+            StackLink::Auth => 19999,
+            StackLink::Rest => 13000,
+            StackLink::Storage => 15000,
+        }
+    }
+}
+
+impl AsRef<str> for StackLink {
+    fn as_ref(&self) -> &str {
+        self.proxy_name()
+    }
+}
+
+impl std::fmt::Display for StackLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.proxy_name())
+    }
+}
+
+/// Toxiproxy container for injecting network faults between containers.
+///
+/// This struct implements the [`Image`] trait from testcontainers, allowing you to
+/// start a Toxiproxy instance with proxies created at startup.
+///
+/// # Default Configuration
+///
+/// The default configuration includes:
+/// - Toxiproxy API on port 8474
+/// - No proxies
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use supabase_testcontainers_modules::Toxiproxy;
+///
+/// let toxiproxy = Toxiproxy::default()
+///     .with_proxy("db", 15432, "db:5432")
+///     .with_proxy("rest", 13000, "rest:3000");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Toxiproxy {
+    /// Environment variables to be passed to the container
+    env_vars: BTreeMap<String, String>,
+    /// Docker image name
+    name: String,
+    /// Docker image tag version
+    tag: String,
+    /// Proxy name to listen port and upstream address
+    proxies: BTreeMap<String, (u16, String)>,
+    /// Listen ports exposed for proxies created through the API
+    exposed_ports: BTreeSet<u16>,
+    /// API port and the proxies' listen ports
+    ports: Vec<ContainerPort>,
+    /// Generated proxy configuration file
+    config: Vec<CopyToContainer>,
+    /// How the container is considered ready
+    ready_check: ReadyCheck,
+}
+
+impl Toxiproxy {
+    /// Creates a new Toxiproxy instance
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a proxy at startup that listens on `listen_port` and forwards to `upstream`
+    ///
+    /// `upstream` is a `host:port` address as seen from the Toxiproxy container. The
+    /// listen port is exposed, so the proxy is also reachable from the host. A proxy
+    /// with the same name replaces the earlier one.
+    pub fn with_proxy(
+        mut self,
+        name: impl Into<String>,
+        listen_port: u16,
+        upstream: impl Into<String>,
+    ) -> Self {
+        self.proxies
+            .insert(name.into(), (listen_port, upstream.into()));
+        self.with_generated_config()
+    }
+
+    /// Exposes `listen_port` for a proxy created later with [`ToxiproxyClient::create_proxy`]
+    ///
+    /// Docker publishes ports only when the container starts, so a proxy created at
+    /// runtime is reachable from the host only if its listen port was exposed here.
+    pub fn with_exposed_port(mut self, listen_port: u16) -> Self {
+        self.exposed_ports.insert(listen_port);
+        self.with_generated_config()
+    }
+
+    /// Returns the proxies created at startup, by name, with listen port and upstream
+    pub fn proxies(&self) -> &BTreeMap<String, (u16, String)> {
+        &self.proxies
+    }
+
+    /// Sets a custom Docker image tag/version
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = tag.into();
+        self
+    }
+
+    /// Sets the Docker image name, e.g. a fork or a copy in a private registry
+    ///
+    /// `SUPABASE_TC_REGISTRY` still prefixes names without a registry
+    pub fn with_image_name(mut self, name: impl Into<String>) -> Self {
+        self.name = with_registry(&name.into());
+        self
+    }
+
+    /// Pins the image to a digest such as `sha256:4c1e...`
+    ///
+    /// Docker pulls the digest and ignores the tag; [`Toxiproxy::with_tag`] removes the pin
+    pub fn with_digest(mut self, digest: impl AsRef<str>) -> Self {
+        self.tag = pin_digest(&self.tag, digest.as_ref());
+        self
+    }
+
+    /// Sets how the container is considered ready
+    ///
+    /// Default is [`ReadyCheck::Http`] on `/version` of the Toxiproxy API.
+    /// [`ReadyCheck::Log`] waits for the startup log line instead.
+    pub fn with_ready_check(mut self, check: ReadyCheck) -> Self {
+        self.ready_check = check;
+        self
+    }
+
+    /// Adds a custom environment variable
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env_vars.insert(key.into(), value.into());
+        self
+    }
+
+    /// Returns the generated proxy configuration
+    pub fn config(&self) -> Value {
+        Value::Array(
+            self.proxies
+                .iter()
+                .map(|(name, (listen_port, upstream))| {
+                    json!({
+                        "name": name,
+                        "listen": format!("0.0.0.0:{}", listen_port),
+                        "upstream": upstream,
+                        "enabled": true,
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// Regenerates the configuration file and the exposed ports
+    fn with_generated_config(mut self) -> Self {
+        let config = self.config().to_string().into_bytes();
+        self.config = vec![CopyToContainer::new(config, CONFIG_PATH)];
+        let mut ports = vec![TOXIPROXY_API_PORT];
+        for port in self
+            .proxies
+            .values()
+            .map(|(port, _)| *port)
+            .chain(self.exposed_ports.iter().copied())
+        {
+            if !ports.contains(&port) {
+                ports.push(port);
+            }
+        }
+        self.ports = ports.into_iter().map(ContainerPort::Tcp).collect();
+        self
+    }
+}
+
+impl Default for Toxiproxy {
+    fn default() -> Self {
+        Self {
+            env_vars: BTreeMap::new(),
+            name: image_name("TOXIPROXY", NAME),
+            tag: image_tag("TOXIPROXY", TAG),
+            proxies: BTreeMap::new(),
+            exposed_ports: BTreeSet::new(),
+            ports: Vec::new(),
+            config: Vec::new(),
+            ready_check: ReadyCheck::http("/version"),
+        }
+        .with_generated_config()
+    }
+}
+
+impl Image for Toxiproxy {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    fn ready_conditions(&self) -> Vec<WaitFor> {
+        vec![self.ready_check.wait_for(
+            TOXIPROXY_API_PORT,
+            WaitFor::message_on_either_std("Starting Toxiproxy HTTP server"),
+        )]
+    }
+
+    fn expose_ports(&self) -> &[ContainerPort] {
+        &self.ports
+    }
+
+    fn env_vars(
+        &self,
+    ) -> impl IntoIterator<Item = (impl Into<Cow<'_, str>>, impl Into<Cow<'_, str>>)> {
+        &self.env_vars
+    }
+
+    fn copy_to_sources(&self) -> impl IntoIterator<Item = &CopyToContainer> {
+        &self.config
+    }
+
+    fn cmd(&self) -> impl IntoIterator<Item = impl Into<Cow<'_, str>>> {
+        [
+            "-host=0.0.0.0".to_string(),
+            format!("-config={}", CONFIG_PATH),
+        ]
+    }
+
+    #[allow(unused_variables)]
+    fn exec_after_start(
+        &self,
+        cs: ContainerState,
+    ) -> Result<Vec<ExecCommand>, TestcontainersError> {
+        Ok(vec![])
+    }
+}
+
+/// Direction of traffic a [`Toxic`] applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToxicStream {
+    /// Client to server
+    Upstream,
+    /// Server to client
+    Downstream,
+}
+
+impl ToxicStream {
+    /// Returns the stream name Toxiproxy uses
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ToxicStream::Upstream => "upstream",
+            ToxicStream::Downstream => "downstream",
+        }
+    }
+}
+
+/// A network fault added to a proxy with [`ToxiproxyClient::add_toxic`]
+///
+/// Toxics apply downstream by default and are named `<type>_<stream>` unless
+/// [`Toxic::with_name`] is used; a proxy cannot have two toxics with the same name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Toxic {
+    /// Name, if not derived from type and stream
+    name: Option<String>,
+    /// Toxic type, e.g. `latency`
+    kind: String,
+    /// Direction of traffic affected
+    stream: ToxicStream,
+    /// Probability of the toxic applying to a connection, between 0 and 1
+    toxicity: f32,
+    /// Type-specific attributes
+    attributes: serde_json::Map<String, Value>,
+}
+
+impl Toxic {
+    /// Creates a toxic of any type Toxiproxy supports, without attributes
+    pub fn new(kind: impl Into<String>) -> Self {
+        Self {
+            name: None,
+            kind: kind.into(),
+            stream: ToxicStream::Downstream,
+            toxicity: 1.0,
+            attributes: serde_json::Map::new(),
+        }
+    }
+
+    /// Delays all data by `latency`
+    pub fn latency(latency: Duration) -> Self {
+        Self::new("latency").with_attribute("latency", millis(latency))
+    }
+
+    /// Limits the link to `rate` kilobytes per second
+    pub fn bandwidth(rate: u64) -> Self {
+        Self::new("bandwidth").with_attribute("rate", rate)
+    }
+
+    /// Stops all data and closes the connection after `timeout`
+    ///
+    /// A zero timeout keeps the connection open without data until the toxic is
+    /// removed, like a peer that hangs.
+    pub fn timeout(timeout: Duration) -> Self {
+        Self::new("timeout").with_attribute("timeout", millis(timeout))
+    }
+
+    /// Resets connections (TCP RST) after `timeout`
+    pub fn reset_peer(timeout: Duration) -> Self {
+        Self::new("reset_peer").with_attribute("timeout", millis(timeout))
+    }
+
+    /// Delays closing connections by `delay`
+    pub fn slow_close(delay: Duration) -> Self {
+        Self::new("slow_close").with_attribute("delay", millis(delay))
+    }
+
+    /// Closes connections after `bytes` have been transmitted
+    pub fn limit_data(bytes: u64) -> Self {
+        Self::new("limit_data").with_attribute("bytes", bytes)
+    }
+
+    /// Adds random variation of up to `jitter` to a latency toxic
+    pub fn with_jitter(self, jitter: Duration) -> Self {
+        self.with_attribute("jitter", millis(jitter))
+    }
+
+    /// Sets a type-specific attribute
+    pub fn with_attribute(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.attributes.insert(key.into(), value.into());
+        self
+    }
+
+    /// Sets the toxic's name, used to remove it
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Sets the direction of traffic the toxic applies to
+    ///
+    /// Default is [`ToxicStream::Downstream`].
+    pub fn with_stream(mut self, stream: ToxicStream) -> Self {
+        self.stream = stream;
+        self
+    }
+
+    /// Sets the probability of the toxic applying to a connection, between 0 and 1
+    ///
+    /// Default is 1.
+    pub fn with_toxicity(mut self, toxicity: f32) -> Self {
+        self.toxicity = toxicity;
+        self
+    }
+
+    /// Returns the toxic's name
+    pub fn name(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("{}_{}", self.kind, self.stream.as_str()),
+        }
+    }
+
+    /// Returns the body Toxiproxy's API expects
+    fn body(&self) -> Value {
+        json!({
+            "name": self.name(),
+            "type": self.kind,
+            "stream": self.stream.as_str(),
+            "toxicity": self.toxicity,
+            "attributes": self.attributes,
+        })
+    }
+}
+
+/// A proxy as reported by [`ToxiproxyClient::proxies`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToxiproxyProxy {
+    /// Proxy name
+    pub name: String,
+    /// Listen address inside the Toxiproxy container
+    pub listen: String,
+    /// Upstream address
+    pub upstream: String,
+    /// Whether the proxy accepts connections
+    pub enabled: bool,
+    /// Names of the toxics attached
+    pub toxics: Vec<String>,
+}
+
+/// Client for the Toxiproxy API
+///
+/// Proxy names are `&str` or anything else implementing `AsRef<str>`, such as
+/// [`StackLink`].
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+///
+/// use supabase_testcontainers_modules::{StackLink, Toxic, ToxiproxyClient};
+///
+/// async fn slow_database(client: &ToxiproxyClient) -> anyhow::Result<()> {
+///     client
+///         .add_toxic(StackLink::RestDb, &Toxic::latency(Duration::from_secs(2)))
+///         .await?;
+///     client.set_enabled(StackLink::AuthDb, false).await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ToxiproxyClient {
+    /// Underlying HTTP client
+    http: reqwest::Client,
+    /// Base URL of the Toxiproxy API, without a trailing slash
+    base_url: String,
+}
+
+impl ToxiproxyClient {
+    /// Creates a client for the Toxiproxy API at `base_url`
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Creates a client for a running [`Toxiproxy`] container
+    ///
+    /// # Errors
+    /// Returns an error if the API port is not mapped.
+    pub async fn from_container(container: &ContainerAsync<Toxiproxy>) -> anyhow::Result<Self> {
+        let host = container.get_host().await?;
+        let port = container.get_host_port_ipv4(TOXIPROXY_API_PORT).await?;
+        Ok(Self::new(format!("http://{}:{}", host, port)))
+    }
+
+    /// Returns the base URL of the Toxiproxy API
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Lists all proxies
+    pub async fn proxies(&self) -> anyhow::Result<Vec<ToxiproxyProxy>> {
+        let body = self
+            .send(self.http.get(format!("{}/proxies", self.base_url)))
+            .await
+            .context("failed to list proxies")?;
+        body.as_object()
+            .context("Toxiproxy returned a non-object proxy list")?
+            .values()
+            .map(parse_proxy)
+            .collect()
+    }
+
+    /// Creates a proxy that listens on `listen_port` and forwards to `upstream`
+    ///
+    /// Only ports exposed with [`Toxiproxy::with_proxy`] or [`Toxiproxy::with_exposed_port`]
+    /// are reachable from the host; other proxies serve containers on the same network.
+    pub async fn create_proxy(
+        &self,
+        name: &str,
+        listen_port: u16,
+        upstream: &str,
+    ) -> anyhow::Result<()> {
+        self.send(
+            self.http
+                .post(format!("{}/proxies", self.base_url))
+                .json(&json!({
+                    "name": name,
+                    "listen": format!("0.0.0.0:{}", listen_port),
+                    "upstream": upstream,
+                    "enabled": true,
+                })),
+        )
+        .await
+        .with_context(|| format!("failed to create proxy '{}'", name))?;
+        Ok(())
+    }
+
+    /// Enables or disables a proxy
+    ///
+    /// Disabling closes the proxy's open connections and refuses new ones, like a
+    /// lost network link.
+    pub async fn set_enabled(&self, proxy: impl AsRef<str>, enabled: bool) -> anyhow::Result<()> {
+        let proxy = proxy.as_ref();
+        self.send(
+            self.http
+                .post(format!("{}/proxies/{}", self.base_url, proxy))
+                .json(&json!({ "enabled": enabled })),
+        )
+        .await
+        .with_context(|| format!("failed to update proxy '{}'", proxy))?;
+        Ok(())
+    }
+
+    /// Adds a toxic to a proxy
+    pub async fn add_toxic(&self, proxy: impl AsRef<str>, toxic: &Toxic) -> anyhow::Result<()> {
+        let proxy = proxy.as_ref();
+        self.send(
+            self.http
+                .post(format!("{}/proxies/{}/toxics", self.base_url, proxy))
+                .json(&toxic.body()),
+        )
+        .await
+        .with_context(|| format!("failed to add toxic '{}' to '{}'", toxic.name(), proxy))?;
+        Ok(())
+    }
+
+    /// Removes the toxic named `name` from a proxy
+    pub async fn remove_toxic(&self, proxy: impl AsRef<str>, name: &str) -> anyhow::Result<()> {
+        let proxy = proxy.as_ref();
+        self.send(self.http.delete(format!(
+            "{}/proxies/{}/toxics/{}",
+            self.base_url, proxy, name
+        )))
+        .await
+        .with_context(|| format!("failed to remove toxic '{}' from '{}'", name, proxy))?;
+        Ok(())
+    }
+
+    /// Removes all toxics and enables all proxies
+    pub async fn reset(&self) -> anyhow::Result<()> {
+        self.send(self.http.post(format!("{}/reset", self.base_url)))
+            .await
+            .context("failed to reset Toxiproxy")?;
+        Ok(())
+    }
+
+    /// Sends a request and returns the JSON body (null when empty)
+    async fn send(&self, request: reqwest::RequestBuilder) -> anyhow::Result<Value> {
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("failed to reach Toxiproxy at {}", self.base_url))?;
+
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .context("failed to read Toxiproxy response")?;
+        if !status.is_success() {
+            bail!("Toxiproxy returned {}: {}", status, text);
+        }
+        if text.trim().is_empty() {
+            return Ok(Value::Null);
+        }
+        serde_json::from_str(&text).context("Toxiproxy returned invalid JSON")
+    }
+}
+
+/// Parses a proxy object from the API
+fn parse_proxy(value: &Value) -> anyhow::Result<ToxiproxyProxy> {
+    let string = |key: &str| {
+        value[key]
+            .as_str()
+            .map(str::to_string)
+            .with_context(|| format!("proxy has no {}", key))
+    };
+    Ok(ToxiproxyProxy {
+        name: string("name")?,
+        listen: string("listen")?,
+        upstream: string("upstream")?,
+        enabled: value["enabled"].as_bool().unwrap_or(false),
+        toxics: value["toxics"]
+            .as_array()
+            .map(|toxics| {
+                toxics
+                    .iter()
+                    .filter_map(|toxic| toxic["name"].as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    })
+}
+
+/// Returns `duration` in whole milliseconds, as Toxiproxy's attributes expect
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis().try_into().unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+#[cfg(feature = "toxiproxy")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_configuration() {
+        let toxiproxy = Toxiproxy::default();
+
+        assert_eq!(toxiproxy.name(), NAME);
+        assert_eq!(toxiproxy.tag(), TAG);
+        assert!(toxiproxy.proxies().is_empty());
+        assert_eq!(
+            toxiproxy.expose_ports(),
+            &[ContainerPort::Tcp(TOXIPROXY_API_PORT)]
+        );
+        assert_eq!(toxiproxy.config(), json!([]));
+    }
+
+    #[test]
+    fn test_proxies_are_configured_and_exposed() {
+        let toxiproxy = Toxiproxy::default()
+            .with_proxy("db", 15432, "db:5432")
+            .with_proxy("rest", 13000, "rest:3000")
+            .with_proxy("db", 15433, "db:5432");
+
+        assert_eq!(
+            toxiproxy.config(),
+            json!([
+                { "name": "db", "listen": "0.0.0.0:15433", "upstream": "db:5432", "enabled": true },
+                { "name": "rest", "listen": "0.0.0.0:13000", "upstream": "rest:3000", "enabled": true },
+            ])
+        );
+        assert_eq!(
+            toxiproxy.expose_ports(),
+            &[
+                ContainerPort::Tcp(TOXIPROXY_API_PORT),
+                ContainerPort::Tcp(15433),
+                ContainerPort::Tcp(13000),
+            ]
+        );
+        assert_eq!(toxiproxy.copy_to_sources().into_iter().count(), 1);
+    }
+
+    #[test]
+    fn test_exposed_ports_are_not_configured() {
+        let toxiproxy = Toxiproxy::default()
+            .with_exposed_port(13000)
+            .with_proxy("db", 15432, "db:5432")
+            .with_exposed_port(15432);
+
+        assert_eq!(
+            toxiproxy.config(),
+            json!([
+                { "name": "db", "listen": "0.0.0.0:15432", "upstream": "db:5432", "enabled": true },
+            ])
+        );
+        assert_eq!(
+            toxiproxy.expose_ports(),
+            &[
+                ContainerPort::Tcp(TOXIPROXY_API_PORT),
+                ContainerPort::Tcp(15432),
+                ContainerPort::Tcp(13000),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cmd_loads_config() {
+        let toxiproxy = Toxiproxy::default();
+        let cmd: Vec<String> = toxiproxy
+            .cmd()
+            .into_iter()
+            .map(|arg| arg.into().into_owned())
+            .collect();
+
+        assert_eq!(cmd, ["-host=0.0.0.0", "-config=/config/toxiproxy.json"]);
+    }
+
+    #[test]
+    fn test_toxic_bodies() {
+        assert_eq!(
+            Toxic::latency(Duration::from_millis(250))
+                .with_jitter(Duration::from_millis(50))
+                .body(),
+            json!({
+                "name": "latency_downstream",
+                "type": "latency",
+                "stream": "downstream",
+                "toxicity": 1.0,
+                "attributes": { "latency": 250, "jitter": 50 },
+            })
+        );
+        assert_eq!(
+            Toxic::bandwidth(64)
+                .with_stream(ToxicStream::Upstream)
+                .with_name("slow_uploads")
+                .with_toxicity(0.5)
+                .body(),
+            json!({
+                "name": "slow_uploads",
+                "type": "bandwidth",
+                "stream": "upstream",
+                "toxicity": 0.5,
+                "attributes": { "rate": 64 },
+            })
+        );
+        assert_eq!(
+            Toxic::timeout(Duration::ZERO).body()["attributes"],
+            json!({ "timeout": 0 })
+        );
+        assert_eq!(
+            Toxic::reset_peer(Duration::from_secs(1)).name(),
+            "reset_peer_downstream"
+        );
+    }
+
+    #[test]
+    fn test_stack_links_are_unique() {
+        let names: std::collections::BTreeSet<_> =
+            StackLink::ALL.iter().map(StackLink::proxy_name).collect();
+        let ports: std::collections::BTreeSet<_> =
+            StackLink::ALL.iter().map(StackLink::listen_port).collect();
+
+        assert_eq!(names.len(), StackLink::ALL.len());
+        assert_eq!(ports.len(), StackLink::ALL.len());
+        assert!(!ports.contains(&TOXIPROXY_API_PORT));
+        assert_eq!(StackLink::RestDb.as_ref(), "rest_db");
+    }
+
+    #[test]
+    fn test_parse_proxy() {
+        let proxy = parse_proxy(&json!({
+            "name": "db",
+            "listen": "[::]:15432",
+            "upstream": "db:5432",
+            "enabled": true,
+            "toxics": [{ "name": "latency_downstream", "type": "latency" }],
+        }))
+        .unwrap();
+
+        assert_eq!(proxy.name, "db");
+        assert_eq!(proxy.upstream, "db:5432");
+        assert!(proxy.enabled);
+        assert_eq!(proxy.toxics, ["latency_downstream"]);
+        assert!(parse_proxy(&json!({ "name": "db" })).is_err());
+    }
+
+    #[test]
+    fn test_client_trims_base_url() {
+        let client = ToxiproxyClient::new("http://localhost:8474/");
+
+        assert_eq!(client.base_url(), "http://localhost:8474");
+    }
+}
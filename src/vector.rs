@@ -0,0 +1,664 @@
+/*! Vector log shipping container management module.
+
+This module provides a testcontainer implementation for [Vector](https://vector.dev/),
+the log router Supabase uses to ship service logs into Analytics (Logflare).
+
+# Features
+
+- Tails the Docker logs of selected containers through the Docker socket
+- Routes each container to a Logflare source, using Supabase's default source names
+- Generated configuration mirroring Supabase's self-hosted `vector.yml`
+- Health endpoint on the Vector API for readiness checks
+
+# Architecture
+
+In hosted Supabase, every service writes logs to stdout/stderr, Vector collects them
+with its `docker_logs` source and posts them to Logflare's `/api/logs` endpoint with a
+per-service `source_name`. Those sources (`gotrue.logs.prod`, `postgREST.logs.prod`,
+...) are seeded by Logflare when [`Analytics::with_supabase_mode`] is enabled, which is
+the default, and are what the dashboards' log queries read from.
+
+[`Analytics::with_supabase_mode`]: crate::Analytics::with_supabase_mode
+
+# Example
+
+```rust,no_run
+use supabase_testcontainers_modules::{LogSource, Vector};
+use testcontainers::{runners::AsyncRunner, ImageExt};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    // Analytics runs as "analytics" and PostgREST as "rest" on "my-network"
+    let vector = Vector::default()
+        .with_analytics_url("http://analytics:4000")
+        .with_api_key("your-public-token")
+        .with_container("rest", LogSource::Rest)
+        .with_network("my-network")
+        .start()
+        .await?;
+
+    Ok(())
+}
+```
+
+# Configuration
+
+The [`Vector`] struct provides builder methods for common configuration options:
+
+- [`Vector::with_analytics_url`] - Logflare URL as seen from the Vector container
+- [`Vector::with_api_key`] - Logflare access token used for ingestion
+- [`Vector::with_container`] - Ship a container's logs to a Supabase source
+- [`Vector::with_route`] - Ship a container's logs to any source name
+- [`Vector::with_docker_socket`] - Host path of the Docker socket
+
+Route at least one container before starting: Vector refuses to start without a sink.
+
+See the struct documentation for the full list of options.
+*/
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use serde_json::{json, Value};
+use testcontainers_modules::testcontainers::core::{
+    AccessMode, ContainerPort, ContainerState, CopyToContainer, ExecCommand, Mount, WaitFor,
+};
+use testcontainers_modules::testcontainers::{Image, TestcontainersError};
+
+use crate::overrides::{image_name, image_tag, pin_digest, with_registry};
+use crate::{ReadyCheck, SupabaseVersion};
+
+/// Default image name for Vector
+const NAME: &str = "timberio/vector";
+/// Default image tag version
+const TAG: &str = crate::consts::VECTOR_TAG;
+/// Default port for the Vector API (health checks)
+pub const VECTOR_API_PORT: u16 = 9001;
+/// Path of the generated configuration inside the container
+const CONFIG_PATH: &str = "/etc/vector/vector.json";
+/// Default Docker socket path on the host
+const DOCKER_SOCKET: &str = "/var/run/docker.sock";
+/// Default Logflare URL, matching Supabase's compose service name
+const DEFAULT_ANALYTICS_URL: &str = "http://analytics:4000";
+
+/// A Supabase service whose logs have a default Logflare source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogSource {
+    /// Auth (GoTrue)
+    Auth,
+    /// PostgREST
+    Rest,
+    /// Realtime
+    Realtime,
+    /// Storage
+    Storage,
+    /// Edge Functions
+    Functions,
+    /// PostgreSQL
+    Db,
+    /// Kong API gateway
+    Kong,
+}
+
+impl LogSource {
+    /// Every service with a default source
+    pub const ALL: [LogSource; 7] = [
+        LogSource::Auth,
+        LogSource::Rest,
+        LogSource::Realtime,
+        LogSource::Storage,
+        LogSource::Functions,
+        LogSource::Db,
+        LogSource::Kong,
+    ];
+
+    /// Returns the Logflare source name Supabase ships this service's logs to
+    pub fn source_name(&self) -> &'static str {
+        match self {
+            LogSource::Auth => "gotrue.logs.prod",
+            LogSource::Rest => "postgREST.logs.prod",
+            LogSource::Realtime => "realtime.logs.prod",
+            LogSource::Storage => "storage.logs.prod.2",
+            LogSource::Functions => "deno-relay-logs",
+            LogSource::Db => "postgres.logs",
+            LogSource::Kong => "cloudflare.logs.prod",
+        }
+    }
+}
+
+impl std::fmt::Display for LogSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.source_name())
+    }
+}
+
+/// Vector container for shipping container logs into Analytics.
+///
+/// This struct implements the [`Image`] trait from testcontainers, allowing you to
+/// start a Vector instance that tails other containers' logs and forwards them to
+/// Logflare sources.
+///
+/// # Default Configuration
+///
+/// The default configuration includes:
+/// - Logflare URL "http://analytics:4000"
+/// - Docker socket mounted read-only from `/var/run/docker.sock`
+/// - Vector API on port 9001
+/// - No routed containers
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use supabase_testcontainers_modules::{LogSource, Vector};
+///
+/// let vector = Vector::default()
+///     .with_api_key("your-public-token")
+///     .with_container("auth", LogSource::Auth)
+///     .with_container("rest", LogSource::Rest)
+///     .with_route("worker", "worker.logs");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Vector {
+    /// Environment variables to be passed to the container
+    env_vars: BTreeMap<String, String>,
+    /// Docker image name
+    name: String,
+    /// Docker image tag version
+    tag: String,
+    /// Logflare URL as seen from the container
+    analytics_url: String,
+    /// Container name to Logflare source name
+    routes: BTreeMap<String, String>,
+    /// Docker socket bind mount
+    mounts: Vec<Mount>,
+    /// Generated configuration file
+    config: Vec<CopyToContainer>,
+    /// How the container is considered ready
+    ready_check: ReadyCheck,
+}
+
+impl Vector {
+    /// Creates a new Vector instance
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new Vector instance with custom environment variables
+    pub fn new_with_env(envs: BTreeMap<&str, &str>) -> Self {
+        let mut instance = Self::default();
+        for (key, val) in envs {
+            instance.env_vars.insert(key.to_string(), val.to_string());
+        }
+        instance
+    }
+
+    /// Sets the Logflare URL as seen from the Vector container
+    ///
+    /// Default is "http://analytics:4000".
+    pub fn with_analytics_url(mut self, url: impl Into<String>) -> Self {
+        self.analytics_url = url.into().trim_end_matches('/').to_string();
+        self.with_generated_config()
+    }
+
+    /// Sets the Logflare access token used for ingestion
+    ///
+    /// Use the Analytics container's public access token.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.env_vars
+            .insert("LOGFLARE_API_KEY".to_string(), api_key.into());
+        self
+    }
+
+    /// Ships the logs of the container named `container_name` to `source`'s default source
+    pub fn with_container(self, container_name: impl Into<String>, source: LogSource) -> Self {
+        self.with_route(container_name, source.source_name())
+    }
+
+    /// Ships the logs of the container named `container_name` to `source_name`
+    pub fn with_route(
+        mut self,
+        container_name: impl Into<String>,
+        source_name: impl Into<String>,
+    ) -> Self {
+        self.routes
+            .insert(container_name.into(), source_name.into());
+        self.with_generated_config()
+    }
+
+    /// Sets the host path of the Docker socket
+    ///
+    /// Default is "/var/run/docker.sock". The socket is mounted read-only.
+    pub fn with_docker_socket(mut self, path: impl Into<String>) -> Self {
+        self.mounts = vec![docker_socket_mount(path.into())];
+        self
+    }
+
+    /// Sets the Vector log level
+    ///
+    /// Valid values: "trace", "debug", "info", "warn", "error"
+    pub fn with_log_level(mut self, level: impl Into<String>) -> Self {
+        self.env_vars.insert("VECTOR_LOG".to_string(), level.into());
+        self
+    }
+
+    /// Sets a custom Docker image tag/version
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = tag.into();
+        self
+    }
+
+    /// Sets the Docker image name, e.g. a fork or a copy in a private registry
+    ///
+    /// `SUPABASE_TC_REGISTRY` still prefixes names without a registry
+    pub fn with_image_name(mut self, name: impl Into<String>) -> Self {
+        self.name = with_registry(&name.into());
+        self
+    }
+
+    /// Pins the image to a digest such as `sha256:4c1e...`
+    ///
+    /// Docker pulls the digest and ignores the tag; [`Vector::with_tag`] removes the pin
+    pub fn with_digest(mut self, digest: impl AsRef<str>) -> Self {
+        self.tag = pin_digest(&self.tag, digest.as_ref());
+        self
+    }
+
+    /// Sets the tag from a [`SupabaseVersion`]
+    pub fn with_version(self, version: &SupabaseVersion) -> Self {
+        self.with_tag(version.vector())
+    }
+
+    /// Sets how the container is considered ready
+    ///
+    /// Default is [`ReadyCheck::Http`] on `/health` of the Vector API, which returns
+    /// 200 once the topology is running. [`ReadyCheck::Log`] waits for the startup
+    /// log line instead.
+    pub fn with_ready_check(mut self, check: ReadyCheck) -> Self {
+        self.ready_check = check;
+        self
+    }
+
+    /// Adds a custom environment variable
+    ///
+    /// Use this for Vector configuration options not covered by other methods.
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env_vars.insert(key.into(), value.into());
+        self
+    }
+
+    /// Returns the generated Vector configuration
+    ///
+    /// The configuration follows Supabase's `vector.yml`: a `docker_logs` source
+    /// limited to the routed containers, a remap renaming `message` to
+    /// `event_message` and `container_name` to `appname`, a router and one HTTP
+    /// sink per Logflare source.
+    pub fn config(&self) -> Value {
+        let containers: Vec<&String> = self.routes.keys().collect();
+
+        let mut route = serde_json::Map::new();
+        let mut sinks = serde_json::Map::new();
+        for (index, (container, source)) in self.routes.iter().enumerate() {
+            let route_name = format!("route_{}", index);
+            route.insert(
+                route_name.clone(),
+                Value::String(format!(".appname == {}", Value::String(container.clone()))),
+            );
+            sinks.insert(
+                format!("logflare_{}", index),
+                json!({
+                    "type": "http",
+                    "inputs": [format!("router.{}", route_name)],
+                    "encoding": { "codec": "json" },
+                    "method": "post",
+                    "batch": { "timeout_secs": 1 },
+                    "request": { "retry_max_duration_secs": 10 },
+                    "uri": format!(
+                        "{}/api/logs?source_name={}&api_key=${{LOGFLARE_API_KEY}}",
+                        self.analytics_url, source
+                    ),
+                }),
+            );
+        }
+
+        json!({
+            "api": { "enabled": true, "address": format!("0.0.0.0:{}", VECTOR_API_PORT) },
+            "sources": {
+                "docker_host": {
+                    "type": "docker_logs",
+                    "include_containers": containers,
+                }
+            },
+            "transforms": {
+                "project_logs": {
+                    "type": "remap",
+                    "inputs": ["docker_host"],
+                    "source": REMAP_SOURCE,
+                },
+                "router": {
+                    "type": "route",
+                    "inputs": ["project_logs"],
+                    "route": route,
+                }
+            },
+            "sinks": sinks,
+        })
+    }
+
+    /// Regenerates the configuration file copied into the container
+    fn with_generated_config(mut self) -> Self {
+        let config = self.config().to_string().into_bytes();
+        self.config = vec![CopyToContainer::new(config, CONFIG_PATH)];
+        self
+    }
+}
+
+/// VRL program normalizing Docker log events into Logflare's shape
+const REMAP_SOURCE: &str = r#".project = "default"
+.event_message = del(.message)
+.appname = del(.container_name)
+del(.container_created_at)
+del(.container_id)
+del(.source_type)
+del(.stream)
+del(.label)
+del(.image)
+del(.host)"#;
+
+/// Returns a read-only bind mount of the Docker socket at the default location
+fn docker_socket_mount(host_path: String) -> Mount {
+    Mount::bind_mount(host_path, DOCKER_SOCKET).with_access_mode(AccessMode::ReadOnly)
+}
+
+impl Default for Vector {
+    fn default() -> Self {
+        let mut env_vars = BTreeMap::new();
+
+        // Placeholder so the configuration's ${LOGFLARE_API_KEY} always resolves
+        env_vars.insert("LOGFLARE_API_KEY".to_string(), String::new());
+
+        Self {
+            env_vars,
+            name: image_name("VECTOR", NAME),
+            tag: image_tag("VECTOR", TAG),
+            analytics_url: DEFAULT_ANALYTICS_URL.to_string(),
+            routes: BTreeMap::new(),
+            mounts: vec![docker_socket_mount(DOCKER_SOCKET.to_string())],
+            config: Vec::new(),
+            ready_check: ReadyCheck::http("/health"),
+        }
+        .with_generated_config()
+    }
+}
+
+impl Image for Vector {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    fn ready_conditions(&self) -> Vec<WaitFor> {
+        vec![self.ready_check.wait_for(
+            VECTOR_API_PORT,
+            WaitFor::message_on_stderr("Vector has started"),
+        )]
+    }
+
+    fn expose_ports(&self) -> &[ContainerPort] {
+        &[ContainerPort::Tcp(VECTOR_API_PORT)]
+    }
+
+    fn env_vars(
+        &self,
+    ) -> impl IntoIterator<Item = (impl Into<Cow<'_, str>>, impl Into<Cow<'_, str>>)> {
+        &self.env_vars
+    }
+
+    fn mounts(&self) -> impl IntoIterator<Item = &Mount> {
+        &self.mounts
+    }
+
+    fn copy_to_sources(&self) -> impl IntoIterator<Item = &CopyToContainer> {
+        &self.config
+    }
+
+    fn cmd(&self) -> impl IntoIterator<Item = impl Into<Cow<'_, str>>> {
+        ["--config", CONFIG_PATH]
+    }
+
+    #[allow(unused_variables)]
+    fn exec_after_start(
+        &self,
+        cs: ContainerState,
+    ) -> Result<Vec<ExecCommand>, TestcontainersError> {
+        Ok(vec![])
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "vector")]
+mod tests {
+    use super::*;
+    use testcontainers_modules::testcontainers::core::MountType;
+
+    #[test]
+    fn test_default_configuration() {
+        let vector = Vector::default();
+
+        assert_eq!(vector.analytics_url, "http://analytics:4000");
+        assert!(vector.routes.is_empty());
+        assert_eq!(
+            vector.env_vars.get("LOGFLARE_API_KEY"),
+            Some(&String::new())
+        );
+        assert_eq!(vector.config.len(), 1);
+    }
+
+    #[test]
+    fn test_name_returns_correct_image() {
+        let vector = Vector::default();
+        assert_eq!(vector.name(), "timberio/vector");
+    }
+
+    #[test]
+    fn test_tag_returns_correct_version() {
+        let vector = Vector::default();
+        assert_eq!(vector.tag(), TAG);
+    }
+
+    #[test]
+    fn test_default_tag_matches_tested_version() {
+        assert_eq!(Vector::default().tag(), SupabaseVersion::tested().vector());
+    }
+
+    #[test]
+    fn test_with_version_sets_tag() {
+        let version = SupabaseVersion::tested().with_vector("0.30.0-alpine");
+        assert_eq!(
+            Vector::default().with_version(&version).tag(),
+            "0.30.0-alpine"
+        );
+    }
+
+    #[test]
+    fn test_with_image_name() {
+        let vector = Vector::default().with_image_name("ghcr.io/acme/vector");
+        assert_eq!(vector.name(), "ghcr.io/acme/vector");
+    }
+
+    #[test]
+    fn test_with_digest_pins_tag() {
+        let vector = Vector::default().with_tag("v1").with_digest("sha256:abc");
+        assert_eq!(vector.tag(), "v1@sha256:abc");
+        assert_eq!(vector.with_tag("v2").tag(), "v2");
+    }
+
+    #[test]
+    fn test_vector_api_port_constant() {
+        assert_eq!(VECTOR_API_PORT, 9001);
+    }
+
+    #[test]
+    fn test_log_source_names() {
+        assert_eq!(LogSource::Auth.source_name(), "gotrue.logs.prod");
+        assert_eq!(LogSource::Rest.source_name(), "postgREST.logs.prod");
+        assert_eq!(LogSource::Realtime.source_name(), "realtime.logs.prod");
+        assert_eq!(LogSource::Storage.source_name(), "storage.logs.prod.2");
+        assert_eq!(LogSource::Functions.source_name(), "deno-relay-logs");
+        assert_eq!(LogSource::Db.source_name(), "postgres.logs");
+        assert_eq!(LogSource::Kong.to_string(), "cloudflare.logs.prod");
+        assert_eq!(LogSource::ALL.len(), 7);
+    }
+
+    #[test]
+    fn test_with_api_key() {
+        let vector = Vector::default().with_api_key("public-token");
+        assert_eq!(
+            vector.env_vars.get("LOGFLARE_API_KEY"),
+            Some(&"public-token".to_string())
+        );
+    }
+
+    #[test]
+    fn test_with_container_routes_to_default_source() {
+        let vector = Vector::default()
+            .with_container("auth", LogSource::Auth)
+            .with_container("rest", LogSource::Rest);
+
+        assert_eq!(
+            vector.routes.get("auth"),
+            Some(&"gotrue.logs.prod".to_string())
+        );
+        assert_eq!(
+            vector.routes.get("rest"),
+            Some(&"postgREST.logs.prod".to_string())
+        );
+    }
+
+    #[test]
+    fn test_config_routes_containers_to_sinks() {
+        let config = Vector::default()
+            .with_analytics_url("http://logflare:4000/")
+            .with_container("auth", LogSource::Auth)
+            .with_route("worker", "worker.logs")
+            .config();
+
+        assert_eq!(
+            config["sources"]["docker_host"]["include_containers"],
+            json!(["auth", "worker"])
+        );
+        assert_eq!(
+            config["transforms"]["router"]["route"],
+            json!({
+                "route_0": ".appname == \"auth\"",
+                "route_1": ".appname == \"worker\"",
+            })
+        );
+        assert_eq!(
+            config["sinks"]["logflare_0"]["inputs"],
+            json!(["router.route_0"])
+        );
+        assert_eq!(
+            config["sinks"]["logflare_0"]["uri"],
+            "http://logflare:4000/api/logs?source_name=gotrue.logs.prod&api_key=${LOGFLARE_API_KEY}"
+        );
+        assert_eq!(
+            config["sinks"]["logflare_1"]["uri"],
+            "http://logflare:4000/api/logs?source_name=worker.logs&api_key=${LOGFLARE_API_KEY}"
+        );
+    }
+
+    #[test]
+    fn test_config_remaps_message_fields() {
+        let config = Vector::default().config();
+        let source = config["transforms"]["project_logs"]["source"]
+            .as_str()
+            .unwrap();
+
+        assert!(source.contains(".event_message = del(.message)"));
+        assert!(source.contains(".appname = del(.container_name)"));
+    }
+
+    #[test]
+    fn test_with_docker_socket() {
+        let vector = Vector::default().with_docker_socket("/run/user/1000/docker.sock");
+        let mounts: Vec<&Mount> = vector.mounts().into_iter().collect();
+
+        assert_eq!(mounts.len(), 1);
+        assert_eq!(mounts[0].mount_type(), MountType::Bind);
+        assert_eq!(mounts[0].source(), Some("/run/user/1000/docker.sock"));
+        assert_eq!(mounts[0].target(), Some("/var/run/docker.sock"));
+        assert_eq!(mounts[0].access_mode(), AccessMode::ReadOnly);
+    }
+
+    #[test]
+    fn test_with_log_level() {
+        let vector = Vector::default().with_log_level("debug");
+        assert_eq!(
+            vector.env_vars.get("VECTOR_LOG"),
+            Some(&"debug".to_string())
+        );
+    }
+
+    #[test]
+    fn test_with_tag_overrides_default() {
+        let vector = Vector::default().with_tag("0.34.0-alpine");
+        assert_eq!(vector.tag(), "0.34.0-alpine");
+    }
+
+    #[test]
+    fn test_with_env_adds_custom_variable() {
+        let vector = Vector::default().with_env("CUSTOM_VAR", "custom_value");
+        assert_eq!(
+            vector.env_vars.get("CUSTOM_VAR"),
+            Some(&"custom_value".to_string())
+        );
+    }
+
+    #[test]
+    fn test_new_with_env() {
+        let mut envs = BTreeMap::new();
+        envs.insert("LOGFLARE_API_KEY", "from-env");
+
+        let vector = Vector::new_with_env(envs);
+        assert_eq!(
+            vector.env_vars.get("LOGFLARE_API_KEY"),
+            Some(&"from-env".to_string())
+        );
+    }
+
+    #[test]
+    fn test_cmd_points_at_generated_config() {
+        let vector = Vector::default();
+        let cmd: Vec<String> = vector
+            .cmd()
+            .into_iter()
+            .map(|arg| arg.into().into_owned())
+            .collect();
+        assert_eq!(cmd, vec!["--config", "/etc/vector/vector.json"]);
+    }
+
+    #[test]
+    fn test_expose_ports() {
+        let vector = Vector::default();
+        assert_eq!(vector.expose_ports(), &[ContainerPort::Tcp(9001)]);
+    }
+
+    #[test]
+    fn test_ready_conditions() {
+        let vector = Vector::default();
+        let conditions = vector.ready_conditions();
+        assert_eq!(conditions.len(), 1);
+        assert!(matches!(conditions[0], WaitFor::Http(_)));
+    }
+
+    #[test]
+    fn test_with_ready_check_log() {
+        let vector = Vector::default().with_ready_check(ReadyCheck::Log);
+        let conditions = vector.ready_conditions();
+        assert_eq!(conditions.len(), 1);
+        assert!(matches!(conditions[0], WaitFor::Log(_)));
+    }
+}
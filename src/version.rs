@@ -0,0 +1,561 @@
+/*! Named sets of image tags that work together.
+
+Each image module has its own default tag, and nothing stops a test from pairing an Auth
+tag with a PostgREST tag that was never run alongside it. [`SupabaseVersion`] names a
+whole set of tags, applies it to the images with their `with_version` builders (or to a
+whole `SupabaseStack` at once), and rejects tags the modules are known not to work with.
+
+# Catalog
+
+| Name | Auth | PostgREST | Storage | Realtime | Functions | Analytics | PostgreSQL | Vector |
+|------|------|-----------|---------|----------|-----------|-----------|------------|--------|
+| `tested` | v2.183.0 | v12.2.3 | v1.11.1 | v2.33.58 | v1.67.4 | 1.26.13 | 15.8.1.085 | 0.28.1-alpine |
+| `cli-2.22` | v2.174.0 | v12.2.12 | v1.22.17 | v2.34.47 | v1.67.4 | 1.14.2 | 17.4.1.016 | 0.28.1-alpine |
+
+`tested` is the set the integration tests run against and matches each image's default
+tag. `cli-2.22` holds the images `supabase start` runs with Supabase CLI v2.22, to test
+against what local development runs.
+
+The PostgreSQL column is the `supabase/postgres` tag GraphQL runs on. A `SupabaseStack`
+runs its database on the `postgres` image of its `LogicalReplication` preset and does
+not apply or check it.
+
+# Validation
+
+[`SupabaseVersion::validate`] rejects:
+
+- tags older than the oldest release their module works with, because the module relies
+  on settings or routes that older releases lack
+- pairs of tags that are known not to work together, from a table of
+  `(service, versions, service, versions, reason)` rules
+
+Tags that are not version numbers, such as `latest`, are not checked. A mixed set that
+passes is untested rather than known to work.
+
+# Example
+
+```rust,no_run
+use supabase_testcontainers_modules::{Auth, PostgREST, SupabaseVersion};
+
+fn main() -> anyhow::Result<()> {
+    let version = SupabaseVersion::named("tested")?
+        // Try a newer Auth against the rest of the set
+        .with_auth("v2.185.0");
+    version.validate()?;
+
+    let auth = Auth::default().with_version(&version);
+    let postgrest = PostgREST::default().with_version(&version);
+    Ok(())
+}
+```
+*/
+
+use anyhow::bail;
+
+use crate::consts;
+
+/// Name of the set matching the images' default tags
+const TESTED: &str = "tested";
+
+/// Oldest versions the modules work with, as `(service, major, minor, reason)`
+///
+/// The modules configure the services through settings and routes that older releases
+/// do not have.
+const MINIMUM_VERSIONS: &[(&str, u64, u64, &str)] = &[
+    ("Auth", 2, 0, "settings use GoTrue v2's GOTRUE_* names"),
+    (
+        "PostgREST",
+        11,
+        0,
+        "readiness is checked on the admin server's /ready route",
+    ),
+    ("Storage", 1, 0, "settings use storage-api v1's names"),
+    (
+        "Realtime",
+        2,
+        0,
+        "settings and the /api/ping route are Realtime v2's",
+    ),
+    (
+        "Functions",
+        1,
+        0,
+        "the main service layout is edge-runtime v1's",
+    ),
+    (
+        "Analytics",
+        1,
+        0,
+        "sources are created through Logflare v1's API",
+    ),
+    (
+        "PostgreSQL",
+        14,
+        0,
+        "Realtime and pg_graphql need PostgreSQL 14 or later",
+    ),
+];
+
+/// Versions from `(major, minor)` on, up to but excluding a second `(major, minor)`
+type VersionRange = ((u64, u64), (u64, u64));
+
+/// Versions from `major.minor` on
+const fn from(major: u64, minor: u64) -> VersionRange {
+    ((major, minor), (u64::MAX, 0))
+}
+
+/// Versions before `major.minor`
+const fn before(major: u64, minor: u64) -> VersionRange {
+    ((0, 0), (major, minor))
+}
+
+/// Pairs of versions that do not work together, as `(service, versions, service,
+/// versions, reason)`
+const INCOMPATIBLE: &[(&str, VersionRange, &str, VersionRange, &str)] = &[(
+    "PostgREST",
+    before(12, 2),
+    "PostgreSQL",
+    from(17, 0),
+    "PostgreSQL 17 is supported from PostgREST v12.2",
+)];
+
+/// A named set of image tags for the Supabase services
+///
+/// Start from [`SupabaseVersion::tested`] or [`SupabaseVersion::named`]; the `with_*`
+/// methods replace single tags, turning it into a mixed set that
+/// [`SupabaseVersion::validate`] checks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SupabaseVersion {
+    /// Catalog name, or the name it was derived from
+    name: String,
+    /// Whether tags were replaced after taking the set from the catalog
+    mixed: bool,
+    /// `supabase/gotrue` tag
+    auth: String,
+    /// `postgrest/postgrest` tag
+    postgrest: String,
+    /// `supabase/storage-api` tag
+    storage: String,
+    /// `supabase/realtime` tag
+    realtime: String,
+    /// `supabase/edge-runtime` tag
+    functions: String,
+    /// `supabase/logflare` tag
+    analytics: String,
+    /// `supabase/postgres` tag
+    postgres: String,
+    /// `timberio/vector` tag
+    vector: String,
+}
+
+impl SupabaseVersion {
+    /// Returns the set matching each image's default tag
+    pub fn tested() -> Self {
+        Self {
+            name: TESTED.to_string(),
+            mixed: false,
+            auth: consts::AUTH_TAG.to_string(),
+            postgrest: consts::POSTGREST_TAG.to_string(),
+            storage: consts::STORAGE_TAG.to_string(),
+            realtime: consts::REALTIME_TAG.to_string(),
+            functions: consts::FUNCTIONS_TAG.to_string(),
+            analytics: consts::ANALYTICS_TAG.to_string(),
+            postgres: consts::SUPABASE_POSTGRES_TAG.to_string(),
+            vector: consts::VECTOR_TAG.to_string(),
+        }
+    }
+
+    /// Returns the set Supabase CLI v2.22 runs `supabase start` with
+    pub fn cli_2_22() -> Self {
+        Self {
+            name: "cli-2.22".to_string(),
+            mixed: false,
+            auth: "v2.174.0".to_string(),
+            postgrest: "v12.2.12".to_string(),
+            storage: "v1.22.17".to_string(),
+            realtime: "v2.34.47".to_string(),
+            functions: "v1.67.4".to_string(),
+            analytics: "1.14.2".to_string(),
+            postgres: "17.4.1.016".to_string(),
+            vector: "0.28.1-alpine".to_string(),
+        }
+    }
+
+    /// Returns every set in the catalog
+    pub fn catalog() -> Vec<Self> {
+        vec![Self::tested(), Self::cli_2_22()]
+    }
+
+    /// Returns the catalog set called `name`
+    ///
+    /// # Errors
+    /// Returns an error listing the available names if there is no such set.
+    pub fn named(name: &str) -> anyhow::Result<Self> {
+        let catalog = Self::catalog();
+        match catalog.iter().find(|version| version.name == name) {
+            Some(version) => Ok(version.clone()),
+            None => bail!(
+                "unknown Supabase version '{}'; available: {}",
+                name,
+                catalog
+                    .iter()
+                    .map(|version| version.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// Returns the catalog name this set was taken from
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns whether tags were replaced after taking the set from the catalog
+    pub fn is_mixed(&self) -> bool {
+        self.mixed
+    }
+
+    /// Returns the Auth tag
+    pub fn auth(&self) -> &str {
+        &self.auth
+    }
+
+    /// Returns the PostgREST tag
+    pub fn postgrest(&self) -> &str {
+        &self.postgrest
+    }
+
+    /// Returns the Storage tag
+    pub fn storage(&self) -> &str {
+        &self.storage
+    }
+
+    /// Returns the Realtime tag
+    pub fn realtime(&self) -> &str {
+        &self.realtime
+    }
+
+    /// Returns the Functions (edge-runtime) tag
+    pub fn functions(&self) -> &str {
+        &self.functions
+    }
+
+    /// Returns the Analytics (Logflare) tag
+    pub fn analytics(&self) -> &str {
+        &self.analytics
+    }
+
+    /// Returns the `supabase/postgres` tag, used by `GraphQL`
+    pub fn postgres(&self) -> &str {
+        &self.postgres
+    }
+
+    /// Returns the Vector tag
+    pub fn vector(&self) -> &str {
+        &self.vector
+    }
+
+    /// Replaces the Auth tag
+    pub fn with_auth(mut self, tag: impl Into<String>) -> Self {
+        self.auth = tag.into();
+        self.mixed = true;
+        self
+    }
+
+    /// Replaces the PostgREST tag
+    pub fn with_postgrest(mut self, tag: impl Into<String>) -> Self {
+        self.postgrest = tag.into();
+        self.mixed = true;
+        self
+    }
+
+    /// Replaces the Storage tag
+    pub fn with_storage(mut self, tag: impl Into<String>) -> Self {
+        self.storage = tag.into();
+        self.mixed = true;
+        self
+    }
+
+    /// Replaces the Realtime tag
+    pub fn with_realtime(mut self, tag: impl Into<String>) -> Self {
+        self.realtime = tag.into();
+        self.mixed = true;
+        self
+    }
+
+    /// Replaces the Functions (edge-runtime) tag
+    pub fn with_functions(mut self, tag: impl Into<String>) -> Self {
+        self.functions = tag.into();
+        self.mixed = true;
+        self
+    }
+
+    /// Replaces the Analytics (Logflare) tag
+    pub fn with_analytics(mut self, tag: impl Into<String>) -> Self {
+        self.analytics = tag.into();
+        self.mixed = true;
+        self
+    }
+
+    /// Replaces the `supabase/postgres` tag
+    pub fn with_postgres(mut self, tag: impl Into<String>) -> Self {
+        self.postgres = tag.into();
+        self.mixed = true;
+        self
+    }
+
+    /// Replaces the Vector tag
+    pub fn with_vector(mut self, tag: impl Into<String>) -> Self {
+        self.vector = tag.into();
+        self.mixed = true;
+        self
+    }
+
+    /// Checks the tags against the versions the modules are known to work with
+    ///
+    /// Tags that are not version numbers, such as `latest`, are not checked.
+    ///
+    /// # Errors
+    /// Returns an error naming every tag older than its module supports and every pair
+    /// of tags known not to work together.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        self.validate_tags(&self.tags())
+    }
+
+    /// [`SupabaseVersion::validate`] without the PostgreSQL tag, which a
+    /// `SupabaseStack` does not apply
+    #[cfg_attr(not(feature = "stack"), allow(dead_code))]
+    pub(crate) fn validate_stack(&self) -> anyhow::Result<()> {
+        let tags: Vec<(&str, &str)> = self
+            .tags()
+            .into_iter()
+            .filter(|(service, _)| *service != "PostgreSQL")
+            .collect();
+        self.validate_tags(&tags)
+    }
+
+    /// Returns the checked tags by service
+    fn tags(&self) -> Vec<(&'static str, &str)> {
+        vec![
+            ("Auth", &self.auth),
+            ("PostgREST", &self.postgrest),
+            ("Storage", &self.storage),
+            ("Realtime", &self.realtime),
+            ("Functions", &self.functions),
+            ("Analytics", &self.analytics),
+            ("PostgreSQL", &self.postgres),
+        ]
+    }
+
+    /// Checks `tags` against [`MINIMUM_VERSIONS`] and [`INCOMPATIBLE`]
+    fn validate_tags(&self, tags: &[(&str, &str)]) -> anyhow::Result<()> {
+        let version = |service: &str| {
+            let &(_, tag) = tags.iter().find(|(name, _)| *name == service)?;
+            Some((tag, parse_version(tag)?))
+        };
+        let too_old = tags.iter().filter_map(|(service, tag)| {
+            let (major, minor) = parse_version(tag)?;
+            let &(_, min_major, min_minor, reason) =
+                MINIMUM_VERSIONS.iter().find(|(name, ..)| name == service)?;
+            ((major, minor) < (min_major, min_minor)).then(|| {
+                format!(
+                    "{} {} is older than {}.{} ({})",
+                    service, tag, min_major, min_minor, reason
+                )
+            })
+        });
+        let incompatible = INCOMPATIBLE
+            .iter()
+            .filter_map(|(a, a_range, b, b_range, reason)| {
+                let (a_tag, a_version) = version(a)?;
+                let (b_tag, b_version) = version(b)?;
+                (in_range(a_version, *a_range) && in_range(b_version, *b_range)).then(|| {
+                    format!(
+                        "{} {} does not work with {} {} ({})",
+                        a, a_tag, b, b_tag, reason
+                    )
+                })
+            });
+        let problems: Vec<String> = too_old.chain(incompatible).collect();
+
+        if !problems.is_empty() {
+            bail!(
+                "Supabase version '{}'{} is incompatible: {}",
+                self.name,
+                if self.mixed {
+                    " (with replaced tags)"
+                } else {
+                    ""
+                },
+                problems.join("; ")
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Default for SupabaseVersion {
+    fn default() -> Self {
+        Self::tested()
+    }
+}
+
+/// Returns whether `version` is in `range`
+fn in_range(version: (u64, u64), (start, end): VersionRange) -> bool {
+    start <= version && version < end
+}
+
+/// Parses the major and minor version of a tag like `v2.183.0` or `15.8.1.085`
+fn parse_version(tag: &str) -> Option<(u64, u64)> {
+    let mut parts = tag.strip_prefix('v').unwrap_or(tag).split(['.', '-']);
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts
+        .next()
+        .and_then(|minor| minor.parse().ok())
+        .unwrap_or(0);
+    Some((major, minor))
+}
+
+#[cfg(test)]
+#[cfg(feature = "const")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_is_tested() {
+        let version = SupabaseVersion::default();
+
+        assert_eq!(version, SupabaseVersion::tested());
+        assert_eq!(version.name(), "tested");
+        assert!(!version.is_mixed());
+    }
+
+    #[test]
+    fn test_catalog_sets_are_valid() {
+        for version in SupabaseVersion::catalog() {
+            version.validate().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_named_lookup() {
+        assert_eq!(
+            SupabaseVersion::named("tested").unwrap(),
+            SupabaseVersion::tested()
+        );
+
+        let error = SupabaseVersion::named("0.0.0").unwrap_err().to_string();
+        assert!(
+            error.contains("unknown Supabase version '0.0.0'"),
+            "{}",
+            error
+        );
+        assert!(error.contains("available: tested, cli-2.22"), "{}", error);
+        assert_eq!(
+            SupabaseVersion::named("cli-2.22").unwrap(),
+            SupabaseVersion::cli_2_22()
+        );
+    }
+
+    #[test]
+    fn test_with_methods_replace_tags() {
+        let version = SupabaseVersion::tested()
+            .with_auth("a")
+            .with_postgrest("b")
+            .with_storage("c")
+            .with_realtime("d")
+            .with_functions("e")
+            .with_analytics("f")
+            .with_postgres("g")
+            .with_vector("h");
+
+        assert!(version.is_mixed());
+        assert_eq!(
+            [
+                version.auth(),
+                version.postgrest(),
+                version.storage(),
+                version.realtime(),
+                version.functions(),
+                version.analytics(),
+                version.postgres(),
+                version.vector(),
+            ],
+            ["a", "b", "c", "d", "e", "f", "g", "h"]
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_old_tags() {
+        let error = SupabaseVersion::tested()
+            .with_postgrest("v10.2.0")
+            .with_postgres("13.3.0")
+            .validate()
+            .unwrap_err()
+            .to_string();
+
+        assert!(error.contains("(with replaced tags)"), "{}", error);
+        assert!(
+            error.contains("PostgREST v10.2.0 is older than 11.0"),
+            "{}",
+            error
+        );
+        assert!(
+            error.contains("PostgreSQL 13.3.0 is older than 14.0"),
+            "{}",
+            error
+        );
+        assert!(!error.contains("Auth"), "{}", error);
+    }
+
+    #[test]
+    fn test_validate_rejects_incompatible_pairs() {
+        let error = SupabaseVersion::cli_2_22()
+            .with_postgrest("v12.0.2")
+            .validate()
+            .unwrap_err()
+            .to_string();
+
+        assert!(
+            error.contains("PostgREST v12.0.2 does not work with PostgreSQL 17.4.1.016"),
+            "{}",
+            error
+        );
+        // The stack's database does not run the PostgreSQL tag
+        SupabaseVersion::cli_2_22()
+            .with_postgrest("v12.0.2")
+            .validate_stack()
+            .unwrap();
+        SupabaseVersion::tested()
+            .with_postgrest("v12.0.2")
+            .validate()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_in_range() {
+        assert!(in_range((12, 1), before(12, 2)));
+        assert!(!in_range((12, 2), before(12, 2)));
+        assert!(in_range((17, 0), from(17, 0)));
+        assert!(!in_range((15, 8), from(17, 0)));
+    }
+
+    #[test]
+    fn test_validate_skips_unversioned_tags() {
+        SupabaseVersion::tested()
+            .with_auth("latest")
+            .with_storage("main")
+            .validate()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("v2.183.0"), Some((2, 183)));
+        assert_eq!(parse_version("15.8.1.085"), Some((15, 8)));
+        assert_eq!(parse_version("0.28.1-alpine"), Some((0, 28)));
+        assert_eq!(parse_version("15-alpine"), Some((15, 0)));
+        assert_eq!(parse_version("latest"), None);
+    }
+}
@@ -1,17 +1,19 @@
 //! Integration tests for Supabase Analytics (Logflare) container setup patterns
 //!
-//! These tests verify the Analytics container configuration and startup.
+//! These tests verify the Analytics container configuration and startup, and use
+//! [`AnalyticsClient`] to create sources, ingest events and query them back.
 //!
-//! Note: Full analytics functionality (HTTP endpoints, log ingestion) requires
-//! successful database migrations. The Logflare container's HTTP server only
-//! starts after migrations complete successfully.
+//! Note: The Logflare container's HTTP server only starts after database migrations
+//! complete; the default ready check waits for `/health`, so no extra delay is needed.
 //!
 //! Run with: `cargo test --features analytics,const --test analytics_integration`
 
 use anyhow::Result;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
-use supabase_testcontainers_modules::{Analytics, ANALYTICS_PORT, LOCAL_HOST};
+use supabase_testcontainers_modules::{
+    Analytics, AnalyticsClient, QueryLanguage, ANALYTICS_PORT, ANALYTICS_QUERY_TIMEOUT, LOCAL_HOST,
+};
 use testcontainers::runners::AsyncRunner;
 use testcontainers::ImageExt;
 use testcontainers_modules::postgres::Postgres;
@@ -64,8 +66,8 @@ mod tests {
         #[allow(dead_code)]
         analytics: ContainerAsync<Analytics>,
         postgres_port: u16,
-        #[allow(dead_code)]
         analytics_port: u16,
+        client: AnalyticsClient,
     }
 
     /// Sets up PostgreSQL and Analytics for testing
@@ -110,14 +112,15 @@ mod tests {
             .await?;
         let analytics_port = analytics.get_host_port_ipv4(ANALYTICS_PORT).await?;
 
-        // Wait for Analytics container to initialize
-        tokio::time::sleep(Duration::from_secs(5)).await;
+        // Startup waits for /health, so migrations have completed here
+        let client = AnalyticsClient::from_container(&analytics).await?;
 
         Ok(AnalyticsContext {
             postgres,
             analytics,
             postgres_port,
             analytics_port,
+            client,
         })
     }
 
@@ -228,4 +231,77 @@ mod tests {
         // Test passes either way - we're verifying we can check
         Ok(())
     }
+
+    /// Test that a created source is listed
+    #[tokio::test]
+    async fn test_create_and_list_sources() -> Result<()> {
+        let ctx = setup_analytics().await?;
+
+        let created = ctx.client.create_source("test.sources").await?;
+        assert_eq!(created.name, "test.sources");
+        assert!(!created.token.is_empty());
+
+        let found = ctx.client.source("test.sources").await?;
+        assert_eq!(found, Some(created));
+
+        Ok(())
+    }
+
+    /// Test that single and batched events become queryable through an endpoint
+    #[tokio::test]
+    async fn test_ingest_and_query_events() -> Result<()> {
+        let ctx = setup_analytics().await?;
+
+        ctx.client.create_source("test.events").await?;
+        ctx.client
+            .ingest(
+                "test.events",
+                serde_json::json!({ "event_message": "single event", "level": "info" }),
+            )
+            .await?;
+        ctx.client
+            .ingest_batch(
+                "test.events",
+                vec![
+                    serde_json::json!({ "event_message": "batched event 1" }),
+                    serde_json::json!({ "event_message": "batched event 2" }),
+                ],
+            )
+            .await?;
+
+        ctx.client
+            .create_endpoint(
+                "test.events.all",
+                "select event_message from `test.events`",
+                QueryLanguage::BqSql,
+            )
+            .await?;
+        let rows = ctx
+            .client
+            .wait_for_results("test.events.all", &[], 3, ANALYTICS_QUERY_TIMEOUT)
+            .await?;
+
+        let mut messages: Vec<&str> = rows
+            .iter()
+            .filter_map(|row| row["event_message"].as_str())
+            .collect();
+        messages.sort();
+        assert_eq!(
+            messages,
+            vec!["batched event 1", "batched event 2", "single event"]
+        );
+
+        Ok(())
+    }
+
+    /// Test that an unknown endpoint is reported as an error
+    #[tokio::test]
+    async fn test_query_unknown_endpoint_fails() -> Result<()> {
+        let ctx = setup_analytics().await?;
+
+        let result = ctx.client.query_endpoint("does.not.exist", &[]).await;
+        assert!(result.is_err(), "Expected error for unknown endpoint");
+
+        Ok(())
+    }
 }
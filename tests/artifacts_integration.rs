@@ -0,0 +1,130 @@
+//! Integration tests for container logs and database dumps of failed tests
+//!
+//! These tests require Docker to be running and will start real containers.
+//! Run with: `cargo test --features stack --test artifacts_integration`
+
+use std::panic::AssertUnwindSafe;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Result;
+use supabase_testcontainers_modules::{Auth, PostgREST, StackService, SupabaseStack};
+
+/// Time for the collectors to receive the latest lines
+const COLLECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Returns a fresh artifacts directory for `test`
+fn artifacts_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "supabase-artifacts-{}-{}",
+        test,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that an explicit call writes every service's logs and the dump
+    #[tokio::test]
+    async fn test_write_artifacts() -> Result<()> {
+        let dir = artifacts_dir("explicit");
+        let mut stack = SupabaseStack::new()
+            .with_auth(Auth::default())
+            .with_postgrest(PostgREST::default())
+            .with_setup_sql("CREATE TABLE public.todos (id serial PRIMARY KEY);")
+            .with_artifacts_dir(&dir)
+            .with_database_dump(true)
+            .start()
+            .await?;
+        stack.restart_service(StackService::Rest).await?;
+        tokio::time::sleep(COLLECT_DELAY).await;
+
+        // Docker's logs of the restarted container hold both runs, each line once
+        let postgrest = stack.postgrest().unwrap();
+        let docker_logs = [
+            ("stdout", postgrest.stdout_to_vec().await?),
+            ("stderr", postgrest.stderr_to_vec().await?),
+        ];
+        let written = stack.write_artifacts("tests::explicit").await?;
+        assert_eq!(written, dir.join("tests__explicit"));
+
+        let log = std::fs::read_to_string(written.join("stack.log"))?;
+        for prefix in ["[db stderr]", "[auth ", "[rest "] {
+            assert!(log.contains(prefix), "no {} lines in:\n{}", prefix, log);
+        }
+        // Lines replayed when following the restarted container are skipped
+        for (stream, docker_log) in docker_logs {
+            let marker = format!("[rest {}] ", stream);
+            let collected: Vec<&str> = log
+                .lines()
+                .filter_map(|line| line.split_once(&marker).map(|(_, text)| text))
+                .collect();
+            let docker_log = String::from_utf8_lossy(&docker_log);
+            assert_eq!(collected, docker_log.lines().collect::<Vec<_>>());
+        }
+
+        let dump = std::fs::read_to_string(written.join("database.sql"))?;
+        assert!(dump.contains("CREATE TABLE public.todos"));
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    /// Test that the guard writes the logs only when the test panics
+    #[tokio::test]
+    async fn test_guard_writes_on_panic() -> Result<()> {
+        let dir = artifacts_dir("guard");
+        let stack = SupabaseStack::new()
+            .with_artifacts_dir(&dir)
+            .start()
+            .await?;
+        tokio::time::sleep(COLLECT_DELAY).await;
+
+        drop(stack.artifacts_guard("passed"));
+        assert!(!dir.join("passed").exists());
+
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            let _guard = stack.artifacts_guard("failed");
+            panic!("assertion failed");
+        }));
+        assert!(result.is_err());
+
+        let log = std::fs::read_to_string(dir.join("failed").join("stack.log"))?;
+        assert!(log.contains("[db stderr]"));
+        // The dump is off by default
+        assert!(!dir.join("failed").join("database.sql").exists());
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    /// Test that the guard takes the dump while the test's runtime is blocked
+    #[tokio::test]
+    async fn test_guard_writes_dump_on_panic() -> Result<()> {
+        let dir = artifacts_dir("guard-dump");
+        let stack = SupabaseStack::new()
+            .with_setup_sql("CREATE TABLE public.todos (id serial PRIMARY KEY);")
+            .with_artifacts_dir(&dir)
+            .with_database_dump(true)
+            .start()
+            .await?;
+        // Leaves a pooled connection to Docker behind on this runtime
+        stack.write_artifacts("warm").await?;
+
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            let _guard = stack.artifacts_guard("failed");
+            panic!("assertion failed");
+        }));
+        assert!(result.is_err());
+
+        let dump = std::fs::read_to_string(dir.join("failed").join("database.sql"))?;
+        assert!(dump.contains("CREATE TABLE public.todos"));
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+//! Integration tests for the synchronous helpers and stack
+//!
+//! These tests are plain `#[test]` functions: containers start with `SyncRunner` or
+//! `SupabaseStack::start_blocking` and no async runtime is involved.
+//!
+//! Run with: `cargo test --features blocking,stack --test blocking_integration`
+
+use anyhow::Result;
+use supabase_testcontainers_modules::{
+    Auth, LogicalReplication, MigrationRunner, PostgREST, SupabaseStack, LOCAL_HOST,
+    REALTIME_PUBLICATION,
+};
+use testcontainers::runners::SyncRunner;
+use testcontainers::ImageExt;
+use testcontainers_modules::postgres::Postgres;
+use tokio_postgres::NoTls;
+
+/// PostgreSQL port constant
+const POSTGRES_PORT: u16 = 5432;
+
+/// Returns the first column of the first row `sql` returns
+///
+/// Uses a throwaway runtime; the crate's blocking API has no query helper.
+fn query_string(db_url: &str, sql: &str) -> Result<String> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(async {
+            let (client, connection) = tokio_postgres::connect(db_url, NoTls).await?;
+            tokio::spawn(connection);
+            Ok(client.query_one(sql, &[]).await?.get(0))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that Auth's schema bootstrap and container work with `SyncRunner`
+    #[test]
+    fn test_sync_runner_with_auth() -> Result<()> {
+        let network = format!("supabase-blocking-{}", std::process::id());
+        let postgres_name = format!("postgres-blocking-{}", std::process::id());
+        let postgres = Postgres::default()
+            .with_tag("15-alpine")
+            .with_network(&network)
+            .with_container_name(&postgres_name)
+            .start()?;
+        let local_db_url = format!(
+            "postgres://postgres:postgres@{}:{}/postgres",
+            LOCAL_HOST,
+            postgres.get_host_port_ipv4(POSTGRES_PORT)?
+        );
+
+        let _auth = Auth::default()
+            .with_db_url(format!(
+                "postgres://supabase_auth_admin:testpassword@{}:{}/postgres",
+                postgres_name, POSTGRES_PORT
+            ))
+            .init_db_schema_blocking(&local_db_url, "testpassword")?
+            .with_network(&network)
+            .start()?;
+
+        // Auth ran its migrations into the bootstrapped schema
+        assert_eq!(
+            query_string(&local_db_url, "SELECT to_regclass('auth.users')::text")?,
+            "auth.users"
+        );
+
+        Ok(())
+    }
+
+    /// Test that the blocking stack migrates, resets and validates replication
+    #[test]
+    fn test_blocking_stack() -> Result<()> {
+        let stack = SupabaseStack::new()
+            .with_postgrest(PostgREST::default())
+            .start_blocking()?;
+        assert!(stack.rest_url().is_some());
+
+        let runner = MigrationRunner::new().with_sql(
+            "20240101000000",
+            "create_todos",
+            "CREATE TABLE public.todos (id serial PRIMARY KEY);",
+        );
+        assert_eq!(stack.migrate(&runner)?, ["20240101000000"]);
+        assert!(runner.apply_blocking(stack.db_url())?.is_empty());
+
+        LogicalReplication::validate_blocking(stack.db_url(), REALTIME_PUBLICATION)?;
+
+        // The snapshot was taken before the migration
+        stack.reset()?;
+        assert_eq!(
+            query_string(
+                stack.db_url(),
+                "SELECT coalesce(to_regclass('public.todos')::text, 'missing')"
+            )?,
+            "missing"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+//! Integration tests for the `supabase-tc` binary
+//!
+//! These tests require Docker to be running and will start real containers.
+//! Run with: `cargo test --features cli --test cli_integration`
+
+use std::process::{Command, Output};
+
+use anyhow::{ensure, Result};
+use serde_json::Value;
+
+/// Runs `supabase-tc` with `args`
+fn supabase_tc(args: &[&str]) -> Result<Output> {
+    Ok(Command::new(env!("CARGO_BIN_EXE_supabase-tc"))
+        .args(args)
+        .output()?)
+}
+
+/// Runs `supabase-tc` with `args` and returns its stdout, failing if it fails
+fn stdout(args: &[&str]) -> Result<String> {
+    let output = supabase_tc(args)?;
+    ensure!(
+        output.status.success(),
+        "supabase-tc {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that a detached stack prints its environment and is removed by `down`
+    #[tokio::test]
+    async fn test_detached_stack() -> Result<()> {
+        let name = format!("supabase-tc-test-{}", std::process::id());
+        let env: Value = serde_json::from_str(&stdout(&[
+            "up",
+            "--detach",
+            "--name",
+            &name,
+            "--services",
+            "rest",
+            "--format",
+            "json",
+        ])?)?;
+        let rest_url = env["rest_url"].as_str().unwrap();
+        assert!(env["auth_url"].is_null());
+        assert!(env["db_url"].as_str().unwrap().starts_with("postgres://"));
+
+        // The printed keys work against the stack
+        let response = reqwest::Client::new()
+            .get(format!("{}/", rest_url))
+            .bearer_auth(env["anon_key"].as_str().unwrap())
+            .send()
+            .await?;
+        assert!(response.status().is_success());
+
+        let dotenv = stdout(&["env", "--name", &name, "--prefix", "VITE_"])?;
+        assert!(dotenv.contains(&format!("VITE_REST_URL={}\n", rest_url)));
+        let status = stdout(&["status", "--name", &name])?;
+        assert!(status
+            .lines()
+            .any(|line| line.starts_with("rest") && line.contains("running")));
+
+        // A second detached `up` reuses the containers
+        let again: Value = serde_json::from_str(&stdout(&[
+            "up",
+            "--detach",
+            "--name",
+            &name,
+            "--services",
+            "rest",
+            "--format",
+            "json",
+        ])?)?;
+        assert_eq!(again, env);
+
+        stdout(&["down", "--name", &name])?;
+        assert!(!supabase_tc(&["status", "--name", &name])?.status.success());
+        assert!(reqwest::get(format!("{}/", rest_url)).await.is_err());
+
+        Ok(())
+    }
+
+    /// Test that usage errors exit with status 2 and print the usage
+    #[test]
+    fn test_usage_error() -> Result<()> {
+        let output = supabase_tc(&["up", "--services", "realtime"])?;
+
+        assert_eq!(output.status.code(), Some(2));
+        let stderr = String::from_utf8(output.stderr)?;
+        assert!(stderr.starts_with("error: unknown service 'realtime'"));
+        assert!(stderr.contains("Usage: supabase-tc <command> [options]"));
+
+        Ok(())
+    }
+}
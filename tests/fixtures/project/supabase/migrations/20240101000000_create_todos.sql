@@ -0,0 +1,4 @@
+CREATE TABLE public.todos (
+    id serial PRIMARY KEY,
+    task text NOT NULL
+);
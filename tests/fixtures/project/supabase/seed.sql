@@ -0,0 +1 @@
+INSERT INTO public.todos (task) VALUES ('first'), ('second'), ('third');
@@ -0,0 +1,168 @@
+//! Integration tests for seeding users, objects and rows
+//!
+//! These tests start a stack with Auth, PostgREST and Storage and seed it with
+//! `Fixtures`.
+//!
+//! Run with: `cargo test --features stack --test fixtures_integration`
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use supabase_testcontainers_modules::{
+    Auth, Fixtures, IdentityFixture, ObjectFixture, PostgREST, RunningStack, Storage,
+    SupabaseStack, UserFixture,
+};
+use tokio_postgres::NoTls;
+
+/// Table with a row-level security policy on the owner
+const SETUP_SQL: &str = "
+    CREATE TABLE public.todos (
+        id serial PRIMARY KEY,
+        owner uuid NOT NULL,
+        task text NOT NULL,
+        done boolean NOT NULL DEFAULT false
+    );
+    ALTER TABLE public.todos ENABLE ROW LEVEL SECURITY;
+    CREATE POLICY own_todos ON public.todos FOR SELECT TO authenticated
+        USING (owner = (current_setting('request.jwt.claims', true)::jsonb ->> 'sub')::uuid);
+";
+
+/// Fixed ID of the seeded user
+const ALICE_ID: &str = "6f1d3c1e-2a4b-4c8d-9e0f-1a2b3c4d5e6f";
+
+/// Runs `sql` against `db_url` and returns the first column of the first row
+async fn query_string(db_url: &str, sql: &str) -> Result<String> {
+    let (client, connection) = tokio_postgres::connect(db_url, NoTls).await?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("connection error: {}", e);
+        }
+    });
+    Ok(client.query_one(sql, &[]).await?.get(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Starts the full stack with the todo table
+    async fn setup_stack() -> Result<RunningStack> {
+        SupabaseStack::new()
+            .with_auth(Auth::default())
+            .with_postgrest(PostgREST::default())
+            .with_storage(Storage::default())
+            .with_setup_sql(SETUP_SQL)
+            .start()
+            .await
+    }
+
+    /// Test that seeded users can sign in and see their own rows
+    #[tokio::test]
+    async fn test_users_and_rows() -> Result<()> {
+        let stack = setup_stack().await?;
+
+        let seeded = Fixtures::new()
+            .with_user(
+                UserFixture::email("alice@example.com")
+                    .with_id(ALICE_ID)
+                    .with_password("password123")
+                    .with_app_metadata(json!({ "role": "admin" }))
+                    .with_user_metadata(json!({ "name": "Alice" }))
+                    .with_identity(IdentityFixture::new("github", "4242")),
+            )
+            .with_rows(
+                "public.todos",
+                [
+                    json!({ "owner": ALICE_ID, "task": "mine" }),
+                    json!({ "owner": "00000000-0000-0000-0000-000000000000", "task": "other" }),
+                ],
+            )
+            .apply(&stack)
+            .await?;
+
+        let alice = seeded.user("alice@example.com").unwrap();
+        assert_eq!(alice.id, ALICE_ID);
+        assert_eq!(alice.user["user_metadata"]["name"], "Alice");
+        assert_eq!(alice.user["app_metadata"]["role"], "admin");
+        let token = alice.access_token.clone().expect("signed in");
+
+        // Generated and default columns are returned
+        let rows = seeded.rows("public.todos");
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0]["id"].is_number());
+        assert_eq!(rows[0]["done"], json!(false));
+
+        // RLS sees Alice's session
+        let visible: Vec<Value> = reqwest::Client::new()
+            .get(format!("{}/todos", stack.rest_url().unwrap()))
+            .bearer_auth(token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0]["task"], "mine");
+
+        let provider = query_string(
+            stack.db_url(),
+            &format!(
+                "SELECT string_agg(provider, ',' ORDER BY provider) FROM auth.identities WHERE user_id = '{}'",
+                ALICE_ID
+            ),
+        )
+        .await?;
+        assert_eq!(provider, "email,github");
+
+        Ok(())
+    }
+
+    /// Test that objects are uploaded into buckets created on demand
+    #[tokio::test]
+    async fn test_objects() -> Result<()> {
+        let stack = setup_stack().await?;
+        let file = std::env::temp_dir().join(format!("fixture-{}.txt", std::process::id()));
+        std::fs::write(&file, "from a file")?;
+
+        let seeded = Fixtures::new()
+            .with_object(
+                ObjectFixture::bytes("docs", "notes/hello.txt", "hello")
+                    .with_content_type("text/plain"),
+            )
+            .with_object(ObjectFixture::file("docs", "notes/file.txt", &file))
+            .apply(&stack)
+            .await?;
+        std::fs::remove_file(&file)?;
+        assert_eq!(seeded.objects.len(), 2);
+
+        let body = reqwest::Client::new()
+            .get(format!(
+                "{}/object/docs/notes/file.txt",
+                stack.storage_url().unwrap()
+            ))
+            .bearer_auth(stack.service_role_key())
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        assert_eq!(body, "from a file");
+
+        Ok(())
+    }
+
+    /// Test that users need Auth in the stack
+    #[tokio::test]
+    async fn test_users_without_auth() -> Result<()> {
+        let stack = SupabaseStack::new().start().await?;
+
+        let error = Fixtures::new()
+            .with_user(UserFixture::email("bob@example.com"))
+            .apply(&stack)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "users were declared but the stack has no Auth");
+
+        Ok(())
+    }
+}
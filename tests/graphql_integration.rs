@@ -1,16 +1,17 @@
 //! Integration tests for GraphQL (pg_graphql) container setup patterns
 //!
-//! These tests verify the GraphQL + PostgREST integration pattern using standard PostgreSQL.
-//! Note: Full pg_graphql functionality requires the supabase/postgres image which has
-//! known compatibility issues with testcontainers. These tests verify the setup patterns
-//! work correctly with standard PostgreSQL.
+//! These tests verify the GraphQL + PostgREST integration pattern using standard PostgreSQL,
+//! and send real GraphQL queries through the `graphql_public.graphql` endpoint served by
+//! [`GraphQL::start_with_endpoint`] on the supabase/postgres image.
 //!
 //! Run with: `cargo test --features graphql,postgrest,const --test graphql_integration`
 
 use anyhow::Result;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
-use supabase_testcontainers_modules::{PostgREST, LOCAL_HOST, POSTGREST_PORT};
+use supabase_testcontainers_modules::{
+    GraphQL, GraphQLDirective, GraphQLEndpoint, PostgREST, LOCAL_HOST, POSTGREST_PORT,
+};
 use testcontainers::runners::AsyncRunner;
 use testcontainers::ImageExt;
 use testcontainers_modules::postgres::Postgres;
@@ -93,7 +94,7 @@ mod tests {
         // Use api as the primary schema so /todos maps to api.todos
         let postgrest = PostgREST::default()
             .with_postgres_connection(&postgrest_db_url)
-            .with_db_schemas("api")
+            .with_schemas(["api"])
             .with_db_anon_role("anon")
             .with_startup_timeout(Duration::from_secs(60))
             .with_network(&network_name)
@@ -101,9 +102,6 @@ mod tests {
             .await?;
         let postgrest_port = postgrest.get_host_port_ipv4(POSTGREST_PORT).await?;
 
-        // Wait for PostgREST to connect to database
-        tokio::time::sleep(Duration::from_secs(2)).await;
-
         Ok(GraphQLPatternContext {
             postgres,
             postgrest,
@@ -328,4 +326,210 @@ mod tests {
 
         Ok(())
     }
+
+    /// Starts supabase/postgres with its GraphQL endpoint and a `todos` table
+    async fn setup_graphql_endpoint() -> Result<GraphQLEndpoint> {
+        let network_name = format!("{}-{}", TEST_NETWORK, unique_test_id());
+        let endpoint = GraphQL::default()
+            .start_with_endpoint(&network_name)
+            .await?;
+
+        let (client, connection) = tokio_postgres::connect(endpoint.db_url(), NoTls).await?;
+
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+
+        client
+            .batch_execute(
+                r#"
+            CREATE TABLE public.todos (
+                id SERIAL PRIMARY KEY,
+                task TEXT NOT NULL,
+                done BOOLEAN NOT NULL DEFAULT false
+            );
+
+            INSERT INTO public.todos (task, done) VALUES
+                ('Learn GraphQL', false),
+                ('Write tests', true);
+
+            GRANT SELECT, INSERT ON public.todos TO anon;
+            GRANT USAGE ON SEQUENCE public.todos_id_seq TO anon;
+            "#,
+            )
+            .await?;
+
+        Ok(endpoint)
+    }
+
+    /// Test that a GraphQL query is resolved over HTTP
+    #[tokio::test]
+    async fn test_graphql_query_over_http() -> Result<()> {
+        let endpoint = setup_graphql_endpoint().await?;
+
+        let data = endpoint
+            .client()
+            .query("{ todosCollection(orderBy: [{ id: AscNullsLast }]) { edges { node { task done } } } }")
+            .await?
+            .into_result()?;
+
+        let edges = data["todosCollection"]["edges"]
+            .as_array()
+            .expect("Expected edges array");
+        assert_eq!(edges.len(), 2);
+        assert_eq!(edges[0]["node"]["task"], "Learn GraphQL");
+        assert_eq!(edges[1]["node"]["done"], true);
+
+        Ok(())
+    }
+
+    /// Test that a mutation with variables inserts a row
+    #[tokio::test]
+    async fn test_graphql_mutation_with_variables() -> Result<()> {
+        let endpoint = setup_graphql_endpoint().await?;
+
+        let data = endpoint
+            .client()
+            .query_with_variables(
+                "mutation AddTodo($task: String!) { insertIntoTodosCollection(objects: [{ task: $task }]) { affectedCount records { task done } } }",
+                serde_json::json!({ "task": "Ship GraphQL" }),
+            )
+            .await?
+            .into_result()?;
+
+        let inserted = &data["insertIntoTodosCollection"];
+        assert_eq!(inserted["affectedCount"], 1);
+        assert_eq!(inserted["records"][0]["task"], "Ship GraphQL");
+        assert_eq!(inserted["records"][0]["done"], false);
+
+        Ok(())
+    }
+
+    /// Test that invalid queries are reported in `errors`
+    #[tokio::test]
+    async fn test_graphql_errors_are_returned() -> Result<()> {
+        let endpoint = setup_graphql_endpoint().await?;
+
+        let response = endpoint
+            .client()
+            .query("{ missingCollection { totalCount } }")
+            .await?;
+
+        assert!(!response.is_ok(), "Expected GraphQL errors");
+        assert!(!response.errors[0].message.is_empty());
+
+        Ok(())
+    }
+
+    /// Test that the image's own wrapper is kept when the endpoint is initialized again
+    #[tokio::test]
+    async fn test_init_http_endpoint_keeps_image_wrapper() -> Result<()> {
+        let endpoint = setup_graphql_endpoint().await?;
+        let (client, connection) = tokio_postgres::connect(endpoint.db_url(), NoTls).await?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+        let owner_sql = "SELECT pg_get_userbyid(proowner)::text FROM pg_proc \
+             WHERE oid = 'graphql_public.graphql(text,text,jsonb,jsonb)'::regprocedure";
+        let owner: String = client.query_one(owner_sql, &[]).await?.get(0);
+        assert_eq!(owner, "supabase_admin");
+
+        GraphQL::init_http_endpoint(endpoint.db_url()).await?;
+
+        let owner_after: String = client.query_one(owner_sql, &[]).await?.get(0);
+        assert_eq!(owner_after, owner);
+        let data = endpoint
+            .client()
+            .query("{ todosCollection { edges { node { task } } } }")
+            .await?
+            .into_result()?;
+        assert_eq!(
+            data["todosCollection"]["edges"].as_array().unwrap().len(),
+            2
+        );
+
+        Ok(())
+    }
+
+    /// Test that schema and table directives change the reflected schema
+    #[tokio::test]
+    async fn test_directives_change_introspected_schema() -> Result<()> {
+        let endpoint = setup_graphql_endpoint().await?;
+        let db_url = endpoint.db_url();
+
+        let sdl = GraphQL::schema_sdl(db_url).await?;
+        assert!(sdl.contains("todosCollection"), "SDL:\n{}", sdl);
+        assert!(!sdl.contains("totalCount"), "SDL:\n{}", sdl);
+
+        GraphQL::apply_schema_directive(
+            db_url,
+            "public",
+            &GraphQLDirective::new().with_inflect_names(true),
+        )
+        .await?;
+        GraphQL::apply_table_directive(
+            db_url,
+            "public",
+            "todos",
+            &GraphQLDirective::new().with_total_count(true),
+        )
+        .await?;
+
+        let sdl = GraphQL::schema_sdl(db_url).await?;
+        assert!(sdl.contains("type TodosConnection"), "SDL:\n{}", sdl);
+        assert!(sdl.contains("totalCount: Int!"), "SDL:\n{}", sdl);
+
+        // The same schema is visible over HTTP
+        let http_sdl = endpoint.client().schema_sdl().await?;
+        assert!(http_sdl.contains("totalCount: Int!"));
+
+        Ok(())
+    }
+
+    /// Test that a view is exposed once it declares primary key columns
+    #[tokio::test]
+    async fn test_view_primary_key_directive() -> Result<()> {
+        let endpoint = setup_graphql_endpoint().await?;
+        let db_url = endpoint.db_url();
+
+        let (client, connection) = tokio_postgres::connect(db_url, NoTls).await?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+        client
+            .batch_execute(
+                "CREATE VIEW public.open_todos AS SELECT id, task FROM public.todos WHERE NOT done;
+                 GRANT SELECT ON public.open_todos TO anon;",
+            )
+            .await?;
+
+        let sdl = GraphQL::schema_sdl(db_url).await?;
+        assert!(!sdl.contains("open_todosCollection"), "SDL:\n{}", sdl);
+
+        GraphQL::apply_table_directive(
+            db_url,
+            "public",
+            "open_todos",
+            &GraphQLDirective::new().with_primary_key_columns(["id"]),
+        )
+        .await?;
+
+        let data = endpoint
+            .client()
+            .query("{ open_todosCollection { edges { node { task } } } }")
+            .await?
+            .into_result()?;
+        assert_eq!(
+            data["open_todosCollection"]["edges"][0]["node"]["task"],
+            "Learn GraphQL"
+        );
+
+        Ok(())
+    }
 }
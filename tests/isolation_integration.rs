@@ -0,0 +1,157 @@
+//! Integration tests for per-test isolation on a shared stack
+//!
+//! These tests start one stack per test function and take several isolated views of
+//! it, checking that their data, services and cleanup are independent.
+//!
+//! Run with: `cargo test --features stack --test isolation_integration`
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use supabase_testcontainers_modules::{Auth, Isolation, PostgREST, RunningStack, SupabaseStack};
+use tokio_postgres::NoTls;
+
+/// Schema and seed data applied before the snapshot
+const SETUP_SQL: &str = "
+    CREATE TABLE public.todos (id serial PRIMARY KEY, task text NOT NULL);
+    INSERT INTO public.todos (task) VALUES ('seeded');
+";
+
+/// Runs `sql` against `db_url` and returns the first column of the first row
+async fn query_i64(db_url: &str, sql: &str) -> Result<i64> {
+    let (client, connection) = tokio_postgres::connect(db_url, NoTls).await?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("connection error: {}", e);
+        }
+    });
+    Ok(client.query_one(sql, &[]).await?.get(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Starts Auth and PostgREST with the todo table seeded
+    async fn setup_stack() -> Result<RunningStack> {
+        SupabaseStack::new()
+            .with_auth(Auth::default())
+            .with_postgrest(PostgREST::default())
+            .with_setup_sql(SETUP_SQL)
+            .start()
+            .await
+    }
+
+    /// Test that isolated databases start from the snapshot and do not share writes
+    #[tokio::test]
+    async fn test_database_isolation() -> Result<()> {
+        let stack = setup_stack().await?;
+        let (first, second) = tokio::try_join!(
+            stack.isolate(Isolation::Database),
+            stack.isolate(Isolation::Database)
+        )?;
+        assert_ne!(first.database(), second.database());
+
+        let http = reqwest::Client::new();
+        let response = http
+            .post(format!("{}/todos", first.rest_url().unwrap()))
+            .bearer_auth(first.service_role_key())
+            .json(&json!({ "task": "only in first" }))
+            .send()
+            .await?;
+        assert!(response.status().is_success(), "{}", response.status());
+
+        let count = "SELECT count(*) FROM public.todos";
+        assert_eq!(query_i64(first.db_url(), count).await?, 2);
+        assert_eq!(query_i64(second.db_url(), count).await?, 1);
+        assert_eq!(query_i64(stack.db_url(), count).await?, 1);
+
+        let rows: Vec<Value> = http
+            .get(format!("{}/todos", second.rest_url().unwrap()))
+            .bearer_auth(second.anon_key())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        assert_eq!(rows.len(), 1);
+
+        Ok(())
+    }
+
+    /// Test that users signed up in one isolated database are unknown to another
+    #[tokio::test]
+    async fn test_database_isolation_auth() -> Result<()> {
+        let stack = setup_stack().await?;
+        let first = stack.isolate(Isolation::Database).await?;
+        let second = stack.isolate(Isolation::Database).await?;
+
+        let http = reqwest::Client::new();
+        let signup = json!({ "email": "isolated@example.com", "password": "password123" });
+        for isolated in [&first, &second] {
+            let response = http
+                .post(format!("{}/signup", isolated.auth_url().unwrap()))
+                .json(&signup)
+                .send()
+                .await?;
+            assert!(response.status().is_success(), "{}", response.status());
+        }
+
+        Ok(())
+    }
+
+    /// Test that schema isolation serves a per-test schema through PostgREST
+    #[tokio::test]
+    async fn test_schema_isolation() -> Result<()> {
+        let stack = setup_stack().await?;
+        let isolated = stack.isolate(Isolation::schemas(["app"])).await?;
+        let schema = isolated
+            .schema("app")
+            .expect("schema was requested")
+            .to_string();
+        assert_ne!(schema, "app");
+
+        // The isolated connection creates tables in the test schema by default
+        let (client, connection) = tokio_postgres::connect(isolated.db_url(), NoTls).await?;
+        tokio::spawn(connection);
+        client
+            .batch_execute("CREATE TABLE notes (id serial PRIMARY KEY, body text)")
+            .await?;
+        let sql = format!(
+            "SELECT count(*) FROM pg_tables WHERE schemaname = '{}' AND tablename = 'notes'",
+            schema
+        );
+        assert_eq!(query_i64(stack.db_url(), &sql).await?, 1);
+
+        isolated.reload_schema().await?;
+        let response = reqwest::get(format!("{}/notes", isolated.rest_url().unwrap())).await?;
+        assert!(response.status().is_success(), "{}", response.status());
+
+        Ok(())
+    }
+
+    /// Test that teardown and drop remove the isolated database and schemas
+    #[tokio::test]
+    async fn test_cleanup() -> Result<()> {
+        let stack = setup_stack().await?;
+
+        let isolated = stack.isolate(Isolation::Database).await?;
+        let database = isolated.database().to_string();
+        isolated.teardown().await?;
+        let sql = format!(
+            "SELECT count(*) FROM pg_database WHERE datname = '{}'",
+            database
+        );
+        assert_eq!(query_i64(stack.db_url(), &sql).await?, 0);
+
+        let isolated = stack.isolate(Isolation::schemas(["app"])).await?;
+        let schema = isolated.schema("app").unwrap().to_string();
+        drop(isolated);
+        let sql = format!(
+            "SELECT count(*) FROM pg_namespace WHERE nspname = '{}'",
+            schema
+        );
+        assert_eq!(query_i64(stack.db_url(), &sql).await?, 0);
+
+        Ok(())
+    }
+}
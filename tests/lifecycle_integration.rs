@@ -0,0 +1,108 @@
+//! Integration tests for stopping, starting and pausing stack services
+//!
+//! These tests require Docker to be running and will start real containers.
+//! Run with: `cargo test --features stack --test lifecycle_integration`
+
+use std::time::Duration;
+
+use anyhow::Result;
+use supabase_testcontainers_modules::{Auth, OpenApiMode, PostgREST, StackService, SupabaseStack};
+
+/// Timeout for requests that should fail while a service is down
+const DOWN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Returns whether `GET url` answers with a success status within [`DOWN_TIMEOUT`]
+async fn is_up(url: &str) -> bool {
+    let Ok(http) = reqwest::Client::builder().timeout(DOWN_TIMEOUT).build() else {
+        return false;
+    };
+    match http.get(url).send().await {
+        Ok(response) => response.status().is_success(),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that a stopped service comes back on the same URL
+    #[tokio::test]
+    async fn test_restart_keeps_urls() -> Result<()> {
+        let mut stack = SupabaseStack::new()
+            .with_auth(Auth::default())
+            .with_stable_ports(true)
+            .start()
+            .await?;
+        let health = format!("{}/health", stack.auth_url().unwrap());
+        let db_url = stack.db_url().to_string();
+        assert!(is_up(&health).await);
+
+        stack.stop_service(StackService::Auth).await?;
+        assert!(!is_up(&health).await);
+
+        stack.start_service(StackService::Auth).await?;
+        assert_eq!(format!("{}/health", stack.auth_url().unwrap()), health);
+        assert!(is_up(&health).await);
+
+        stack.restart_service(StackService::Db).await?;
+        assert_eq!(stack.db_url(), db_url);
+        // Auth reconnects to the restarted database
+        stack.restart_service(StackService::Auth).await?;
+        assert!(is_up(&health).await);
+
+        Ok(())
+    }
+
+    /// Test that PostgREST restarted with new settings keeps its stable URL
+    #[tokio::test]
+    async fn test_restart_postgrest_with() -> Result<()> {
+        let mut stack = SupabaseStack::new()
+            .with_postgrest(PostgREST::default())
+            .with_stable_ports(true)
+            .start()
+            .await?;
+        let root = format!("{}/", stack.rest_url().unwrap());
+        assert!(is_up(&root).await);
+
+        stack
+            .restart_postgrest_with(
+                PostgREST::default().with_openapi_mode(OpenApiMode::IgnorePrivileges),
+            )
+            .await?;
+        assert_eq!(format!("{}/", stack.rest_url().unwrap()), root);
+        assert!(is_up(&root).await);
+
+        Ok(())
+    }
+
+    /// Test that a paused service hangs and recovers when unpaused
+    #[tokio::test]
+    async fn test_pause_and_kill() -> Result<()> {
+        let mut stack = SupabaseStack::new()
+            .with_postgrest(PostgREST::default())
+            .with_stable_ports(false)
+            .start()
+            .await?;
+        let root = format!("{}/", stack.rest_url().unwrap());
+
+        stack.pause_service(StackService::Rest).await?;
+        assert!(!is_up(&root).await);
+        stack.unpause_service(StackService::Rest).await?;
+        assert!(is_up(&root).await);
+
+        stack.kill_service(StackService::Rest).await?;
+        stack.start_service(StackService::Rest).await?;
+        // Without stable ports the URL follows the new host port
+        assert!(is_up(&format!("{}/", stack.rest_url().unwrap())).await);
+
+        let error = stack
+            .stop_service(StackService::Storage)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "storage is not part of the stack");
+
+        Ok(())
+    }
+}
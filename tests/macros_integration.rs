@@ -0,0 +1,90 @@
+//! Integration tests for the `#[supabase_test]` attribute
+//!
+//! Every test here shares one stack with Auth, PostgREST and the fixture project's
+//! migrations. Without Docker the tests are skipped; set `SUPABASE_TC_REQUIRE_DOCKER=1`
+//! to make them fail instead.
+//!
+//! Run with: `cargo test --features macros --test macros_integration`
+
+use anyhow::Result;
+use supabase_testcontainers_modules::{supabase_test, TestStack};
+use tokio_postgres::NoTls;
+
+/// Runs `sql` against `db_url` and returns the first column of the first row
+async fn query_i64(db_url: &str, sql: &str) -> Result<i64> {
+    let (client, connection) = tokio_postgres::connect(db_url, NoTls).await?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("connection error: {}", e);
+        }
+    });
+    Ok(client.query_one(sql, &[]).await?.get(0))
+}
+
+/// Inserts a todo and returns how many there are
+async fn insert_todo(db_url: &str) -> Result<i64> {
+    query_i64(
+        db_url,
+        "INSERT INTO public.todos (task) VALUES ('test') RETURNING id::bigint",
+    )
+    .await?;
+    query_i64(db_url, "SELECT count(*) FROM public.todos").await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that a reset test starts from the migrated snapshot
+    #[supabase_test(
+        services = [auth, postgrest],
+        migrations = "tests/fixtures/project/supabase/migrations"
+    )]
+    async fn test_reset_first(stack: &TestStack) -> Result<()> {
+        assert_eq!(insert_todo(stack.db_url()).await?, 1);
+        Ok(())
+    }
+
+    /// Test that the row inserted by the other reset test is gone
+    #[supabase_test(
+        services = [postgrest, auth],
+        migrations = "tests/fixtures/project/supabase/migrations"
+    )]
+    async fn test_reset_second(stack: &TestStack) -> Result<()> {
+        assert_eq!(insert_todo(stack.db_url()).await?, 1);
+        Ok(())
+    }
+
+    /// Test that a database-isolated test gets its own database and services
+    #[supabase_test(
+        services = [auth, rest],
+        migrations = "tests/fixtures/project/supabase/migrations",
+        isolation = database
+    )]
+    async fn test_database_isolation(stack: TestStack) -> Result<()> {
+        assert!(stack.isolated().is_some());
+        assert_ne!(stack.db_url(), stack.shared().db_url());
+        assert_ne!(stack.rest_url(), stack.shared().rest_url());
+        assert_eq!(insert_todo(stack.db_url()).await?, 1);
+
+        let response = reqwest::Client::new()
+            .get(format!("{}/todos", stack.rest_url().unwrap()))
+            .bearer_auth(stack.service_role_key())
+            .send()
+            .await?;
+        assert!(response.status().is_success());
+
+        Ok(())
+    }
+
+    /// Test that a test without a parameter or return value runs against the stack
+    #[supabase_test(
+        services = [auth, postgrest],
+        migrations = "tests/fixtures/project/supabase/migrations",
+        isolation = none
+    )]
+    async fn test_without_stack_parameter() {
+        let stack = supabase_testcontainers_modules::SharedStack::get().unwrap();
+        assert!(stack.auth_url().is_some());
+    }
+}
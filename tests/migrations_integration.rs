@@ -0,0 +1,167 @@
+//! Integration tests for the migration runner
+//!
+//! These tests apply migrations to a plain PostgreSQL container and to a running
+//! stack, and check the CLI-compatible tracking in
+//! `supabase_migrations.schema_migrations`.
+//!
+//! Run with: `cargo test --features stack --test migrations_integration`
+
+use anyhow::Result;
+use serde_json::Value;
+use supabase_testcontainers_modules::{MigrationRunner, PostgREST, SupabaseStack};
+use testcontainers::runners::AsyncRunner;
+use testcontainers::{ContainerAsync, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+use tokio_postgres::NoTls;
+
+/// Creates the todo table
+const CREATE_TODOS: &str = "
+CREATE TABLE public.todos (id serial PRIMARY KEY, task text NOT NULL);
+CREATE FUNCTION public.todo_count() RETURNS bigint AS $$
+    SELECT count(*) FROM public.todos;
+$$ LANGUAGE sql;
+";
+
+/// Starts PostgreSQL and returns it with its host URL
+async fn start_postgres() -> Result<(ContainerAsync<Postgres>, String)> {
+    let postgres = Postgres::default().with_tag("15-alpine").start().await?;
+    let db_url = format!(
+        "postgres://postgres:postgres@{}:{}/postgres",
+        postgres.get_host().await?,
+        postgres.get_host_port_ipv4(5432).await?
+    );
+    Ok((postgres, db_url))
+}
+
+/// Returns the first column of every row `sql` returns
+async fn query_strings(db_url: &str, sql: &str) -> Result<Vec<String>> {
+    let (client, connection) = tokio_postgres::connect(db_url, NoTls).await?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("connection error: {}", e);
+        }
+    });
+    Ok(client
+        .query(sql, &[])
+        .await?
+        .iter()
+        .map(|row| row.get(0))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that migrations are applied once and recorded like the CLI does
+    #[tokio::test]
+    async fn test_migrations_are_tracked() -> Result<()> {
+        let (_postgres, db_url) = start_postgres().await?;
+        let runner = MigrationRunner::new()
+            .with_sql(
+                "20240102000000",
+                "seed_todos",
+                "INSERT INTO public.todos (task) VALUES ('a');",
+            )
+            .with_sql("20240101000000", "create_todos", CREATE_TODOS);
+
+        let applied = runner.apply(&db_url).await?;
+        assert_eq!(applied, ["20240101000000", "20240102000000"]);
+
+        // Already recorded: nothing to apply
+        assert!(runner.apply(&db_url).await?.is_empty());
+        assert_eq!(
+            query_strings(&db_url, "SELECT task FROM public.todos").await?,
+            ["a"]
+        );
+        assert_eq!(
+            query_strings(
+                &db_url,
+                "SELECT name FROM supabase_migrations.schema_migrations ORDER BY version"
+            )
+            .await?,
+            ["create_todos", "seed_todos"]
+        );
+        let statements = query_strings(
+            &db_url,
+            "SELECT unnest(statements) FROM supabase_migrations.schema_migrations \
+             WHERE version = '20240101000000'",
+        )
+        .await?;
+        assert_eq!(statements.len(), 2);
+
+        Ok(())
+    }
+
+    /// Test that a failing statement is reported and its migration rolled back
+    #[tokio::test]
+    async fn test_failure_names_migration_and_statement() -> Result<()> {
+        let (_postgres, db_url) = start_postgres().await?;
+        let runner = MigrationRunner::new()
+            .with_sql("1", "ok", "CREATE TABLE public.a (id int);")
+            .with_sql(
+                "2",
+                "broken",
+                "CREATE TABLE public.b (id int);\n\nINSERT INTO public.missing VALUES (1);",
+            );
+
+        let error = runner.apply(&db_url).await.unwrap_err().to_string();
+        assert!(
+            error.starts_with("migration 2_broken.sql failed at statement 2 (line 3)"),
+            "{}",
+            error
+        );
+        assert!(error.contains("public.missing"), "{}", error);
+
+        // The first migration stays applied, the second is rolled back
+        assert_eq!(
+            query_strings(
+                &db_url,
+                "SELECT version FROM supabase_migrations.schema_migrations"
+            )
+            .await?,
+            ["1"]
+        );
+        assert_eq!(
+            query_strings(
+                &db_url,
+                "SELECT coalesce(to_regclass('public.b')::text, 'missing')"
+            )
+            .await?,
+            ["missing"]
+        );
+
+        Ok(())
+    }
+
+    /// Test that migrating a running stack reloads PostgREST's schema cache
+    #[tokio::test]
+    async fn test_stack_migrate_reloads_postgrest() -> Result<()> {
+        let stack = SupabaseStack::new()
+            .with_postgrest(PostgREST::default())
+            .start()
+            .await?;
+
+        let runner = MigrationRunner::new().with_sql(
+            "20240101000000",
+            "create_todos",
+            format!(
+                "{}\nINSERT INTO public.todos (task) VALUES ('migrated');",
+                CREATE_TODOS
+            ),
+        );
+        assert_eq!(stack.migrate(&runner).await?, ["20240101000000"]);
+
+        let rows: Vec<Value> = reqwest::Client::new()
+            .get(format!("{}/todos", stack.rest_url().unwrap()))
+            .bearer_auth(stack.anon_key())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        assert_eq!(rows.len(), 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+//! Integration tests for the PostgreSQL logical replication preset
+//!
+//! These tests start PostgreSQL with and without [`LogicalReplication`] and check that
+//! validation accepts the preset and explains what a stock server is missing.
+//!
+//! Run with: `cargo test --features postgres_testcontainer --test postgres_integration`
+
+use anyhow::Result;
+use supabase_testcontainers_modules::{LogicalReplication, LOCAL_HOST, REALTIME_PUBLICATION};
+use testcontainers::runners::AsyncRunner;
+use testcontainers::{ContainerAsync, ImageExt};
+use testcontainers_modules::postgres::Postgres;
+
+/// PostgreSQL port constant
+const POSTGRES_PORT: u16 = 5432;
+
+/// Builds the connection URL for a started PostgreSQL container
+async fn db_url(container: &ContainerAsync<Postgres>) -> Result<String> {
+    let port = container.get_host_port_ipv4(POSTGRES_PORT).await?;
+    Ok(format!(
+        "postgres://postgres:postgres@{}:{}/postgres",
+        LOCAL_HOST, port
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that the preset passes validation
+    #[tokio::test]
+    async fn test_preset_validates() -> Result<()> {
+        let postgres = LogicalReplication::default()
+            .apply(Postgres::default())
+            .start()
+            .await?;
+        let url = db_url(&postgres).await?;
+
+        LogicalReplication::validate(&url, REALTIME_PUBLICATION).await?;
+
+        Ok(())
+    }
+
+    /// Test that the server runs with the configured settings
+    #[tokio::test]
+    async fn test_preset_settings() -> Result<()> {
+        let postgres = LogicalReplication::default()
+            .with_max_replication_slots(4)
+            .with_max_wal_senders(6)
+            .apply(Postgres::default())
+            .start()
+            .await?;
+        let url = db_url(&postgres).await?;
+
+        let (client, connection) = tokio_postgres::connect(&url, tokio_postgres::NoTls).await?;
+        tokio::spawn(connection);
+
+        let row = client
+            .query_one(
+                "SELECT current_setting('wal_level'), \
+                        current_setting('max_replication_slots'), \
+                        current_setting('max_wal_senders')",
+                &[],
+            )
+            .await?;
+        assert_eq!(row.get::<_, String>(0), "logical");
+        assert_eq!(row.get::<_, String>(1), "4");
+        assert_eq!(row.get::<_, String>(2), "6");
+
+        Ok(())
+    }
+
+    /// Test that a stock server fails validation with a clear error
+    #[tokio::test]
+    async fn test_stock_postgres_fails_validation() -> Result<()> {
+        let postgres = Postgres::default().with_tag("15-alpine").start().await?;
+        let url = db_url(&postgres).await?;
+
+        let err = LogicalReplication::validate(&url, REALTIME_PUBLICATION)
+            .await
+            .expect_err("stock PostgreSQL should not validate");
+        let message = err.to_string();
+        assert!(message.contains("wal_level is 'replica'"), "{}", message);
+        assert!(
+            message.contains("publication 'supabase_realtime' does not exist"),
+            "{}",
+            message
+        );
+
+        Ok(())
+    }
+
+    /// Test that tables can be added to the publication
+    #[tokio::test]
+    async fn test_publish_tables() -> Result<()> {
+        let postgres = LogicalReplication::default()
+            .apply(Postgres::default())
+            .start()
+            .await?;
+        let url = db_url(&postgres).await?;
+
+        let (client, connection) = tokio_postgres::connect(&url, tokio_postgres::NoTls).await?;
+        tokio::spawn(connection);
+        client
+            .batch_execute("CREATE TABLE public.messages (id serial PRIMARY KEY, body text)")
+            .await?;
+
+        LogicalReplication::publish_tables(&url, REALTIME_PUBLICATION, &["public.messages"])
+            .await?;
+
+        let row = client
+            .query_one(
+                "SELECT count(*) FROM pg_publication_tables \
+                 WHERE pubname = $1 AND tablename = 'messages'",
+                &[&REALTIME_PUBLICATION],
+            )
+            .await?;
+        assert_eq!(row.get::<_, i64>(0), 1);
+
+        Ok(())
+    }
+}
@@ -8,7 +8,9 @@
 use anyhow::Result;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
-use supabase_testcontainers_modules::{PostgREST, LOCAL_HOST, POSTGREST_PORT};
+use supabase_testcontainers_modules::{
+    PostgREST, LOCAL_HOST, POSTGREST_ADMIN_PORT, POSTGREST_PORT,
+};
 use testcontainers::runners::AsyncRunner;
 use testcontainers::ImageExt;
 use testcontainers_modules::postgres::Postgres;
@@ -59,7 +61,6 @@ mod tests {
     struct PostgRESTContext {
         #[allow(dead_code)]
         postgres: ContainerAsync<Postgres>,
-        #[allow(dead_code)]
         postgrest: ContainerAsync<PostgREST>,
         postgres_port: u16,
         postgrest_port: u16,
@@ -97,7 +98,7 @@ mod tests {
         // Note: PostgREST methods must be called before ImageExt methods
         let mut postgrest_image = PostgREST::default()
             .with_postgres_connection(&postgrest_db_url)
-            .with_db_schemas("api")
+            .with_schemas(["api"])
             .with_db_anon_role("anon");
 
         if let Some(secret) = jwt_secret {
@@ -115,9 +116,6 @@ mod tests {
             .await?;
         let postgrest_port = postgrest.get_host_port_ipv4(POSTGREST_PORT).await?;
 
-        // Wait for PostgREST to connect to database
-        tokio::time::sleep(Duration::from_secs(2)).await;
-
         Ok(PostgRESTContext {
             postgres,
             postgrest,
@@ -453,6 +451,110 @@ mod tests {
         Ok(())
     }
 
+    /// Test that the admin server liveness and readiness probes return 200
+    #[tokio::test]
+    async fn test_admin_live_and_ready_endpoints() -> Result<()> {
+        let ctx = setup_postgrest(None, None).await?;
+        let admin_port = ctx
+            .postgrest
+            .get_host_port_ipv4(POSTGREST_ADMIN_PORT)
+            .await?;
+
+        let client = reqwest::Client::new();
+        for path in ["live", "ready"] {
+            let response = client
+                .get(format!("{}/{}", postgrest_url(admin_port), path))
+                .send()
+                .await?;
+            assert!(
+                response.status().is_success(),
+                "/{} should return success, got: {}",
+                path,
+                response.status()
+            );
+        }
+
+        assert!(PostgREST::is_ready(&ctx.postgrest).await?);
+
+        Ok(())
+    }
+
+    /// Test that metrics report a loaded schema cache and a healthy pool
+    #[tokio::test]
+    async fn test_metrics_report_schema_cache_and_pool() -> Result<()> {
+        let ctx = setup_postgrest(None, None).await?;
+
+        let metrics = PostgREST::metrics(&ctx.postgrest).await?;
+
+        assert!(
+            metrics.schema_cache_loads().unwrap_or_default() >= 1.0,
+            "Schema cache should have loaded: {:?}",
+            metrics
+        );
+        assert!(
+            metrics.schema_cache_query_time_seconds().is_some(),
+            "Schema cache load time should be reported"
+        );
+        assert!(
+            metrics.db_pool_max().unwrap_or_default() > 0.0,
+            "Pool size should be reported"
+        );
+
+        Ok(())
+    }
+
+    /// Test that tables created after startup are served once the schema cache is reloaded
+    #[tokio::test]
+    async fn test_reload_schema_exposes_new_table() -> Result<()> {
+        let ctx = setup_postgrest(None, None).await?;
+        let db_url = postgres_url(ctx.postgres_port);
+        let client = reqwest::Client::new();
+        let notes_url = format!("{}/notes", postgrest_url(ctx.postgrest_port));
+
+        // Table does not exist yet, so PostgREST should not know about it
+        let before = client.get(&notes_url).send().await?;
+        assert_eq!(before.status().as_u16(), 404, "Unknown table should 404");
+
+        let (pg, connection) = tokio_postgres::connect(&db_url, NoTls).await?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+        pg.batch_execute(
+            r#"
+            CREATE TABLE api.notes (id SERIAL PRIMARY KEY, body TEXT NOT NULL);
+            GRANT SELECT ON api.notes TO anon;
+            "#,
+        )
+        .await?;
+
+        PostgREST::reload_schema(&ctx.postgrest, &db_url).await?;
+
+        let after = client.get(&notes_url).send().await?;
+        assert!(
+            after.status().is_success(),
+            "New table should be served after reload, got: {}",
+            after.status()
+        );
+
+        Ok(())
+    }
+
+    /// Test that a config reload is acknowledged by PostgREST
+    #[tokio::test]
+    async fn test_reload_config_completes() -> Result<()> {
+        let ctx = setup_postgrest(None, None).await?;
+        let db_url = postgres_url(ctx.postgres_port);
+
+        PostgREST::reload_config(&ctx.postgrest, &db_url).await?;
+
+        let loads = PostgREST::schema_cache_loads(&ctx.postgrest).await?;
+        assert!(loads >= 1, "Schema cache should have loaded at least once");
+
+        Ok(())
+    }
+
     /// Creates a simple JWT token for testing
     /// Note: In production, use a proper JWT library
     fn create_test_jwt(role: &str, secret: &str) -> String {
@@ -0,0 +1,92 @@
+//! Integration tests for stacks configured from a Supabase CLI project
+//!
+//! These tests start the stack described by `tests/fixtures/project/supabase` and
+//! check that its migrations, seed, buckets and settings were applied.
+//!
+//! Run with: `cargo test --features project --test project_integration`
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use supabase_testcontainers_modules::{RunningStack, SupabaseStack};
+use tokio_postgres::NoTls;
+
+/// Project root of the fixture project
+const PROJECT_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/project");
+
+/// Runs `sql` against `db_url` and returns the first column of the first row
+async fn query_i64(db_url: &str, sql: &str) -> Result<i64> {
+    let (client, connection) = tokio_postgres::connect(db_url, NoTls).await?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("connection error: {}", e);
+        }
+    });
+    Ok(client.query_one(sql, &[]).await?.get(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Starts the fixture project's stack
+    async fn setup_stack() -> Result<RunningStack> {
+        SupabaseStack::from_project_dir(PROJECT_DIR)?.start().await
+    }
+
+    /// Test that migrations and seed ran and `api.max_rows` limits responses
+    #[tokio::test]
+    async fn test_migrations_seed_and_max_rows() -> Result<()> {
+        let stack = setup_stack().await?;
+
+        assert_eq!(
+            query_i64(stack.db_url(), "SELECT count(*) FROM public.todos").await?,
+            3
+        );
+
+        let rows: Vec<Value> = reqwest::Client::new()
+            .get(format!("{}/todos", stack.rest_url().unwrap()))
+            .bearer_auth(stack.anon_key())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        assert_eq!(rows.len(), 2, "api.max_rows = 2");
+
+        Ok(())
+    }
+
+    /// Test that buckets from `[storage.buckets]` exist
+    #[tokio::test]
+    async fn test_buckets_created() -> Result<()> {
+        let stack = setup_stack().await?;
+
+        let bucket: Value = reqwest::Client::new()
+            .get(format!("{}/bucket/avatars", stack.storage_url().unwrap()))
+            .bearer_auth(stack.service_role_key())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        assert_eq!(bucket["public"], json!(true));
+        assert_eq!(bucket["file_size_limit"], json!(1048576));
+
+        Ok(())
+    }
+
+    /// Test that `auth.enable_signup = false` disables signups
+    #[tokio::test]
+    async fn test_signup_disabled() -> Result<()> {
+        let stack = setup_stack().await?;
+
+        let response = reqwest::Client::new()
+            .post(format!("{}/signup", stack.auth_url().unwrap()))
+            .json(&json!({ "email": "new@example.com", "password": "password123" }))
+            .send()
+            .await?;
+        assert!(response.status().is_client_error(), "{}", response.status());
+
+        Ok(())
+    }
+}
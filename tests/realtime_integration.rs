@@ -13,7 +13,7 @@
 //! Run with: `cargo test --features realtime,const --test realtime_integration`
 
 use std::sync::atomic::{AtomicU64, Ordering};
-use supabase_testcontainers_modules::{Realtime, REALTIME_PORT};
+use supabase_testcontainers_modules::{ReadyCheck, Realtime, REALTIME_PORT};
 
 /// JWT secret used for authentication tests (must be at least 32 characters)
 const JWT_SECRET: &str = "super-secret-jwt-token-with-at-least-32-characters-for-hs256";
@@ -123,6 +123,24 @@ mod tests {
         // Should have exactly one ready condition
         assert_eq!(conditions.len(), 1);
 
+        // Should probe /api/ping over HTTP by default
+        match &conditions[0] {
+            WaitFor::Http(http_wait) => {
+                assert!(
+                    format!("{:?}", http_wait).contains("/api/ping"),
+                    "Should wait for GET /api/ping"
+                );
+            }
+            _ => panic!("Expected Http wait condition"),
+        }
+    }
+
+    /// Test that the log ready condition can be restored
+    #[test]
+    fn test_log_ready_check() {
+        let realtime = Realtime::default().with_ready_check(ReadyCheck::Log);
+        let conditions = realtime.ready_conditions();
+
         // Should be waiting for "Realtime has started" message
         match &conditions[0] {
             WaitFor::Log(log_wait) => {
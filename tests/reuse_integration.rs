@@ -0,0 +1,121 @@
+//! Integration tests for container reuse across test runs
+//!
+//! These tests start containers marked for reuse twice in one process, as two test
+//! runs would, and check that the second start attaches to the first container.
+//!
+//! Run with: `cargo test --features "reuse stack" --test reuse_integration`
+
+use anyhow::Result;
+use supabase_testcontainers_modules::{PostgREST, Reusable, SupabaseStack};
+use testcontainers::runners::AsyncRunner;
+use testcontainers::ImageExt;
+use testcontainers_modules::postgres::Postgres;
+use tokio_postgres::NoTls;
+
+/// Counts the rows of `public.todos`
+async fn count_todos(db_url: &str) -> Result<i64> {
+    let (client, connection) = tokio_postgres::connect(db_url, NoTls).await?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("connection error: {}", e);
+        }
+    });
+    Ok(client
+        .query_one("SELECT count(*) FROM public.todos", &[])
+        .await?
+        .get(0))
+}
+
+/// Returns a value unique to this test run, so runs do not attach to each other
+fn run_id() -> String {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or_default();
+    format!("{}-{}", std::process::id(), timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that a request with the same configuration attaches to the running container
+    #[tokio::test]
+    async fn test_same_configuration_is_reused() -> Result<()> {
+        let run = run_id();
+        let first = Postgres::default()
+            .with_env_var("REUSE_TEST_RUN", &run)
+            .reusable()
+            .start()
+            .await?;
+        let second = Postgres::default()
+            .with_env_var("REUSE_TEST_RUN", &run)
+            .reusable()
+            .start()
+            .await?;
+
+        assert_eq!(first.id(), second.id());
+
+        first.rm().await?;
+        Ok(())
+    }
+
+    /// Test that a changed configuration starts a new container
+    #[tokio::test]
+    async fn test_changed_configuration_starts_new_container() -> Result<()> {
+        let run = run_id();
+        let first = Postgres::default()
+            .with_env_var("REUSE_TEST_RUN", &run)
+            .reusable()
+            .start()
+            .await?;
+        let second = Postgres::default()
+            .with_env_var("REUSE_TEST_RUN", &run)
+            .with_tag("16-alpine")
+            .reusable()
+            .start()
+            .await?;
+
+        assert_ne!(first.id(), second.id());
+
+        first.rm().await?;
+        second.rm().await?;
+        Ok(())
+    }
+
+    /// Test that a reused stack skips setup and starts from the snapshot
+    ///
+    /// The stack's containers keep running afterwards, as reused containers do.
+    #[tokio::test]
+    async fn test_reused_stack_resets_to_snapshot() -> Result<()> {
+        let config = SupabaseStack::new()
+            .with_postgrest(PostgREST::default())
+            .with_setup_sql(format!(
+                "CREATE TABLE public.todos (id serial PRIMARY KEY, task text NOT NULL);
+                 INSERT INTO public.todos (task) VALUES ('seeded');
+                 COMMENT ON TABLE public.todos IS 'run {}';",
+                run_id()
+            ))
+            .with_reuse(true);
+
+        let first = config.clone().start().await?;
+        let (client, connection) = tokio_postgres::connect(first.db_url(), NoTls).await?;
+        tokio::spawn(connection);
+        client
+            .batch_execute("INSERT INTO public.todos (task) VALUES ('left behind')")
+            .await?;
+        assert_eq!(count_todos(first.db_url()).await?, 2);
+        let network = first.network().to_string();
+        let postgres_id = first.postgres().id().to_string();
+        drop(client);
+        drop(first);
+
+        // Setup SQL would fail on the existing table if it ran again
+        let second = config.start().await?;
+        assert_eq!(second.network(), network);
+        assert_eq!(second.postgres().id(), postgres_id);
+        assert_eq!(count_todos(second.db_url()).await?, 1);
+
+        Ok(())
+    }
+}
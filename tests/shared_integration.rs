@@ -0,0 +1,110 @@
+//! Integration tests for the stack shared across a test binary
+//!
+//! Every test here gets its own tokio runtime but uses the same containers through
+//! `SupabaseStack::shared`.
+//!
+//! Run with: `cargo test --features stack --test shared_integration`
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use supabase_testcontainers_modules::{Auth, Isolation, PostgREST, SharedStack, SupabaseStack};
+use tokio_postgres::NoTls;
+
+/// Schema and seed data applied before the snapshot
+const SETUP_SQL: &str = "
+    CREATE TABLE public.todos (id serial PRIMARY KEY, task text NOT NULL);
+    INSERT INTO public.todos (task) VALUES ('seeded');
+";
+
+/// Runs `sql` against `db_url` and returns the first column of the first row
+async fn query_i64(db_url: &str, sql: &str) -> Result<i64> {
+    let (client, connection) = tokio_postgres::connect(db_url, NoTls).await?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("connection error: {}", e);
+        }
+    });
+    Ok(client.query_one(sql, &[]).await?.get(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the binary's stack, starting Auth and PostgREST on first use
+    async fn stack() -> Result<&'static SharedStack> {
+        SupabaseStack::new()
+            .with_auth(Auth::default())
+            .with_postgrest(PostgREST::default())
+            .with_setup_sql(SETUP_SQL)
+            .shared()
+            .await
+    }
+
+    /// Test that every caller gets the same stack
+    #[tokio::test]
+    async fn test_shared_stack_is_started_once() -> Result<()> {
+        let first = stack().await?;
+        let second = stack().await?;
+
+        assert!(std::ptr::eq(first, second));
+        assert!(std::ptr::eq(
+            first,
+            SharedStack::get().expect("stack was started")
+        ));
+
+        Ok(())
+    }
+
+    /// Test that the stack works from a current-thread runtime
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_shared_stack_from_current_thread_runtime() -> Result<()> {
+        let stack = stack().await?;
+        let isolated = stack.isolate(Isolation::Database).await?;
+
+        let rows: Vec<Value> = reqwest::Client::new()
+            .get(format!("{}/todos", isolated.rest_url().unwrap()))
+            .bearer_auth(isolated.anon_key())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        assert_eq!(rows.len(), 1);
+
+        Ok(())
+    }
+
+    /// Test that the stack works from a multi-thread runtime
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_shared_stack_from_multi_thread_runtime() -> Result<()> {
+        let stack = stack().await?;
+        let isolated = stack.isolate(Isolation::Database).await?;
+
+        let response = reqwest::Client::new()
+            .post(format!("{}/signup", isolated.auth_url().unwrap()))
+            .json(&json!({ "email": "shared@example.com", "password": "password123" }))
+            .send()
+            .await?;
+        assert!(response.status().is_success(), "{}", response.status());
+
+        isolated.teardown().await
+    }
+
+    /// Test that isolated databases are removed when dropped on a test's runtime
+    #[tokio::test]
+    async fn test_isolated_stack_dropped_on_test_runtime() -> Result<()> {
+        let stack = stack().await?;
+        let isolated = stack.isolate(Isolation::Database).await?;
+        let database = isolated.database().to_string();
+        drop(isolated);
+
+        let sql = format!(
+            "SELECT count(*) FROM pg_database WHERE datname = '{}'",
+            database
+        );
+        assert_eq!(query_i64(stack.db_url(), &sql).await?, 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,226 @@
+//! Integration tests for the Supabase stack and snapshot-based resets
+//!
+//! These tests start PostgreSQL, Auth, PostgREST and Storage through
+//! `SupabaseStack`, change state through the services' APIs and check that
+//! `RunningStack::reset` returns everything to the snapshot.
+//!
+//! Run with: `cargo test --features stack --test stack_integration`
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use supabase_testcontainers_modules::{
+    Auth, PostgREST, RunningStack, SnapshotStrategy, Storage, SupabaseStack,
+};
+use tokio_postgres::NoTls;
+
+/// Schema and seed data applied before the snapshot
+const SETUP_SQL: &str = "
+    CREATE TABLE public.todos (id serial PRIMARY KEY, task text NOT NULL);
+    INSERT INTO public.todos (task) VALUES ('seeded');
+";
+
+/// Counts the rows of `public.todos`
+async fn count_todos(db_url: &str) -> Result<i64> {
+    let (client, connection) = tokio_postgres::connect(db_url, NoTls).await?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("connection error: {}", e);
+        }
+    });
+    Ok(client
+        .query_one("SELECT count(*) FROM public.todos", &[])
+        .await?
+        .get(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Starts the full stack with the todo table seeded
+    async fn setup_stack(strategy: SnapshotStrategy) -> Result<RunningStack> {
+        SupabaseStack::new()
+            .with_auth(Auth::default())
+            .with_postgrest(PostgREST::default())
+            .with_storage(Storage::default())
+            .with_setup_sql(SETUP_SQL)
+            .with_snapshot_strategy(strategy)
+            .start()
+            .await
+    }
+
+    /// Test that reset removes rows written through PostgREST
+    #[tokio::test]
+    async fn test_reset_restores_rows() -> Result<()> {
+        let stack = setup_stack(SnapshotStrategy::Auto).await?;
+        let rest_url = stack.rest_url().expect("PostgREST is part of the stack");
+        let http = reqwest::Client::new();
+
+        let response = http
+            .post(format!("{}/todos", rest_url))
+            .bearer_auth(stack.service_role_key())
+            .json(&json!({ "task": "written by test" }))
+            .send()
+            .await?;
+        assert!(response.status().is_success(), "{}", response.status());
+        assert_eq!(count_todos(stack.db_url()).await?, 2);
+
+        stack.reset().await?;
+        assert_eq!(count_todos(stack.db_url()).await?, 1);
+
+        // PostgREST serves the restored database again
+        let rows: Vec<Value> = http
+            .get(format!("{}/todos", rest_url))
+            .bearer_auth(stack.anon_key())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["task"], "seeded");
+
+        Ok(())
+    }
+
+    /// Test that reset drops tables created after the snapshot
+    #[tokio::test]
+    async fn test_reset_drops_new_tables() -> Result<()> {
+        let stack = setup_stack(SnapshotStrategy::Template).await?;
+        let rest_url = stack.rest_url().expect("PostgREST is part of the stack");
+        let postgrest = stack.postgrest().expect("PostgREST is part of the stack");
+
+        let (client, connection) = tokio_postgres::connect(stack.db_url(), NoTls).await?;
+        tokio::spawn(connection);
+        client
+            .batch_execute("CREATE TABLE public.scratch (id int)")
+            .await?;
+        PostgREST::reload_schema(postgrest, stack.db_url()).await?;
+
+        let status = reqwest::get(format!("{}/scratch", rest_url))
+            .await?
+            .status();
+        assert!(status.is_success(), "{}", status);
+
+        stack.reset().await?;
+
+        let status = reqwest::get(format!("{}/scratch", rest_url))
+            .await?
+            .status();
+        assert_eq!(status, reqwest::StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+
+    /// Test that reset removes users signed up after the snapshot
+    #[tokio::test]
+    async fn test_reset_removes_auth_users() -> Result<()> {
+        let stack = setup_stack(SnapshotStrategy::Auto).await?;
+        let auth_url = stack.auth_url().expect("Auth is part of the stack");
+        let http = reqwest::Client::new();
+
+        let signup = json!({ "email": "reset@example.com", "password": "password123" });
+        let response = http
+            .post(format!("{}/signup", auth_url))
+            .json(&signup)
+            .send()
+            .await?;
+        assert!(response.status().is_success(), "{}", response.status());
+
+        stack.reset().await?;
+
+        // Signing up with the same email works again
+        let response = http
+            .post(format!("{}/signup", auth_url))
+            .json(&signup)
+            .send()
+            .await?;
+        assert!(response.status().is_success(), "{}", response.status());
+
+        Ok(())
+    }
+
+    /// Test that reset removes buckets and files created after the snapshot
+    #[tokio::test]
+    async fn test_reset_removes_storage_objects() -> Result<()> {
+        let stack = setup_stack(SnapshotStrategy::Auto).await?;
+        let storage_url = stack.storage_url().expect("Storage is part of the stack");
+        let http = reqwest::Client::new();
+
+        let response = http
+            .post(format!("{}/bucket", storage_url))
+            .bearer_auth(stack.service_role_key())
+            .json(&json!({ "name": "reset-bucket", "public": false }))
+            .send()
+            .await?;
+        assert!(response.status().is_success(), "{}", response.status());
+        let response = http
+            .post(format!("{}/object/reset-bucket/hello.txt", storage_url))
+            .bearer_auth(stack.service_role_key())
+            .header("Content-Type", "text/plain")
+            .body("hello")
+            .send()
+            .await?;
+        assert!(response.status().is_success(), "{}", response.status());
+
+        stack.reset().await?;
+
+        let response = http
+            .get(format!("{}/bucket/reset-bucket", storage_url))
+            .bearer_auth(stack.service_role_key())
+            .send()
+            .await?;
+        assert!(
+            !response.status().is_success(),
+            "bucket should be gone after reset"
+        );
+
+        Ok(())
+    }
+
+    /// Test that the dump strategy restores the database
+    #[tokio::test]
+    async fn test_dump_strategy() -> Result<()> {
+        let stack = SupabaseStack::new()
+            .with_setup_sql(SETUP_SQL)
+            .with_snapshot_strategy(SnapshotStrategy::Dump)
+            .start()
+            .await?;
+        assert_eq!(stack.snapshot().strategy(), SnapshotStrategy::Dump);
+
+        let (client, connection) = tokio_postgres::connect(stack.db_url(), NoTls).await?;
+        tokio::spawn(connection);
+        client.batch_execute("DELETE FROM public.todos").await?;
+        assert_eq!(count_todos(stack.db_url()).await?, 0);
+
+        stack.reset().await?;
+        assert_eq!(count_todos(stack.db_url()).await?, 1);
+
+        Ok(())
+    }
+
+    /// Test that a snapshot taken later replaces the initial one
+    #[tokio::test]
+    async fn test_take_snapshot() -> Result<()> {
+        let mut stack = SupabaseStack::new()
+            .with_setup_sql(SETUP_SQL)
+            .start()
+            .await?;
+
+        let (client, connection) = tokio_postgres::connect(stack.db_url(), NoTls).await?;
+        tokio::spawn(connection);
+        client
+            .batch_execute("INSERT INTO public.todos (task) VALUES ('second seed')")
+            .await?;
+        stack.take_snapshot().await?;
+
+        let (client, connection) = tokio_postgres::connect(stack.db_url(), NoTls).await?;
+        tokio::spawn(connection);
+        client.batch_execute("DELETE FROM public.todos").await?;
+
+        stack.reset().await?;
+        assert_eq!(count_todos(stack.db_url()).await?, 2);
+
+        Ok(())
+    }
+}
@@ -126,9 +126,6 @@ mod tests {
             .await?;
         let storage_port = storage.get_host_port_ipv4(STORAGE_PORT).await?;
 
-        // Wait for Storage to connect to database
-        tokio::time::sleep(Duration::from_secs(3)).await;
-
         Ok(StorageContext {
             postgres,
             storage,
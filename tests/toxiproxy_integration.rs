@@ -0,0 +1,132 @@
+//! Integration tests for fault injection with Toxiproxy
+//!
+//! These tests require Docker to be running and will start real containers.
+//! Run with: `cargo test --features stack --test toxiproxy_integration`
+
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use supabase_testcontainers_modules::{
+    PostgREST, StackLink, SupabaseStack, Toxic, Toxiproxy, ToxiproxyClient,
+};
+use testcontainers::runners::AsyncRunner;
+use testcontainers::ImageExt;
+use testcontainers_modules::postgres::Postgres;
+use tokio_postgres::NoTls;
+
+/// Injected latency, well above a local query's round trip
+const LATENCY: Duration = Duration::from_millis(1000);
+/// Port the database proxy listens on
+const DB_PROXY_PORT: u16 = 15432;
+
+/// Connects to `db_url` and runs a trivial query
+async fn ping(db_url: &str) -> Result<()> {
+    let (client, connection) = tokio_postgres::connect(db_url, NoTls).await?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("connection error: {}", e);
+        }
+    });
+    client.query_one("SELECT 1", &[]).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that toxics and disabled proxies affect a proxied database
+    #[tokio::test]
+    async fn test_proxied_database() -> Result<()> {
+        let network = format!("toxiproxy-test-{}", std::process::id());
+        let db_name = format!("{}-db", network);
+        let _postgres = Postgres::default()
+            .with_tag("15-alpine")
+            .with_network(&network)
+            .with_container_name(&db_name)
+            .start()
+            .await?;
+        let toxiproxy = Toxiproxy::default()
+            .with_proxy("db", DB_PROXY_PORT, format!("{}:5432", db_name))
+            .with_network(&network)
+            .start()
+            .await?;
+        let db_url = format!(
+            "postgres://postgres:postgres@{}:{}/postgres",
+            toxiproxy.get_host().await?,
+            toxiproxy.get_host_port_ipv4(DB_PROXY_PORT).await?
+        );
+        let client = ToxiproxyClient::from_container(&toxiproxy).await?;
+
+        ping(&db_url).await?;
+
+        client.add_toxic("db", &Toxic::latency(LATENCY)).await?;
+        let started = Instant::now();
+        ping(&db_url).await?;
+        assert!(started.elapsed() >= LATENCY);
+
+        client.set_enabled("db", false).await?;
+        assert!(ping(&db_url).await.is_err());
+
+        let proxies = client.proxies().await?;
+        assert_eq!(proxies.len(), 1);
+        assert!(!proxies[0].enabled);
+        assert_eq!(proxies[0].toxics, ["latency_downstream"]);
+
+        client.reset().await?;
+        ping(&db_url).await?;
+
+        Ok(())
+    }
+
+    /// Test that a stack's host and database links are proxied and reset
+    #[tokio::test]
+    async fn test_stack_links() -> Result<()> {
+        let stack = SupabaseStack::new()
+            .with_postgrest(PostgREST::default())
+            .with_toxiproxy(Toxiproxy::default())
+            .with_setup_sql("CREATE TABLE public.todos (id serial PRIMARY KEY);")
+            .start()
+            .await?;
+        let toxiproxy = stack.toxiproxy_client().expect("links are proxied");
+        let todos = format!("{}/todos", stack.rest_url().unwrap());
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        let get = || http.get(&todos).bearer_auth(stack.anon_key()).send();
+
+        let names: Vec<String> = toxiproxy
+            .proxies()
+            .await?
+            .into_iter()
+            .map(|proxy| proxy.name)
+            .collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&StackLink::Rest.to_string()));
+        assert!(names.contains(&StackLink::RestDb.to_string()));
+
+        get().await?.error_for_status()?;
+
+        // Slow host link
+        toxiproxy
+            .add_toxic(StackLink::Rest, &Toxic::latency(LATENCY))
+            .await?;
+        let started = Instant::now();
+        get().await?.error_for_status()?;
+        assert!(started.elapsed() >= LATENCY);
+
+        // Lost database link
+        toxiproxy.set_enabled(StackLink::RestDb, false).await?;
+        let failed = match get().await {
+            Ok(response) => !response.status().is_success(),
+            Err(_) => true,
+        };
+        assert!(failed, "PostgREST answered without its database");
+
+        stack.reset().await?;
+        assert!(toxiproxy.proxies().await?.iter().all(|proxy| proxy.enabled));
+        get().await?.error_for_status()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+//! Integration tests for container logs and bootstrap spans as tracing events
+//!
+//! These tests require Docker to be running and will start real containers.
+//! Run with: `cargo test --features stack,tracing --test tracing_integration`
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use supabase_testcontainers_modules::{forward_logs, Auth, SupabaseStack};
+use testcontainers::runners::AsyncRunner;
+use testcontainers::ImageExt;
+use testcontainers_modules::postgres::Postgres;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+/// Target of forwarded container log events
+const LOGS_TARGET: &str = "supabase_testcontainers_modules::logs";
+/// Time for the forwarding tasks to receive the latest lines
+const FORWARD_DELAY: Duration = Duration::from_secs(1);
+
+/// An event's level, target and fields
+#[derive(Debug, Clone)]
+struct CapturedEvent {
+    level: Level,
+    target: String,
+    fields: HashMap<String, String>,
+}
+
+/// Subscriber recording events and span names
+#[derive(Default, Clone)]
+struct Capture {
+    events: Arc<Mutex<Vec<CapturedEvent>>>,
+    spans: Arc<Mutex<Vec<String>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Capture {
+    /// Returns the container log events
+    fn log_events(&self) -> Vec<CapturedEvent> {
+        let events = self.events.lock().unwrap();
+        events
+            .iter()
+            .filter(|event| event.target == LOGS_TARGET)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Collects an event's fields as strings
+struct Fields<'a>(&'a mut HashMap<String, String>);
+
+impl Visit for Fields<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+}
+
+impl Subscriber for Capture {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        self.spans
+            .lock()
+            .unwrap()
+            .push(span.metadata().name().to_string());
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = HashMap::new();
+        event.record(&mut Fields(&mut fields));
+        self.events.lock().unwrap().push(CapturedEvent {
+            level: *event.metadata().level(),
+            target: event.metadata().target().to_string(),
+            fields,
+        });
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that a stack forwards its containers' logs and instruments its bootstrap
+    #[tokio::test]
+    async fn test_stack_logs_and_spans() -> Result<()> {
+        let capture = Capture::default();
+        let _default = tracing::subscriber::set_default(capture.clone());
+
+        let stack = SupabaseStack::new()
+            .with_auth(Auth::default())
+            .start()
+            .await?;
+        tokio::time::sleep(FORWARD_DELAY).await;
+
+        let auth_id = stack.auth().unwrap().id().to_string();
+        let events = capture.log_events();
+        let auth_events: Vec<_> = events
+            .iter()
+            .filter(|event| event.fields.get("service").map(String::as_str) == Some("auth"))
+            .collect();
+        assert!(!auth_events.is_empty(), "no auth events in {:?}", events);
+        assert!(auth_events
+            .iter()
+            .all(|event| event.fields.get("container_id") == Some(&auth_id)));
+        // GoTrue logs JSON; its message is extracted from the line
+        assert!(auth_events.iter().any(|event| {
+            let message = &event.fields["message"];
+            !message.starts_with('{')
+        }));
+        assert!(events
+            .iter()
+            .any(|event| event.fields.get("service").map(String::as_str) == Some("db")));
+
+        let spans = capture.spans.lock().unwrap().clone();
+        for name in ["start", "init_db_schema", "apply", "create"] {
+            assert!(
+                spans.iter().any(|span| span == name),
+                "no {} span in {:?}",
+                name,
+                spans
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Test that a single container's logs are forwarded until the handle is dropped
+    #[tokio::test]
+    async fn test_forward_logs() -> Result<()> {
+        let capture = Capture::default();
+        let _default = tracing::subscriber::set_default(capture.clone());
+
+        let postgres = Postgres::default().with_tag("15-alpine").start().await?;
+        let forwarder = forward_logs(&postgres, "db");
+        tokio::time::sleep(FORWARD_DELAY).await;
+
+        let events = capture.log_events();
+        assert!(events.iter().any(|event| {
+            event.level == Level::INFO
+                && event.fields["stream"] == "stderr"
+                && event.fields["message"].contains("database system is ready")
+        }));
+
+        drop(forwarder);
+        let forwarded = capture.log_events().len();
+        postgres
+            .exec(testcontainers::core::ExecCommand::new([
+                "psql",
+                "-U",
+                "postgres",
+                "-c",
+                "SELECT pg_terminate_backend(pg_backend_pid())",
+            ]))
+            .await?;
+        tokio::time::sleep(FORWARD_DELAY).await;
+        assert_eq!(capture.log_events().len(), forwarded);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+//! Integration tests for Vector log shipping into Analytics (Logflare)
+//!
+//! These tests start PostgreSQL, Analytics and PostgREST on a shared network, route
+//! PostgREST's container logs through Vector and query them back from Logflare.
+//!
+//! Note: Vector reads container logs through the Docker socket, which must be
+//! available at `/var/run/docker.sock` on the host.
+//!
+//! Run with: `cargo test --features vector,analytics,postgrest,const --test vector_integration`
+
+use anyhow::Result;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use supabase_testcontainers_modules::{
+    Analytics, AnalyticsClient, LogSource, PostgREST, QueryLanguage, Vector, ANALYTICS_PORT,
+    ANALYTICS_QUERY_TIMEOUT, VECTOR_API_PORT,
+};
+use testcontainers::runners::AsyncRunner;
+use testcontainers::ImageExt;
+use testcontainers_modules::postgres::Postgres;
+
+/// PostgreSQL port constant
+const POSTGRES_PORT: u16 = 5432;
+/// Network name for container-to-container communication
+const TEST_NETWORK: &str = "vector-test-network";
+/// PostgreSQL container alias on the shared network
+const POSTGRES_ALIAS: &str = "vector-db";
+/// Analytics container alias on the shared network
+const ANALYTICS_ALIAS: &str = "vector-analytics";
+/// PostgREST container alias on the shared network
+const POSTGREST_ALIAS: &str = "vector-rest";
+
+/// Public access token for ingestion
+const PUBLIC_ACCESS_TOKEN: &str = "test-public-access-token";
+/// Private access token for management operations
+const PRIVATE_ACCESS_TOKEN: &str = "test-private-access-token";
+/// Base64 encryption key (32 bytes = 256 bits, base64 encoded)
+const ENCRYPTION_KEY: &str = "dGhpcy1pcy1hLTMyLWJ5dGUtZW5jcnlwdGlvbi1rZXk=";
+
+/// Atomic counter for generating unique test IDs
+static TEST_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Generates a unique test ID combining timestamp and atomic counter
+fn unique_test_id() -> String {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let counter = TEST_COUNTER.fetch_add(1, Ordering::SeqCst);
+    format!("{}-{}", timestamp, counter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testcontainers::ContainerAsync;
+
+    /// Helper struct keeping the log shipping stack alive
+    struct VectorContext {
+        #[allow(dead_code)]
+        postgres: ContainerAsync<Postgres>,
+        #[allow(dead_code)]
+        analytics: ContainerAsync<Analytics>,
+        #[allow(dead_code)]
+        postgrest: ContainerAsync<PostgREST>,
+        vector: ContainerAsync<Vector>,
+        client: AnalyticsClient,
+    }
+
+    /// Starts PostgreSQL, Analytics, PostgREST and Vector shipping PostgREST's logs
+    async fn setup_vector() -> Result<VectorContext> {
+        let test_id = unique_test_id();
+        let network_name = format!("{}-{}", TEST_NETWORK, test_id);
+        let postgres_name = format!("{}-{}", POSTGRES_ALIAS, test_id);
+        let analytics_name = format!("{}-{}", ANALYTICS_ALIAS, test_id);
+        let postgrest_name = format!("{}-{}", POSTGREST_ALIAS, test_id);
+
+        let postgres = Postgres::default()
+            .with_tag("15-alpine")
+            .with_network(&network_name)
+            .with_container_name(&postgres_name)
+            .start()
+            .await?;
+
+        let analytics = Analytics::default()
+            .with_postgres_backend_url(format!(
+                "postgresql://postgres:postgres@{}:{}/postgres",
+                postgres_name, POSTGRES_PORT
+            ))
+            .with_db_hostname(&postgres_name)
+            .with_db_port(POSTGRES_PORT)
+            .with_db_username("postgres")
+            .with_db_password("postgres")
+            .with_db_database("postgres")
+            .with_public_access_token(PUBLIC_ACCESS_TOKEN)
+            .with_private_access_token(PRIVATE_ACCESS_TOKEN)
+            .with_encryption_key(ENCRYPTION_KEY)
+            .with_startup_timeout(Duration::from_secs(120))
+            .with_network(&network_name)
+            .with_container_name(&analytics_name)
+            .start()
+            .await?;
+
+        let client = AnalyticsClient::from_container(&analytics).await?;
+
+        // Supabase mode seeds the default sources; create it if this version does not
+        let source = LogSource::Rest.source_name();
+        if client.source(source).await?.is_none() {
+            client.create_source(source).await?;
+        }
+
+        let postgrest = PostgREST::default()
+            .with_postgres_connection(&format!(
+                "postgres://postgres:postgres@{}:{}/postgres",
+                postgres_name, POSTGRES_PORT
+            ))
+            .with_db_anon_role("postgres")
+            .with_log_level("info")
+            .with_network(&network_name)
+            .with_container_name(&postgrest_name)
+            .start()
+            .await?;
+
+        let vector = Vector::default()
+            .with_analytics_url(format!("http://{}:{}", analytics_name, ANALYTICS_PORT))
+            .with_api_key(PUBLIC_ACCESS_TOKEN)
+            .with_container(&postgrest_name, LogSource::Rest)
+            .with_network(&network_name)
+            .start()
+            .await?;
+
+        Ok(VectorContext {
+            postgres,
+            analytics,
+            postgrest,
+            vector,
+            client,
+        })
+    }
+
+    /// Test that the Vector API reports healthy
+    #[tokio::test]
+    async fn test_vector_api_is_healthy() -> Result<()> {
+        let ctx = setup_vector().await?;
+
+        let port = ctx.vector.get_host_port_ipv4(VECTOR_API_PORT).await?;
+        let response = reqwest::get(format!("http://localhost:{}/health", port)).await?;
+        assert!(
+            response.status().is_success(),
+            "Expected success, got: {}",
+            response.status()
+        );
+
+        Ok(())
+    }
+
+    /// Test that PostgREST's container logs arrive in its default source
+    #[tokio::test]
+    async fn test_postgrest_logs_reach_analytics() -> Result<()> {
+        let ctx = setup_vector().await?;
+
+        ctx.client
+            .create_endpoint(
+                "test.rest.logs",
+                "select event_message from `postgREST.logs.prod`",
+                QueryLanguage::BqSql,
+            )
+            .await?;
+        let rows = ctx
+            .client
+            .wait_for_results("test.rest.logs", &[], 1, ANALYTICS_QUERY_TIMEOUT)
+            .await?;
+
+        assert!(
+            rows.iter()
+                .any(|row| row["event_message"].as_str().is_some_and(|m| !m.is_empty())),
+            "Expected PostgREST log lines, got: {:?}",
+            rows
+        );
+
+        Ok(())
+    }
+}